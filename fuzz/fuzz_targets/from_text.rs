@@ -0,0 +1,23 @@
+//! Parsing of a text report, as read by `--from-text` and the text sources
+//! of `diff`
+
+#![no_main]
+#![allow(dead_code)]
+
+#[path = "../../src/decoded.rs"]
+mod decoded;
+#[path = "../../src/default_out.rs"]
+mod default_out;
+#[path = "../../src/dmifn.rs"]
+mod dmifn;
+#[path = "../../src/output.rs"]
+mod output;
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let text = String::from_utf8_lossy(data);
+    for decoded_struct in decoded::parse_text(&text) {
+        let _ = decoded_struct.to_text_lines();
+    }
+});
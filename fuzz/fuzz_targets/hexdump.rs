@@ -0,0 +1,22 @@
+//! Parsing of a `--from-hexdump` file, as printed by `-u`
+
+#![no_main]
+#![allow(dead_code)]
+
+#[path = "../../src/build.rs"]
+mod build;
+#[path = "../../src/dump.rs"]
+mod dump;
+
+use libfuzzer_sys::fuzz_target;
+use smbioslib::*;
+
+fuzz_target!(|data: &[u8]| {
+    let text = String::from_utf8_lossy(data);
+    if let Ok((table, version)) = dump::parse_hexdump(&text) {
+        let smbios_data = SMBiosData::from_vec_and_version(table, version);
+        for undefined_struct in smbios_data.iter() {
+            let _ = undefined_struct.defined_struct();
+        }
+    }
+});
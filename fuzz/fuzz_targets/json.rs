@@ -0,0 +1,17 @@
+//! JSON rendering of a raw table, as with -j and --json-pretty
+
+#![no_main]
+#![allow(dead_code)]
+
+#[path = "../../src/build.rs"]
+mod build;
+#[path = "../../src/json_out.rs"]
+mod json_out;
+
+use libfuzzer_sys::fuzz_target;
+use smbioslib::*;
+
+fuzz_target!(|data: &[u8]| {
+    let smbios_data = SMBiosData::from_vec_and_version(data.to_vec(), None);
+    let _ = serde_json::to_string(&json_out::JsonTable(&smbios_data)).unwrap();
+});
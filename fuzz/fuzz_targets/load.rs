@@ -0,0 +1,24 @@
+//! Loading of a `--from-dump` file: a raw table or a Windows
+//! GetSystemFirmwareTable buffer, then the structures and their strings
+
+#![no_main]
+#![allow(dead_code)]
+
+#[path = "../../src/build.rs"]
+mod build;
+#[path = "../../src/dump.rs"]
+mod dump;
+
+use libfuzzer_sys::fuzz_target;
+use smbioslib::*;
+
+fuzz_target!(|data: &[u8]| {
+    let (table, version) = dump::split_dump(data.to_vec());
+    let smbios_data = SMBiosData::from_vec_and_version(table, version);
+    for undefined_struct in smbios_data.iter() {
+        let _ = undefined_struct.defined_struct();
+        for string in undefined_struct.strings.iter() {
+            let _ = String::from_utf8_lossy(string);
+        }
+    }
+});
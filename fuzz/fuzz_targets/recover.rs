@@ -0,0 +1,25 @@
+//! Recovery of a damaged table, as with `--recover`
+//!
+//! The structures kept must form a table that is recovered whole.
+
+#![no_main]
+#![allow(dead_code)]
+
+#[path = "../../src/build.rs"]
+mod build;
+#[path = "../../src/json_out.rs"]
+mod json_out;
+#[path = "../../src/recover.rs"]
+mod recover;
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let recovered = recover::recover_table(data, Some(data.len()));
+    for corruption in recovered.corruptions.iter() {
+        let _ = corruption.to_string();
+    }
+    let again = recover::recover_table(&recovered.table, None);
+    assert_eq!(again.table, recovered.table);
+    assert!(again.corruptions.is_empty());
+});
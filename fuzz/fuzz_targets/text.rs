@@ -0,0 +1,34 @@
+//! Text rendering of every structure type, as in the default output and in
+//! the decoded fields used by the other output formats
+//!
+//! The input is a raw table, decoded as if no version were known and as
+//! SMBIOS 2.0 and 3.7 to take the version-dependent branches.
+
+#![no_main]
+#![allow(dead_code)]
+
+#[path = "../../src/decoded.rs"]
+mod decoded;
+#[path = "../../src/default_out.rs"]
+mod default_out;
+#[path = "../../src/dmifn.rs"]
+mod dmifn;
+#[path = "../../src/output.rs"]
+mod output;
+
+use libfuzzer_sys::fuzz_target;
+use smbioslib::*;
+
+fuzz_target!(|data: &[u8]| {
+    for version in [
+        None,
+        Some(SMBiosVersion::new(2, 0, 0)),
+        Some(SMBiosVersion::new(3, 7, 0)),
+    ]
+    .iter()
+    {
+        let smbios_data = SMBiosData::from_vec_and_version(data.to_vec(), *version);
+        let _ = output::capture(|| default_out::default_dump(&smbios_data, false));
+        let _ = decoded::decode_table(&smbios_data);
+    }
+});
@@ -0,0 +1,154 @@
+//! Batch decoding of a directory of tables
+//!
+//! Every entry of the directory is one machine: a binary dump, a hex dump,
+//! a text report or a sysfs-style directory (see [`crate::source`]).
+//! Entries are loaded and decoded in parallel.  An entry that cannot be
+//! loaded is reported as a [`BatchError`] and does not stop the run.
+
+use crate::{decoded::DecodedStruct, source::decoded_load_from_source, Opt};
+use serde::Serialize;
+use std::{
+    fs,
+    io::Error,
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+/// The decoded table of one machine
+#[derive(Debug, Serialize)]
+pub struct Machine {
+    /// File name within the directory
+    pub file: String,
+    /// System UUID as decoded from the System Information structure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<String>,
+    /// Structures selected with -t and -H
+    pub structures: Vec<DecodedStruct>,
+}
+
+/// An entry that could not be loaded
+#[derive(Debug, Serialize)]
+pub struct BatchError {
+    /// File name within the directory
+    pub file: String,
+    /// Why it could not be loaded
+    pub error: String,
+}
+
+/// Loads every entry of `dir` using up to `jobs` threads
+///
+/// Machines and errors are returned in file name order.
+pub fn load_directory(
+    dir: &Path,
+    opt: &Opt,
+    jobs: usize,
+) -> Result<(Vec<Machine>, Vec<BatchError>), Error> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    paths.sort();
+
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<Result<Machine, BatchError>>>> =
+        Mutex::new(paths.iter().map(|_| None).collect());
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, paths.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let path = match paths.get(index) {
+                    Some(path) => path,
+                    None => break,
+                };
+                let result = load_machine(path, opt);
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+
+    let mut machines = Vec::new();
+    let mut errors = Vec::new();
+    for result in results.into_inner().unwrap().into_iter().flatten() {
+        match result {
+            Ok(machine) => machines.push(machine),
+            Err(error) => errors.push(error),
+        }
+    }
+    Ok((machines, errors))
+}
+
+fn load_machine(path: &Path, opt: &Opt) -> Result<Machine, BatchError> {
+    let file = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    let loaded = catch_unwind(AssertUnwindSafe(|| decoded_load_from_source(path, opt)));
+    let structs = match loaded {
+        Ok(Ok(structs)) if structs.is_empty() => {
+            return Err(BatchError {
+                file,
+                error: "no structures found".to_string(),
+            })
+        }
+        Ok(Ok(structs)) => structs,
+        Ok(Err(error)) => {
+            return Err(BatchError {
+                file,
+                error: error.to_string(),
+            })
+        }
+        Err(_) => {
+            return Err(BatchError {
+                file,
+                error: "the decoder panicked".to_string(),
+            })
+        }
+    };
+    let uuid = structs
+        .iter()
+        .filter(|decoded| decoded.struct_type == 1)
+        .find_map(|decoded| decoded.field("UUID"))
+        .and_then(|field| field.value.clone());
+    Ok(Machine {
+        file,
+        uuid,
+        structures: opt.select_structs(structs),
+    })
+}
+
+#[test]
+fn test_load_directory() {
+    use structopt::StructOpt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut table = vec![
+        // System Information with a UUID
+        0x01, 0x1B, 0x01, 0x00, 0x01, 0x02, 0x00, 0x00, 0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66,
+        0x77, 0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF, 0x06, 0x00, 0x00,
+    ];
+    table.extend_from_slice(b"ACME Corp.\0Road Runner\0\0");
+    table.extend_from_slice(&[0x7F, 0x04, 0x02, 0x00, 0x00, 0x00]);
+    fs::write(dir.path().join("b.bin"), &table).unwrap();
+    fs::write(dir.path().join("a.bin"), &table).unwrap();
+    fs::write(dir.path().join("c.bin"), b"garbage").unwrap();
+
+    let opt = Opt::from_iter(&["dmidecode", "--type", "127"]);
+    let (machines, errors) = load_directory(dir.path(), &opt, 2).unwrap();
+    assert_eq!(
+        machines.iter().map(|m| m.file.as_str()).collect::<Vec<_>>(),
+        vec!["a.bin", "b.bin"]
+    );
+    assert_eq!(
+        machines[0].uuid.as_deref(),
+        Some("33221100-5544-7766-8899-aabbccddeeff")
+    );
+    assert_eq!(machines[0].structures.len(), 1);
+    assert_eq!(machines[0].structures[0].struct_type, 127);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].file, "c.bin");
+}
@@ -0,0 +1,595 @@
+//! Building binary tables from a JSON or YAML description
+//!
+//! A description lists the structures of the table in order.  The fields of
+//! a structure follow its 4-byte header; string fields are added to the
+//! string set and replaced by their string number.  Structures without a
+//! handle are given the lowest free one, and a structure with an `id` can be
+//! referred to by a `handle` field of another structure:
+//!
+//! ```json
+//! {
+//!   "version": { "major": 3, "minor": 2, "revision": 0 },
+//!   "structures": [
+//!     { "type": 16, "id": "array", "fields": [ { "byte": 3 }, ... ] },
+//!     { "type": 17, "fields": [ { "handle": "array" }, { "word": 65534 }, ... ] },
+//!     { "type": 11, "fields": [ { "byte": 1 } ], "strings": [ "OEM string" ] }
+//!   ]
+//! }
+//! ```
+//!
+//! Field kinds are `byte`, `word`, `dword`, `qword` (little-endian numbers),
+//! `bytes` (a hex string), `string` (an empty string is string number 0)
+//! and `handle`.  A type 127 (End-of-Table) structure is appended unless the
+//! description ends with one.  An entry of `strings` is either text or, for
+//! a string that is not UTF-8, its bytes as `{ "hex": "E9 74 E9" }`; string
+//! sets cannot hold an empty string.
+//!
+//! The `-j` output is not a description: it holds decoded values (names of
+//! enumerated values, sizes, flags) rather than the bytes they were decoded
+//! from, and does not say which string numbers and reserved bytes the
+//! structures hold.  `build --capture` describes an existing table instead.
+
+use serde::{Deserialize, Serialize};
+use smbioslib::*;
+use std::{
+    collections::{BTreeSet, HashMap},
+    convert::TryFrom,
+    fmt::Write,
+    io::{Error, ErrorKind},
+    path::Path,
+    str::FromStr,
+};
+
+/// SMBIOS version of a description
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionSpec {
+    pub major: u8,
+    pub minor: u8,
+    #[serde(default)]
+    pub revision: u8,
+}
+
+/// Description of a table
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TableSpec {
+    /// SMBIOS version written to the entry point (default: 3.0.0)
+    #[serde(default = "default_version")]
+    pub version: VersionSpec,
+    /// Structures in table order
+    pub structures: Vec<StructSpec>,
+}
+
+/// Description of a structure
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StructSpec {
+    /// DMI type
+    #[serde(rename = "type")]
+    pub struct_type: u8,
+    /// Handle, allocated when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub handle: Option<u16>,
+    /// Name that `handle` fields of other structures refer to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Fields following the header
+    #[serde(default)]
+    pub fields: Vec<FieldSpec>,
+    /// Strings placed in the string set before those of string fields
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub strings: Vec<StringSpec>,
+}
+
+/// Description of a string of the string set
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum StringSpec {
+    /// UTF-8 text
+    Text(String),
+    /// Raw bytes as hexadecimal, for strings that are not UTF-8
+    Hex { hex: String },
+}
+
+impl StringSpec {
+    /// Describes `bytes` as text when they are UTF-8, and as hex otherwise
+    fn from_bytes(bytes: &[u8]) -> Self {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => StringSpec::Text(text.to_string()),
+            Err(_) => StringSpec::Hex {
+                hex: hex_string(bytes),
+            },
+        }
+    }
+
+    /// Bytes of the string, which must be neither empty nor hold a NUL
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let bytes = match self {
+            StringSpec::Text(text) => text.as_bytes().to_vec(),
+            StringSpec::Hex { hex } => parse_hex(hex)?,
+        };
+        if bytes.is_empty() {
+            return Err(invalid_data(format!(
+                "Empty string {:?}, which would end the string set",
+                self
+            )));
+        }
+        if bytes.contains(&0) {
+            return Err(invalid_data(format!("String {:?} contains NUL", self)));
+        }
+        Ok(bytes)
+    }
+}
+
+/// Description of a field
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldSpec {
+    Byte(u8),
+    Word(u16),
+    Dword(u32),
+    Qword(u64),
+    /// Raw bytes as hexadecimal, e.g. "00 11 22"
+    Bytes(String),
+    /// A string, stored as its string number
+    String(String),
+    /// The handle of the structure with this `id`
+    Handle(String),
+}
+
+/// Entry point structure format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryPointFormat {
+    /// SMBIOS 2.1 32-bit entry point (_SM_)
+    Bits32,
+    /// SMBIOS 3.0 64-bit entry point (_SM3_)
+    Bits64,
+}
+
+impl FromStr for EntryPointFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "32" => Ok(EntryPointFormat::Bits32),
+            "64" => Ok(EntryPointFormat::Bits64),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid entry point format {}, expected 32 or 64", s),
+            )),
+        }
+    }
+}
+
+/// A table built from a description
+pub struct BuiltTable {
+    /// The structure table
+    pub table: Vec<u8>,
+    version: VersionSpec,
+    structure_count: usize,
+    maximum_structure_size: usize,
+}
+
+fn default_version() -> VersionSpec {
+    VersionSpec {
+        major: 3,
+        minor: 0,
+        revision: 0,
+    }
+}
+
+fn invalid_data(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+/// Parses a description, in YAML when `yaml` is set and in JSON otherwise
+pub fn parse_spec(text: &str, yaml: bool) -> Result<TableSpec, Error> {
+    let value: serde_json::Value = match yaml {
+        true => serde_yaml::from_str(text).map_err(|e| invalid_data(e.to_string()))?,
+        false => serde_json::from_str(text)?,
+    };
+    if value.get("table").is_some() && value.get("structures").is_none() {
+        return Err(invalid_data(
+            "This is the output of -j, which holds decoded values rather than the bytes \
+             of the table and cannot be built; describe the table with build --capture"
+                .to_string(),
+        ));
+    }
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Reads a description file, in YAML if its name ends in .yaml or .yml
+pub fn read_spec(path: &Path) -> Result<TableSpec, Error> {
+    let yaml = matches!(
+        path.extension().and_then(|extension| extension.to_str()),
+        Some("yaml") | Some("yml")
+    );
+    parse_spec(&std::fs::read_to_string(path)?, yaml)
+}
+
+/// Builds the table described by `spec`
+pub fn build_table(spec: &TableSpec) -> Result<BuiltTable, Error> {
+    let mut structures: Vec<&StructSpec> = spec.structures.iter().collect();
+    let terminator = StructSpec {
+        struct_type: 127,
+        handle: None,
+        id: None,
+        fields: Vec::new(),
+        strings: Vec::new(),
+    };
+    if structures.last().map(|last| last.struct_type) != Some(127) {
+        structures.push(&terminator);
+    }
+
+    // Explicit handles first, then the lowest free ones
+    let mut used = BTreeSet::new();
+    for structure in structures.iter() {
+        if let Some(handle) = structure.handle {
+            if !used.insert(handle) {
+                return Err(invalid_data(format!("Duplicate handle {:#06X}", handle)));
+            }
+        }
+    }
+    let mut next_handle = 0u16;
+    let mut handles = Vec::with_capacity(structures.len());
+    for structure in structures.iter() {
+        let handle = match structure.handle {
+            Some(handle) => handle,
+            None => {
+                while used.contains(&next_handle) {
+                    next_handle = next_handle
+                        .checked_add(1)
+                        .ok_or_else(|| invalid_data("Out of handles".to_string()))?;
+                }
+                used.insert(next_handle);
+                next_handle
+            }
+        };
+        handles.push(handle);
+    }
+
+    let mut ids = HashMap::new();
+    for (structure, handle) in structures.iter().zip(handles.iter()) {
+        if let Some(id) = structure.id.as_ref() {
+            if ids.insert(id.as_str(), *handle).is_some() {
+                return Err(invalid_data(format!("Duplicate structure id {}", id)));
+            }
+        }
+    }
+
+    let mut built = BuiltTable {
+        table: Vec::new(),
+        version: spec.version,
+        structure_count: structures.len(),
+        maximum_structure_size: 0,
+    };
+    for (structure, handle) in structures.iter().zip(handles) {
+        let start = built.table.len();
+        build_struct(&mut built.table, structure, handle, &ids)?;
+        built.maximum_structure_size = built.maximum_structure_size.max(built.table.len() - start);
+    }
+    Ok(built)
+}
+
+fn build_struct(
+    table: &mut Vec<u8>,
+    structure: &StructSpec,
+    handle: u16,
+    ids: &HashMap<&str, u16>,
+) -> Result<(), Error> {
+    let mut fields = vec![structure.struct_type, 0];
+    fields.extend_from_slice(&handle.to_le_bytes());
+    let mut strings = structure
+        .strings
+        .iter()
+        .map(StringSpec::to_bytes)
+        .collect::<Result<Vec<Vec<u8>>, Error>>()?;
+
+    for field in structure.fields.iter() {
+        match field {
+            FieldSpec::Byte(value) => fields.push(*value),
+            FieldSpec::Word(value) => fields.extend_from_slice(&value.to_le_bytes()),
+            FieldSpec::Dword(value) => fields.extend_from_slice(&value.to_le_bytes()),
+            FieldSpec::Qword(value) => fields.extend_from_slice(&value.to_le_bytes()),
+            FieldSpec::Bytes(hex) => fields.extend(parse_hex(hex)?),
+            FieldSpec::String(string) if string.is_empty() => fields.push(0),
+            FieldSpec::String(string) => {
+                if string.contains('\0') {
+                    return Err(invalid_data(format!("String {:?} contains NUL", string)));
+                }
+                let position = match strings.iter().position(|s| s == string.as_bytes()) {
+                    Some(position) => position,
+                    None => {
+                        strings.push(string.as_bytes().to_vec());
+                        strings.len() - 1
+                    }
+                };
+                let number = u8::try_from(position + 1).map_err(|_| {
+                    invalid_data(format!(
+                        "Structure type {} has more than 255 strings",
+                        structure.struct_type
+                    ))
+                })?;
+                fields.push(number);
+            }
+            FieldSpec::Handle(id) => {
+                let handle = ids
+                    .get(id.as_str())
+                    .ok_or_else(|| invalid_data(format!("No structure with id {}", id)))?;
+                fields.extend_from_slice(&handle.to_le_bytes());
+            }
+        }
+    }
+
+    fields[1] = u8::try_from(fields.len()).map_err(|_| {
+        invalid_data(format!(
+            "Structure type {} is {} bytes long, the maximum is 255",
+            structure.struct_type,
+            fields.len()
+        ))
+    })?;
+    push_struct(table, &fields, &strings);
+    Ok(())
+}
+
+/// Formats bytes as hexadecimal separated by spaces, e.g. "00 11 22"
+fn hex_string(bytes: &[u8]) -> String {
+    let mut hex = String::new();
+    for (index, byte) in bytes.iter().enumerate() {
+        if index > 0 {
+            hex.push(' ');
+        }
+        write!(&mut hex, "{:02X}", byte).unwrap();
+    }
+    hex
+}
+
+/// Parses hexadecimal bytes, ignoring whitespace
+fn parse_hex(hex: &str) -> Result<Vec<u8>, Error> {
+    let digits: Vec<char> = hex.chars().filter(|c| !c.is_whitespace()).collect();
+    let pairs = digits.chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return Err(invalid_data(format!(
+            "Odd number of hex digits in {:?}",
+            hex
+        )));
+    }
+    pairs
+        .map(|pair| {
+            let byte: String = pair.iter().collect();
+            u8::from_str_radix(&byte, 16)
+                .map_err(|_| invalid_data(format!("Invalid hex byte {:?}", byte)))
+        })
+        .collect()
+}
+
+impl BuiltTable {
+    /// Builds an entry point for the table, located at address 0
+    ///
+    /// Without a format, the 64-bit entry point is used for SMBIOS 3.0 and
+    /// later, and the 32-bit one for earlier versions.
+    pub fn entry_point(&self, format: Option<EntryPointFormat>) -> Result<Vec<u8>, Error> {
+        let format = format.unwrap_or(match self.version.major >= 3 {
+            true => EntryPointFormat::Bits64,
+            false => EntryPointFormat::Bits32,
+        });
+        match format {
+            EntryPointFormat::Bits32 => self.entry_point_32(),
+            EntryPointFormat::Bits64 => Ok(self.entry_point_64()),
+        }
+    }
+
+    fn entry_point_32(&self) -> Result<Vec<u8>, Error> {
+        let table_length = u16::try_from(self.table.len()).map_err(|_| {
+            invalid_data(format!(
+                "The table is {} bytes long, too long for a 32-bit entry point",
+                self.table.len()
+            ))
+        })?;
+        let structure_count = u16::try_from(self.structure_count)
+            .map_err(|_| invalid_data("Too many structures".to_string()))?;
+        let maximum_structure_size = u16::try_from(self.maximum_structure_size)
+            .map_err(|_| invalid_data("Structure too large".to_string()))?;
+
+        let mut entry_point = Vec::with_capacity(0x1F);
+        entry_point.extend_from_slice(b"_SM_");
+        entry_point.push(0); // checksum
+        entry_point.push(0x1F);
+        entry_point.push(self.version.major);
+        entry_point.push(self.version.minor);
+        entry_point.extend_from_slice(&maximum_structure_size.to_le_bytes());
+        entry_point.push(0); // entry point revision
+        entry_point.extend_from_slice(&[0; 5]); // formatted area
+        entry_point.extend_from_slice(b"_DMI_");
+        entry_point.push(0); // intermediate checksum
+        entry_point.extend_from_slice(&table_length.to_le_bytes());
+        entry_point.extend_from_slice(&0u32.to_le_bytes()); // table address
+        entry_point.extend_from_slice(&structure_count.to_le_bytes());
+        entry_point.push((self.version.major << 4) | (self.version.minor & 0x0F));
+
+        entry_point[0x15] = checksum(&entry_point[0x10..]);
+        entry_point[0x04] = checksum(&entry_point);
+        Ok(entry_point)
+    }
+
+    fn entry_point_64(&self) -> Vec<u8> {
+        let mut entry_point = Vec::with_capacity(0x18);
+        entry_point.extend_from_slice(b"_SM3_");
+        entry_point.push(0); // checksum
+        entry_point.push(0x18);
+        entry_point.push(self.version.major);
+        entry_point.push(self.version.minor);
+        entry_point.push(self.version.revision);
+        entry_point.push(1); // entry point revision
+        entry_point.push(0); // reserved
+        entry_point.extend_from_slice(&(self.table.len() as u32).to_le_bytes());
+        entry_point.extend_from_slice(&0u64.to_le_bytes()); // table address
+
+        entry_point[0x05] = checksum(&entry_point);
+        entry_point
+    }
+}
+
+/// Value that makes `data` sum to zero
+fn checksum(data: &[u8]) -> u8 {
+    data.iter()
+        .fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+        .wrapping_neg()
+}
+
+/// Describes an existing table, so that it can be edited and rebuilt
+///
+/// Each structure is described with its handle, its fields as raw bytes and
+/// its strings, in hex when they are not UTF-8 so that they round-trip.
+pub fn capture_spec(data: &SMBiosData) -> TableSpec {
+    let version = data
+        .version
+        .map_or_else(default_version, |version| VersionSpec {
+            major: version.major,
+            minor: version.minor,
+            revision: version.revision,
+        });
+    let structures = data
+        .iter()
+        .map(|undefined_struct| {
+            let hex = hex_string(undefined_struct.fields.get(4..).unwrap_or_default());
+            StructSpec {
+                struct_type: undefined_struct.header.struct_type(),
+                handle: Some(*undefined_struct.header.handle()),
+                id: None,
+                fields: match hex.is_empty() {
+                    true => Vec::new(),
+                    false => vec![FieldSpec::Bytes(hex)],
+                },
+                strings: undefined_struct
+                    .strings
+                    .iter()
+                    .map(|string| StringSpec::from_bytes(string))
+                    .collect(),
+            }
+        })
+        .collect();
+    TableSpec {
+        version,
+        structures,
+    }
+}
+
+/// Returns the raw table of `data`, as it would be read from the firmware
+pub fn table_bytes(data: &SMBiosData) -> Vec<u8> {
+    let mut table = Vec::new();
+    for undefined_struct in data.iter() {
+        let strings: Vec<Vec<u8>> = undefined_struct.strings.iter().cloned().collect();
+        push_struct(&mut table, &undefined_struct.fields, &strings);
+    }
+    table
+}
+
+/// Appends a structure (header and fields, then strings) to `table`
+pub fn push_struct(table: &mut Vec<u8>, fields: &[u8], strings: &[Vec<u8>]) {
+    table.extend_from_slice(fields);
+    for string in strings.iter() {
+        table.extend_from_slice(string);
+        table.push(0);
+    }
+    // The string area ends with a double zero, even when empty
+    if strings.is_empty() {
+        table.push(0);
+    }
+    table.push(0);
+}
+
+#[test]
+fn test_build_table() {
+    let spec: TableSpec = serde_json::from_str(
+        r#"{
+            "version": { "major": 2, "minor": 8 },
+            "structures": [
+                { "type": 16, "handle": 0, "id": "array", "fields": [
+                    { "byte": 3 }, { "byte": 3 }, { "byte": 3 }, { "dword": 8388608 },
+                    { "word": 65534 }, { "word": 1 } ] },
+                { "type": 17, "fields": [
+                    { "handle": "array" }, { "word": 65534 }, { "word": 64 }, { "word": 64 },
+                    { "word": 8192 }, { "byte": 9 }, { "byte": 0 }, { "string": "DIMM 0" },
+                    { "string": "BANK 0" }, { "byte": 26 }, { "word": 128 } ] },
+                { "type": 11, "fields": [ { "byte": 1 } ], "strings": [ "OEM" ] }
+            ]
+        }"#,
+    )
+    .unwrap();
+    let built = build_table(&spec).unwrap();
+    let data = SMBiosData::from_vec_and_version(built.table.clone(), None);
+
+    let handles: Vec<u16> = data.iter().map(|s| *s.header.handle()).collect();
+    assert_eq!(handles, vec![0, 1, 2, 3]);
+    assert_eq!(data.iter().last().unwrap().header.struct_type(), 127);
+
+    let device = data.first::<SMBiosMemoryDevice<'_>>().unwrap();
+    assert_eq!(*device.physical_memory_array_handle().unwrap(), 0);
+    assert_eq!(device.device_locator().to_utf8_lossy().unwrap(), "DIMM 0");
+    assert_eq!(device.bank_locator().to_utf8_lossy().unwrap(), "BANK 0");
+
+    let entry_point = SMBiosEntryPoint32::try_from(built.entry_point(None).unwrap()).unwrap();
+    assert_eq!(entry_point.major_version(), 2);
+    assert_eq!(entry_point.minor_version(), 8);
+    assert_eq!(entry_point.number_of_smbios_structures(), 4);
+    assert_eq!(
+        entry_point.structure_table_length() as usize,
+        built.table.len()
+    );
+    let entry_point = built.entry_point(Some(EntryPointFormat::Bits64)).unwrap();
+    assert!(SMBiosEntryPoint64::try_from(entry_point).is_ok());
+
+    // Capturing and rebuilding gives the same table back
+    let rebuilt = build_table(&capture_spec(&data)).unwrap();
+    assert_eq!(rebuilt.table, built.table);
+
+    // Strings that are not UTF-8 are captured in hex and come back intact
+    let mut table = Vec::new();
+    push_struct(
+        &mut table,
+        &[11, 5, 0x00, 0x00, 2],
+        &[b"OEM".to_vec(), b"\xE9t\xE9".to_vec()],
+    );
+    let data = SMBiosData::from_vec_and_version(table, None);
+    let spec = capture_spec(&data);
+    let json = serde_json::to_string(&spec.structures[0].strings).unwrap();
+    assert_eq!(json, r#"["OEM",{"hex":"E9 74 E9"}]"#);
+    let rebuilt = build_table(&spec).unwrap();
+    assert!(rebuilt
+        .table
+        .starts_with(&data.iter().next().unwrap().fields));
+    assert_eq!(
+        SMBiosData::from_vec_and_version(rebuilt.table, None)
+            .iter()
+            .next()
+            .unwrap()
+            .strings
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>(),
+        vec![b"OEM".to_vec(), b"\xE9t\xE9".to_vec()]
+    );
+
+    // An empty string would end the string set early
+    for strings in [r#"[ "OEM", "" ]"#, r#"[ { "hex": "" } ]"#].iter() {
+        let json = format!(
+            r#"{{ "structures": [ {{ "type": 11, "strings": {} }} ] }}"#,
+            strings
+        );
+        let error = build_table(&parse_spec(&json, false).unwrap())
+            .err()
+            .unwrap();
+        assert!(error.to_string().starts_with("Empty string"), "{}", error);
+    }
+
+    // YAML describes the same tables
+    let json = r#"{ "structures": [ { "type": 11, "fields": [ { "string": "OEM" } ] } ] }"#;
+    let yaml = "structures:\n  - type: 11\n    fields:\n      - string: OEM\n";
+    assert_eq!(
+        build_table(&parse_spec(yaml, true).unwrap()).unwrap().table,
+        build_table(&parse_spec(json, false).unwrap())
+            .unwrap()
+            .table
+    );
+    let error = parse_spec(r#"{ "version": null, "table": [] }"#, false).unwrap_err();
+    assert!(error.to_string().contains("build --capture"));
+}
@@ -0,0 +1,742 @@
+//! CSV and TSV export of decoded structures
+//!
+//! Each structure type becomes a table: one row per structure, a "Handle"
+//! column followed by one column per decoded field, headed by dmidecode's
+//! field names.  The columns of a type are the fields the decoder can print
+//! for it, in the order it prints them, whether or not the table holds
+//! them, so that the header of a type is the same on every machine and for
+//! every SMBIOS version; a structure without a field leaves its cell empty.
+//! Fields the list does not name (numbered fields such as the OEM strings,
+//! repeated records, OEM-specific types) follow, in decoder order.  Fields
+//! that list their content on separate lines (e.g. "Characteristics") are
+//! joined into one cell.
+
+use crate::decoded::{DecodedField, DecodedStruct};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+};
+
+/// Joins the lines of list fields
+pub const LIST_SEPARATOR: &str = "; ";
+
+/// Field separated text format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delimited {
+    /// Comma separated values (RFC 4180)
+    Csv,
+    /// Tab separated values
+    Tsv,
+}
+
+impl Delimited {
+    /// File name extension
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Delimited::Csv => "csv",
+            Delimited::Tsv => "tsv",
+        }
+    }
+
+    fn separator(&self) -> char {
+        match self {
+            Delimited::Csv => ',',
+            Delimited::Tsv => '\t',
+        }
+    }
+
+    /// Quotes a CSV cell when needed; TSV cannot quote, so separators and
+    /// line breaks become spaces
+    fn cell(&self, value: &str) -> String {
+        match self {
+            Delimited::Csv if value.contains(&[',', '"', '\n', '\r'][..]) => {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            }
+            Delimited::Csv => value.to_string(),
+            Delimited::Tsv => value.replace(&['\t', '\n', '\r'][..], " "),
+        }
+    }
+}
+
+/// Fields the decoder prints for each structure type, in order
+const TYPE_FIELDS: &[(u8, &[&str])] = &[
+    (
+        0,
+        &[
+            "Vendor",
+            "Version",
+            "Release Date",
+            "Address",
+            "Runtime Size",
+            "ROM Size",
+            "Characteristics",
+            "BIOS Revision",
+            "Firmware Revision",
+        ],
+    ),
+    (
+        1,
+        &[
+            "Manufacturer",
+            "Product Name",
+            "Version",
+            "Serial Number",
+            "UUID",
+            "Wake-up Type",
+            "SKU Number",
+            "Family",
+        ],
+    ),
+    (
+        2,
+        &[
+            "Manufacturer",
+            "Product Name",
+            "Version",
+            "Serial Number",
+            "Asset Tag",
+            "Features",
+            "Location In Chassis",
+            "Chassis Handle",
+            "Type",
+            "Contained Object Handles",
+        ],
+    ),
+    (
+        3,
+        &[
+            "Manufacturer",
+            "Type",
+            "Lock",
+            "Version",
+            "Serial Number",
+            "Asset Tag",
+            "Boot-up State",
+            "Power Supply State",
+            "Thermal State",
+            "Security Status",
+            "OEM Information",
+            "Height",
+            "Number Of Power Cords",
+            "Contained Elements",
+            "SKU Number",
+        ],
+    ),
+    (
+        4,
+        &[
+            "Socket Designation",
+            "Type",
+            "Family",
+            "Manufacturer",
+            "ID",
+            "Signature",
+            "Flags",
+            "Version",
+            "Voltage",
+            "External Clock",
+            "Max Speed",
+            "Current Speed",
+            "Status",
+            "Upgrade",
+            "L1 Cache Handle",
+            "L2 Cache Handle",
+            "L3 Cache Handle",
+            "Serial Number",
+            "Asset Tag",
+            "Part Number",
+            "Core Count",
+            "Core Enabled",
+            "Thread Count",
+            "Characteristics",
+        ],
+    ),
+    (
+        5,
+        &[
+            "Error Detecting Method",
+            "Error Correcting Capabilities",
+            "Supported Interleave",
+            "Current Interleave",
+            "Maximum Memory Module Size",
+            "Maximum Total Memory Size",
+            "Supported Speeds",
+            "Supported Memory Types",
+            "Associated Memory Slots",
+            "Enabled Error Correcting Capabilities",
+        ],
+    ),
+    (
+        6,
+        &[
+            "Socket Designation",
+            "Bank Connections",
+            "Current Speed",
+            "Type",
+            "Installed Size",
+            "Enabled Size",
+            "Error Status",
+        ],
+    ),
+    (
+        7,
+        &[
+            "Socket Designation",
+            "Configuration",
+            "Operational Mode",
+            "Location",
+            "Installed Size",
+            "Maximum Size",
+            "Supported SRAM Types",
+            "Installed SRAM Type",
+            "Speed",
+            "Error Correction Type",
+            "System Type",
+            "Associativity",
+        ],
+    ),
+    (
+        8,
+        &[
+            "Internal Reference Designator",
+            "Internal Connector Type",
+            "External Reference Designator",
+            "External Connector Type",
+            "Port Type",
+        ],
+    ),
+    (
+        9,
+        &[
+            "Designation",
+            "Type",
+            "Current Usage",
+            "Length",
+            "ID",
+            "Characteristics",
+            "Bus Address",
+            "Data Bus Width",
+            "Peer Devices",
+        ],
+    ),
+    (10, &["Type", "Status", "Description"]),
+    (
+        13,
+        &[
+            "Language Description Format",
+            "Installable Languages",
+            "Currently Installed Language",
+        ],
+    ),
+    (14, &["Name", "Items"]),
+    (
+        15,
+        &[
+            "Area Length",
+            "Header Start Offset",
+            "Header Length",
+            "Data Start Offset",
+            "Access Method",
+            "Access Address",
+            "Status",
+            "Change Token",
+            "Header Format",
+            "Supported Log Type Descriptors",
+        ],
+    ),
+    (
+        16,
+        &[
+            "Location",
+            "Use",
+            "Error Correction Type",
+            "Maximum Capacity",
+            "Error Information Handle",
+            "Number Of Devices",
+        ],
+    ),
+    (
+        17,
+        &[
+            "Array Handle",
+            "Error Information Handle",
+            "Total Width",
+            "Data Width",
+            "Size",
+            "Form Factor",
+            "Set",
+            "Locator",
+            "Bank Locator",
+            "Type",
+            "Type Detail",
+            "Speed",
+            "Manufacturer",
+            "Serial Number",
+            "Asset Tag",
+            "Part Number",
+            "Rank",
+            "Configured Memory Speed",
+            "Minimum Voltage",
+            "Maximum Voltage",
+            "Configured Voltage",
+            "Memory Technology",
+            "Memory Operating Mode Capability",
+            "Firmware Version",
+            "Module Manufacturer ID",
+            "Module Product ID",
+            "Memory Subsystem Controller Manufacturer ID",
+            "Memory Subsystem Controller Product ID",
+            "Non-Volatile Size",
+            "Volatile Size",
+            "Cache Size",
+            "Logical Size",
+        ],
+    ),
+    (
+        18,
+        &[
+            "Type",
+            "Granularity",
+            "Operation",
+            "Vendor Syndrome",
+            "Memory Array Address",
+            "Device Address",
+            "Resolution",
+        ],
+    ),
+    (
+        19,
+        &[
+            "Starting Address",
+            "Ending Address",
+            "Range Size",
+            "Physical Array Handle",
+            "Partition Width",
+        ],
+    ),
+    (
+        20,
+        &[
+            "Starting Address",
+            "Ending Address",
+            "Range Size",
+            "Physical Device Handle",
+            "Memory Array Mapped Address Handle",
+            "Partition Row Position",
+            "Interleave Position",
+            "Interleaved Data Depth",
+        ],
+    ),
+    (21, &["Type", "Interface", "Buttons"]),
+    (
+        22,
+        &[
+            "Location",
+            "Manufacturer",
+            "Manufacture Date",
+            "Serial Number",
+            "Name",
+            "Chemistry",
+            "Design Capacity",
+            "Design Voltage",
+            "SBDS Version",
+            "Maximum Error",
+            "SBDS Serial Number",
+            "SBDS Manufacture Date",
+            "SBDS Chemistry",
+            "OEM-specific Information",
+        ],
+    ),
+    (
+        23,
+        &[
+            "Status",
+            "Watchdog Timer",
+            "Boot Option",
+            "Boot Option On Limit",
+            "Reset Count",
+            "Reset Limit",
+            "Timer Interval",
+            "Timeout",
+        ],
+    ),
+    (
+        24,
+        &[
+            "Power-On Password Status",
+            "Keyboard Password Status",
+            "Administrator Password Status",
+            "Front Panel Reset Status",
+        ],
+    ),
+    (25, &["Next Scheduled Power-on"]),
+    (26, PROBE_FIELDS),
+    (
+        27,
+        &[
+            "Temperature Probe Handle",
+            "Type",
+            "Status",
+            "Cooling Unit Group",
+            "OEM-specific Information",
+            "Nominal Speed",
+            "Description",
+        ],
+    ),
+    (28, PROBE_FIELDS),
+    (29, PROBE_FIELDS),
+    (
+        30,
+        &[
+            "Manufacturer Name",
+            "Inbound Connection",
+            "Outbound Connection",
+        ],
+    ),
+    (31, &["Checksum"]),
+    (32, &["Status"]),
+    (
+        33,
+        &[
+            "Type",
+            "Granularity",
+            "Operation",
+            "Vendor Syndrome",
+            "Memory Array Address",
+            "Device Address",
+            "Resolution",
+        ],
+    ),
+    (34, &["Description", "Type", "Address", "Address Type"]),
+    (
+        35,
+        &[
+            "Description",
+            "Management Device Handle",
+            "Component Handle",
+            "Threshold Handle",
+        ],
+    ),
+    (
+        36,
+        &[
+            "Lower Non-critical Threshold",
+            "Upper Non-critical Threshold",
+            "Lower Critical Threshold",
+            "Upper Critical Threshold",
+            "Lower Non-recoverable Threshold",
+            "Upper Non-recoverable Threshold",
+        ],
+    ),
+    (37, &["Type", "Maximal Load", "Devices"]),
+    (
+        38,
+        &[
+            "Interface Type",
+            "Specification Version",
+            "I2C Slave Address",
+            "NV Storage Device Address",
+            "Base Address",
+            "Register Spacing",
+            "Interrupt Polarity",
+            "Interrupt Trigger Mode",
+            "Interrupt Number",
+        ],
+    ),
+    (
+        39,
+        &[
+            "Power Unit Group",
+            "Location",
+            "Name",
+            "Manufacturer",
+            "Serial Number",
+            "Asset Tag",
+            "Model Part Number",
+            "Revision",
+            "Max Power Capacity",
+            "Status",
+            "Type",
+            "Input Voltage Range Switching",
+            "Plugged",
+            "Hot Replaceable",
+            "Input Voltage Probe Handle",
+            "Cooling Device Handle",
+            "Input Current Probe Handle",
+        ],
+    ),
+    (
+        40,
+        &["Referenced Handle", "Referenced Offset", "String", "Value"],
+    ),
+    (
+        41,
+        &[
+            "Reference Designation",
+            "Type",
+            "Status",
+            "Type Instance",
+            "Bus Address",
+        ],
+    ),
+    (
+        42,
+        &[
+            "Interface Type",
+            "Vendor ID",
+            "Host Interface Type",
+            "Device Type",
+            "idVendor",
+            "idProduct",
+            "VendorID",
+            "DeviceID",
+            "SubVendorID",
+            "SubDeviceID",
+            "Protocol ID",
+            "Service UUID",
+            "Host IP Assignment Type",
+            "Host IP Address Format",
+            "IPv4 Address",
+            "IPv4 Mask",
+            "IPv6 Address",
+            "IPv6 Mask",
+            "Redfish Service IP Discovery Type",
+            "Redfish Service IP Address Format",
+            "IPv4 Redfish Service Address",
+            "IPv4 Redfish Service Mask",
+            "IPv6 Redfish Service Address",
+            "IPv6 Redfish Service Mask",
+            "Redfish Service Port",
+            "Redfish Service Vlan",
+            "Redfish Service Hostname",
+        ],
+    ),
+    (
+        43,
+        &[
+            "Vendor ID",
+            "Specification Version",
+            "Firmware Revision",
+            "Description",
+            "Characteristics",
+            "OEM-specific Information",
+        ],
+    ),
+];
+
+/// Fields of the voltage, temperature and electrical current probes
+/// (types 26, 28 and 29)
+const PROBE_FIELDS: &[&str] = &[
+    "Description",
+    "Location",
+    "Status",
+    "Maximum Value",
+    "Minimum Value",
+    "Resolution",
+    "Tolerance",
+    "Accuracy",
+    "OEM-specific Information",
+    "Nominal Value",
+];
+
+/// Column names of a structure: field names, with repeated names numbered
+/// ("Type", "Type #2", ...)
+fn field_columns(decoded: &DecodedStruct) -> Vec<(String, &DecodedField)> {
+    let mut columns: Vec<(String, &DecodedField)> = Vec::new();
+    for field in decoded.fields.iter() {
+        let count = decoded
+            .fields
+            .iter()
+            .take_while(|other| !std::ptr::eq(*other, field))
+            .filter(|other| other.name == field.name)
+            .count();
+        let name = match count {
+            0 => field.name.clone(),
+            n => format!("{} #{}", field.name, n + 1),
+        };
+        columns.push((name, field));
+    }
+    columns
+}
+
+/// A structure with the values of the key columns that precede its fields
+pub type Row<'a> = (Vec<String>, &'a DecodedStruct);
+
+/// Rows without key columns
+pub fn rows(structs: &[DecodedStruct]) -> Vec<Row<'_>> {
+    structs
+        .iter()
+        .map(|decoded| (Vec::new(), decoded))
+        .collect()
+}
+
+/// Columns of a table of structures of one type: the fields of the type,
+/// then the other field names of the rows in decoder order
+///
+/// A field missing from the first structures is placed after the field that
+/// precedes it in the structure that has it.
+pub fn columns(rows: &[Row<'_>]) -> Vec<String> {
+    let fields = rows
+        .first()
+        .and_then(|(_, decoded)| {
+            TYPE_FIELDS
+                .iter()
+                .find(|(struct_type, _)| *struct_type == decoded.struct_type)
+        })
+        .map(|(_, fields)| *fields)
+        .unwrap_or_default();
+    let mut columns: Vec<String> = Vec::new();
+    for (_, decoded) in rows.iter() {
+        let mut position = 0;
+        for (name, _) in field_columns(decoded) {
+            if fields.contains(&name.as_str()) {
+                continue;
+            }
+            match columns.iter().position(|column| *column == name) {
+                Some(index) => position = index + 1,
+                None => {
+                    columns.insert(position, name);
+                    position += 1;
+                }
+            }
+        }
+    }
+    fields
+        .iter()
+        .map(|field| field.to_string())
+        .chain(columns)
+        .collect()
+}
+
+/// Cell content of a field: its value and its lines
+fn field_value(field: &DecodedField) -> String {
+    field
+        .value
+        .iter()
+        .chain(field.items.iter())
+        .map(|value| value.as_str())
+        .collect::<Vec<_>>()
+        .join(LIST_SEPARATOR)
+}
+
+/// Writes rows as a table, header row first: the `keys` columns, "Handle",
+/// then the fields
+pub fn write_table(keys: &[&str], rows: &[Row<'_>], format: Delimited) -> String {
+    let columns = columns(rows);
+    let separator = format.separator().to_string();
+    let mut output = String::new();
+
+    let header: Vec<String> = keys
+        .iter()
+        .copied()
+        .chain(std::iter::once("Handle"))
+        .chain(columns.iter().map(|column| column.as_str()))
+        .map(|name| format.cell(name))
+        .collect();
+    output.push_str(&header.join(&separator));
+    output.push('\n');
+
+    for (key_values, decoded) in rows.iter() {
+        let fields = field_columns(decoded);
+        let mut row: Vec<String> = key_values.iter().map(|value| format.cell(value)).collect();
+        row.push(format!("{:#06X}", decoded.handle));
+        for column in columns.iter() {
+            let value = fields
+                .iter()
+                .find(|(name, _)| name == column)
+                .map(|(_, field)| field_value(field))
+                .unwrap_or_default();
+            row.push(format.cell(&value));
+        }
+        output.push_str(&row.join(&separator));
+        output.push('\n');
+    }
+    output
+}
+
+/// Groups rows by structure type, in type order
+pub fn by_type(rows: Vec<Row<'_>>) -> BTreeMap<u8, Vec<Row<'_>>> {
+    let mut types: BTreeMap<u8, Vec<Row<'_>>> = BTreeMap::new();
+    for row in rows {
+        types.entry(row.1.struct_type).or_default().push(row);
+    }
+    types
+}
+
+/// Writes one table per structure type into `dir` (type-17.csv, ...) and
+/// returns their paths
+pub fn export_types(
+    keys: &[&str],
+    rows: Vec<Row<'_>>,
+    format: Delimited,
+    dir: &Path,
+) -> Result<Vec<PathBuf>, Error> {
+    fs::create_dir_all(dir)?;
+    let mut paths = Vec::new();
+    for (struct_type, rows) in by_type(rows) {
+        let path = dir.join(format!("type-{}.{}", struct_type, format.extension()));
+        fs::write(&path, write_table(keys, &rows, format))?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+#[test]
+fn test_write_table() {
+    let text = "Handle 0x0008, DMI type 17, 40 bytes
+Memory Device
+\tSize: 8 GB
+\tLocator: DIMM 0
+\tPart Number: \"HMA81GU6\", rev. 2
+
+Handle 0x0009, DMI type 17, 40 bytes
+Memory Device
+\tSize: No Module Installed
+\tForm Factor: DIMM
+\tLocator: DIMM 1
+
+Handle 0x0000, DMI type 0, 24 bytes
+BIOS Information
+\tCharacteristics:
+\t\tPCI is supported
+\t\tBIOS is upgradeable
+\tCharacteristics:
+\t\tACPI is supported
+";
+    let structs = crate::decoded::parse_text(text);
+    let types = by_type(rows(&structs));
+    assert_eq!(types.keys().copied().collect::<Vec<_>>(), vec![0, 17]);
+
+    // Every field of the type is a column, in decoder order, whether or not
+    // the structures have it
+    let memory = write_table(&[], &types[&17], Delimited::Csv);
+    let lines: Vec<&str> = memory.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with(
+        "Handle,Array Handle,Error Information Handle,Total Width,Data Width,Size,\
+         Form Factor,Set,Locator,Bank Locator,Type,Type Detail,Speed,Manufacturer,\
+         Serial Number,Asset Tag,Part Number,"
+    ));
+    assert!(lines[0].ends_with(",Cache Size,Logical Size"));
+    assert!(lines[1].starts_with("0x0008,,,,,8 GB,,,DIMM 0,,,,,,,,\"\"\"HMA81GU6\"\", rev. 2\","));
+    assert!(lines[2].starts_with("0x0009,,,,,No Module Installed,DIMM,,DIMM 1,"));
+    assert_eq!(lines[0].split(',').count(), lines[2].split(',').count());
+
+    // Repeated fields follow the fields of the type
+    assert_eq!(
+        write_table(&[], &types[&0], Delimited::Tsv),
+        "Handle\tVendor\tVersion\tRelease Date\tAddress\tRuntime Size\tROM Size\t\
+         Characteristics\tBIOS Revision\tFirmware Revision\tCharacteristics #2
+0x0000\t\t\t\t\t\t\tPCI is supported; BIOS is upgradeable\t\t\tACPI is supported
+"
+    );
+
+    let keyed: Vec<Row<'_>> = types[&17]
+        .iter()
+        .map(|(_, decoded)| (vec!["a.bin".to_string()], *decoded))
+        .collect();
+    assert!(write_table(&["File"], &keyed, Delimited::Csv)
+        .starts_with("File,Handle,Array Handle,Error Information Handle,"));
+}
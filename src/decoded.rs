@@ -0,0 +1,328 @@
+//! Decoded field model
+//!
+//! A structure as it reads in the default text output: the handle and type
+//! line, the structure name, and its fields with dmidecode's field names.
+//! Features that compare or export decoded values (diff, CSV, ...) work on
+//! this model rather than on the raw structures.  It is also the JSON output
+//! of --from-text, as a report has no raw bytes to decode the typed values
+//! of the -j schema from.
+
+use crate::default_out::dump_undefined_struct;
+use crate::output::capture;
+use serde::Serialize;
+use smbioslib::*;
+
+/// A decoded structure
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DecodedStruct {
+    /// Structure handle
+    pub handle: u16,
+    /// DMI type
+    #[serde(rename = "type")]
+    pub struct_type: u8,
+    /// Length of the formatted area in bytes
+    pub length: usize,
+    /// Structure name, e.g. "BIOS Information"
+    pub name: String,
+    /// Decoded fields in output order
+    pub fields: Vec<DecodedField>,
+}
+
+/// A decoded field
+///
+/// Fields such as "Characteristics" have no value of their own and list
+/// their content in `items` instead.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DecodedField {
+    /// Field name as printed by dmidecode
+    pub name: String,
+    /// Field value, if printed on the same line
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// Indented lines following the field
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub items: Vec<String>,
+}
+
+/// Fields that identify one instance of a multi-instance structure type
+const DESIGNATION_FIELDS: [&str; 7] = [
+    "Socket Designation",
+    "Designation",
+    "Reference Designation",
+    "Internal Reference Designator",
+    "External Reference Designator",
+    "Bank Locator",
+    "Locator",
+];
+
+impl DecodedStruct {
+    /// Returns the first field called `name`
+    pub fn field(&self, name: &str) -> Option<&DecodedField> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    /// Returns the value of the first field called `name`
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.field(name)?.value.as_deref()
+    }
+
+    /// Returns the leading number of a value ("2400 MT/s", "4")
+    pub fn number(&self, name: &str) -> Option<u64> {
+        self.value(name)?.split(' ').next()?.parse().ok()
+    }
+
+    /// Returns a size value ("8 GB", "512 kB") in bytes
+    pub fn size_bytes(&self, name: &str) -> Option<u64> {
+        let mut parts = self.value(name)?.split(' ');
+        let size: u64 = parts.next()?.parse().ok()?;
+        let exponent = match parts.next()? {
+            "bytes" => 0,
+            "kB" => 1,
+            "MB" => 2,
+            "GB" => 3,
+            "TB" => 4,
+            "PB" => 5,
+            "EB" => 6,
+            _ => return None,
+        };
+        size.checked_mul(1u64 << (10 * exponent))
+    }
+
+    /// Returns the locator or designation naming this instance, if any
+    pub fn designation(&self) -> Option<String> {
+        let parts: Vec<&str> = DESIGNATION_FIELDS
+            .iter()
+            .filter_map(|name| self.field(name))
+            .filter_map(|field| field.value.as_deref())
+            .collect();
+        match parts.is_empty() {
+            true => None,
+            false => Some(parts.join(" / ")),
+        }
+    }
+
+    /// Returns the structure formatted as in the default text output
+    pub fn to_text_lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!(
+                "Handle {:#06X}, DMI type {}, {} bytes",
+                self.handle, self.struct_type, self.length
+            ),
+            self.name.clone(),
+        ];
+        for field in self.fields.iter() {
+            lines.extend(field.to_text_lines());
+        }
+        lines
+    }
+}
+
+impl DecodedField {
+    /// Returns the field formatted as in the default text output
+    pub fn to_text_lines(&self) -> Vec<String> {
+        let mut lines = vec![match &self.value {
+            Some(value) => format!("\t{}: {}", self.name, value),
+            None if self.items.is_empty() => format!("\t{}", self.name),
+            None => format!("\t{}:", self.name),
+        }];
+        lines.extend(self.items.iter().map(|item| format!("\t\t{}", item)));
+        lines
+    }
+}
+
+/// Decodes a single structure through the default text decoder
+pub fn decode_struct(
+    undefined_struct: &UndefinedStruct,
+    bios_version: Option<SMBiosVersion>,
+) -> DecodedStruct {
+    let text = capture(|| dump_undefined_struct(undefined_struct, bios_version, false));
+    parse_text(&text).pop().unwrap_or_else(|| DecodedStruct {
+        handle: *undefined_struct.header.handle(),
+        struct_type: undefined_struct.header.struct_type(),
+        length: undefined_struct.fields.len(),
+        name: String::new(),
+        fields: Vec::new(),
+    })
+}
+
+/// Decodes every structure of a table
+pub fn decode_table(data: &SMBiosData) -> Vec<DecodedStruct> {
+    data.iter()
+        .map(|undefined_struct| decode_struct(undefined_struct, data.version))
+        .collect()
+}
+
+/// Parses dmidecode text output into decoded structures
+///
+/// Anything before the first "Handle 0x..." line (version and entry point
+/// information) is ignored.  Reports from dmidecode versions before 2.9,
+/// which print the type on its own line and indent everything below the
+/// handle one more level, are accepted as well.
+pub fn parse_text(text: &str) -> Vec<DecodedStruct> {
+    let mut structs: Vec<DecodedStruct> = Vec::new();
+    let mut expect_name = false;
+    let mut legacy = false;
+
+    for line in text.lines() {
+        let mut line = line.trim_end_matches('\r');
+        if let Some(header) = parse_handle_line(line) {
+            structs.push(header);
+            expect_name = true;
+            legacy = false;
+            continue;
+        }
+        if let Some(handle) = line
+            .strip_prefix("Handle 0x")
+            .and_then(|handle| u16::from_str_radix(handle, 16).ok())
+        {
+            structs.push(DecodedStruct {
+                handle,
+                struct_type: 0,
+                length: 0,
+                name: String::new(),
+                fields: Vec::new(),
+            });
+            expect_name = true;
+            legacy = true;
+            continue;
+        }
+        let current = match structs.last_mut() {
+            Some(current) => current,
+            None => continue,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        if legacy {
+            line = line.strip_prefix('\t').unwrap_or(line);
+            if let Some((struct_type, length)) = parse_type_and_length(line) {
+                current.struct_type = struct_type;
+                current.length = length;
+                continue;
+            }
+        }
+        if let Some(item) = line.strip_prefix("\t\t") {
+            match current.fields.last_mut() {
+                Some(field) => field.items.push(item.to_string()),
+                None => current.fields.push(DecodedField {
+                    name: item.to_string(),
+                    value: None,
+                    items: Vec::new(),
+                }),
+            }
+        } else if let Some(field) = line.strip_prefix('\t') {
+            current.fields.push(parse_field_line(field));
+        } else if expect_name {
+            current.name = line.to_string();
+            expect_name = false;
+        }
+    }
+
+    structs
+}
+
+/// Parses "Handle 0x0000, DMI type 0, 20 bytes"
+fn parse_handle_line(line: &str) -> Option<DecodedStruct> {
+    let rest = line.strip_prefix("Handle 0x")?;
+    let (handle, rest) = rest.split_at(rest.find(", ")?);
+    let handle = u16::from_str_radix(handle, 16).ok()?;
+    let (struct_type, length) = parse_type_and_length(&rest[2..])?;
+    Some(DecodedStruct {
+        handle,
+        struct_type,
+        length,
+        name: String::new(),
+        fields: Vec::new(),
+    })
+}
+
+/// Parses "DMI type 0, 20 bytes", with an optional trailing period
+fn parse_type_and_length(text: &str) -> Option<(u8, usize)> {
+    let mut parts = text.split(", ");
+    let struct_type = parts.next()?.strip_prefix("DMI type ")?.parse().ok()?;
+    let length = parts
+        .next()?
+        .trim_end_matches('.')
+        .strip_suffix(" bytes")?
+        .parse()
+        .ok()?;
+    Some((struct_type, length))
+}
+
+/// Parses "Name: Value", "Name:" or a bare line
+fn parse_field_line(line: &str) -> DecodedField {
+    match line.find(": ") {
+        Some(position) => DecodedField {
+            name: line[..position].to_string(),
+            value: Some(line[position + 2..].to_string()),
+            items: Vec::new(),
+        },
+        None => DecodedField {
+            name: line.strip_suffix(':').unwrap_or(line).to_string(),
+            value: None,
+            items: Vec::new(),
+        },
+    }
+}
+
+#[test]
+fn test_parse_text() {
+    let text = "# dmidecode 3.3\n\
+                SMBIOS 3.2.0 present.\n\
+                \n\
+                Handle 0x0000, DMI type 0, 24 bytes\n\
+                BIOS Information\n\
+                \tVendor: ACME\n\
+                \tCharacteristics:\n\
+                \t\tPCI is supported\n\
+                \t\tBIOS is upgradeable\n\
+                \tBIOS Revision: 1.50\n\
+                \n\
+                Handle 0x0011, DMI type 17, 40 bytes\n\
+                Memory Device\n\
+                \tBank Locator: P0 CHANNEL A\n\
+                \tLocator: DIMM 0\n";
+    let structs = parse_text(text);
+    assert_eq!(structs.len(), 2);
+    assert_eq!(structs[0].name, "BIOS Information");
+    assert_eq!(structs[0].length, 24);
+    assert_eq!(
+        structs[0].field("Characteristics").unwrap().items,
+        vec!["PCI is supported", "BIOS is upgradeable"]
+    );
+    assert_eq!(structs[1].handle, 0x11);
+    assert_eq!(
+        structs[1].designation(),
+        Some("P0 CHANNEL A / DIMM 0".to_string())
+    );
+    assert_eq!(
+        structs[0].to_text_lines().join("\n"),
+        text.lines().skip(3).take(7).collect::<Vec<_>>().join("\n")
+    );
+}
+
+#[test]
+fn test_parse_legacy_text() {
+    let text = "# dmidecode 2.2\r\n\
+                SMBIOS 2.3 present.\r\n\
+                \r\n\
+                Handle 0x0000\r\n\
+                \tDMI type 0, 20 bytes.\r\n\
+                \tBIOS Information\r\n\
+                \t\tVendor: Phoenix Technologies LTD\r\n\
+                \t\tCharacteristics:\r\n\
+                \t\t\tPCI is supported\r\n";
+    let structs = parse_text(text);
+    assert_eq!(structs.len(), 1);
+    assert_eq!(structs[0].struct_type, 0);
+    assert_eq!(structs[0].length, 20);
+    assert_eq!(structs[0].name, "BIOS Information");
+    assert_eq!(
+        structs[0].field("Vendor").unwrap().value,
+        Some("Phoenix Technologies LTD".to_string())
+    );
+    assert_eq!(
+        structs[0].field("Characteristics").unwrap().items,
+        vec!["PCI is supported"]
+    );
+}
@@ -1,6 +1,7 @@
 use std::convert::TryInto;
 
 use crate::dmifn::*;
+use crate::output::{print, println};
 use smbioslib::*;
 
 pub const OUT_OF_SPEC: &str = "<OUT OF SPEC>";
@@ -562,6 +563,9 @@ pub fn dump_undefined_struct(
                     ChassisHeight::U(units) => {
                         println!("\tHeight: {} U", units);
                     }
+                    ChassisHeight::SpecifiedInRackHeight => {
+                        println!("\tHeight: See Rack Height");
+                    }
                 }
             }
             if let Some(number_of_power_cords) = data.number_of_power_cords() {
@@ -1188,7 +1192,8 @@ pub fn dump_undefined_struct(
             match (data.log_header_start_offset(), data.log_data_start_offset()) {
                 (Some(log_header_start_offset), Some(log_data_start_offset)) => {
                     println!("\tHeader Start Offset: {:#06X}", log_header_start_offset);
-                    let length = log_data_start_offset - log_header_start_offset;
+                    // The data may wrongly start before the header
+                    let length = log_data_start_offset.saturating_sub(log_header_start_offset);
                     if length > 0 {
                         println!(
                             "\tHeader Length: {} {}",
@@ -1561,7 +1566,7 @@ pub fn dump_undefined_struct(
                 println!("\tManufacturer: {}", manufacturer);
             }
             if let Some(manufacture_date) = dmidecode_string_val(&data.manufacture_date()) {
-                println!("Manufacture Date: {}", manufacture_date);
+                println!("\tManufacture Date: {}", manufacture_date);
             }
             if let Some(serial_number) = dmidecode_string_val(&data.serial_number()) {
                 println!("\tSerial Number: {}", serial_number);
@@ -1589,7 +1594,7 @@ pub fn dump_undefined_struct(
                 dmi_battery_maximum_error(maximum_error_in_battery_data);
             }
             if let Some(sbds_serial_number) = data.sbds_serial_number() {
-                println!("\tSBDS Serial Number {:#06X}", sbds_serial_number);
+                println!("\tSBDS Serial Number: {:04X}", sbds_serial_number);
             }
             if let Some(sbds_manufacture_date) = data.sbds_manufacture_date() {
                 println!(
@@ -2248,7 +2253,12 @@ pub fn dump_undefined_struct(
                     if let Some(string) = dmidecode_string_val(&entry.1.string()) {
                         println!("\tString: {}", string);
                     }
-                    if let Some(value) = entry.1.value() {
+                    // An entry shorter than its 5-byte header has no value
+                    let value = match entry.1.entry_length() {
+                        Some(entry_length) if entry_length >= 5 => entry.1.value(),
+                        _ => None,
+                    };
+                    if let Some(value) = value {
                         print!("\tValue: ");
                         match value.len() {
                             1 => println!("{:#04x}", value[0]),
@@ -2307,8 +2317,9 @@ pub fn dump_undefined_struct(
                 minor: 2,
                 revision: 0,
             };
-            if let Some(version) = bios_version {
-                if version < three_two_version {
+            // Without a version, the SMBIOS 3.2 layout is assumed
+            match bios_version {
+                Some(version) if version < three_two_version => {
                     if let Some(interface_type) = data.interface_type() {
                         println!(
                             "\tInterface Type: {}",
@@ -2330,9 +2341,8 @@ pub fn dump_undefined_struct(
                             }
                         }
                     }
-                } else {
-                    dmi_parse_controller_structure(&data);
                 }
+                _ => dmi_parse_controller_structure(&data),
             }
         }
         DefinedStruct::TpmDevice(data) => {
@@ -2419,3 +2429,31 @@ fn dmidecode_string_val(s: &SMBiosString) -> Option<String> {
         }
     }
 }
+
+#[test]
+fn test_malformed_structures() {
+    let mut table = Vec::new();
+    // System Event Log whose data starts before its header
+    let mut event_log = vec![15, 23, 0x00, 0x00, 0x00, 0x01, 0x10, 0x00, 0x08, 0x00];
+    event_log.resize(23, 0);
+    crate::build::push_struct(&mut table, &event_log, &[]);
+    // Portable Battery with the largest capacity and multiplier
+    let mut battery = vec![22, 26, 0x01, 0x00];
+    battery.resize(26, 0);
+    battery[10] = 0xFF;
+    battery[11] = 0xFF;
+    battery[21] = 0xFF;
+    crate::build::push_struct(&mut table, &battery, &[]);
+    // Additional Information with an entry shorter than its header
+    let additional = vec![40, 11, 0x02, 0x00, 1, 2, 0x00, 0x00, 0x04, 0, 0];
+    crate::build::push_struct(&mut table, &additional, &[]);
+    let data = SMBiosData::from_vec_and_version(table, Some(SMBiosVersion::new(3, 4, 0)));
+
+    let text = crate::output::capture(|| default_dump(&data, false));
+    assert!(text.contains("\tHeader Start Offset: 0x0010\n\tData Start Offset: 0x0008\n"));
+    assert!(text.contains("\tDesign Capacity: 16711425 mwh\n"));
+    assert!(!text.contains("\tValue:"));
+
+    let json = serde_json::to_string(&crate::json_out::JsonTable(&data)).unwrap();
+    assert!(json.contains("\"number_of_entries\":1,\"entry_iterator\":[]"));
+}
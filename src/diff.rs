@@ -0,0 +1,232 @@
+//! Differences between two SMBIOS tables
+//!
+//! Structures are paired by type and locator/designation.  Within a group,
+//! structures with the same handle are paired first and the remaining ones
+//! in table order.  Paired structures are compared field by field using the
+//! decoded (dmidecode) field names.
+
+use crate::decoded::{DecodedField, DecodedStruct};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Structures of one type and designation, from the first and second table
+type StructGroup = (Vec<DecodedStruct>, Vec<DecodedStruct>);
+
+/// A field that differs between two paired structures
+#[derive(Debug, Serialize)]
+pub struct FieldChange {
+    /// Field name
+    pub name: String,
+    /// Field in the first table, if present
+    pub a: Option<DecodedField>,
+    /// Field in the second table, if present
+    pub b: Option<DecodedField>,
+}
+
+/// A pair of structures with differing fields
+#[derive(Debug, Serialize)]
+pub struct StructChange {
+    /// DMI type
+    #[serde(rename = "type")]
+    pub struct_type: u8,
+    /// Structure name
+    pub name: String,
+    /// Locator or designation the structures were paired by
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub designation: Option<String>,
+    /// Handle in the first table
+    pub handle_a: u16,
+    /// Handle in the second table
+    pub handle_b: u16,
+    /// Fields that differ
+    pub fields: Vec<FieldChange>,
+}
+
+/// All differences between two tables
+#[derive(Debug, Default, Serialize)]
+pub struct TableDiff {
+    /// Structures only in the second table
+    pub added: Vec<DecodedStruct>,
+    /// Structures only in the first table
+    pub removed: Vec<DecodedStruct>,
+    /// Structures in both tables with differing fields
+    pub changed: Vec<StructChange>,
+}
+
+impl TableDiff {
+    /// Compares two decoded tables
+    pub fn from_decoded(a: Vec<DecodedStruct>, b: Vec<DecodedStruct>) -> Self {
+        let mut groups: BTreeMap<(u8, Option<String>), StructGroup> = BTreeMap::new();
+        for decoded in a {
+            let key = (decoded.struct_type, decoded.designation());
+            groups.entry(key).or_default().0.push(decoded);
+        }
+        for decoded in b {
+            let key = (decoded.struct_type, decoded.designation());
+            groups.entry(key).or_default().1.push(decoded);
+        }
+
+        let mut diff = TableDiff::default();
+        for ((_, designation), (a, mut b)) in groups {
+            let mut unpaired = Vec::new();
+            for struct_a in a {
+                match b
+                    .iter()
+                    .position(|struct_b| struct_b.handle == struct_a.handle)
+                {
+                    Some(position) => {
+                        let struct_b = b.remove(position);
+                        diff.compare(&struct_a, &struct_b, &designation);
+                    }
+                    None => unpaired.push(struct_a),
+                }
+            }
+            let mut b = b.into_iter();
+            for struct_a in unpaired {
+                match b.next() {
+                    Some(struct_b) => diff.compare(&struct_a, &struct_b, &designation),
+                    None => diff.removed.push(struct_a),
+                }
+            }
+            diff.added.extend(b);
+        }
+
+        diff.added.sort_by_key(|decoded| decoded.handle);
+        diff.removed.sort_by_key(|decoded| decoded.handle);
+        diff.changed.sort_by_key(|change| change.handle_a);
+        diff
+    }
+
+    /// True if the tables are the same
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    fn compare(&mut self, a: &DecodedStruct, b: &DecodedStruct, designation: &Option<String>) {
+        // Fields may repeat within a structure, so they are keyed by name and occurrence
+        fn keyed(fields: &[DecodedField]) -> Vec<((String, usize), &DecodedField)> {
+            let mut seen: BTreeMap<&str, usize> = BTreeMap::new();
+            fields
+                .iter()
+                .map(|field| {
+                    let count = seen.entry(field.name.as_str()).or_insert(0);
+                    *count += 1;
+                    ((field.name.clone(), *count), field)
+                })
+                .collect()
+        }
+
+        let fields_a = keyed(&a.fields);
+        let fields_b = keyed(&b.fields);
+        let mut changes = Vec::new();
+        for (key, field_a) in fields_a.iter() {
+            match fields_b.iter().find(|(key_b, _)| key_b == key) {
+                Some((_, field_b)) if field_a == field_b => {}
+                found => changes.push(FieldChange {
+                    name: key.0.clone(),
+                    a: Some((*field_a).clone()),
+                    b: found.map(|(_, field_b)| (*field_b).clone()),
+                }),
+            }
+        }
+        for (key, field_b) in fields_b.iter() {
+            if !fields_a.iter().any(|(key_a, _)| key_a == key) {
+                changes.push(FieldChange {
+                    name: key.0.clone(),
+                    a: None,
+                    b: Some((*field_b).clone()),
+                });
+            }
+        }
+
+        if !changes.is_empty() {
+            self.changed.push(StructChange {
+                struct_type: a.struct_type,
+                name: a.name.clone(),
+                designation: designation.clone(),
+                handle_a: a.handle,
+                handle_b: b.handle,
+                fields: changes,
+            });
+        }
+    }
+
+    /// Prints the differences in a unified diff style
+    pub fn print_unified(&self, name_a: &str, name_b: &str) {
+        println!("--- {}", name_a);
+        println!("+++ {}", name_b);
+        for removed in self.removed.iter() {
+            println!(
+                "@@ Handle {:#06X}, DMI type {} @@",
+                removed.handle, removed.struct_type
+            );
+            for line in removed.to_text_lines() {
+                println!("-{}", line);
+            }
+        }
+        for added in self.added.iter() {
+            println!(
+                "@@ Handle {:#06X}, DMI type {} @@",
+                added.handle, added.struct_type
+            );
+            for line in added.to_text_lines() {
+                println!("+{}", line);
+            }
+        }
+        for change in self.changed.iter() {
+            let handle = match change.handle_a == change.handle_b {
+                true => format!("{:#06X}", change.handle_a),
+                false => format!("{:#06X} -> {:#06X}", change.handle_a, change.handle_b),
+            };
+            match &change.designation {
+                Some(designation) => println!(
+                    "@@ Handle {}, DMI type {}, {} ({}) @@",
+                    handle, change.struct_type, change.name, designation
+                ),
+                None => println!(
+                    "@@ Handle {}, DMI type {}, {} @@",
+                    handle, change.struct_type, change.name
+                ),
+            }
+            for field in change.fields.iter() {
+                for line in field.a.iter().flat_map(|a| a.to_text_lines()) {
+                    println!("-{}", line);
+                }
+                for line in field.b.iter().flat_map(|b| b.to_text_lines()) {
+                    println!("+{}", line);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_table_diff() {
+    use crate::decoded::parse_text;
+
+    let a = parse_text(
+        "Handle 0x0000, DMI type 0, 24 bytes\nBIOS Information\n\tVersion: 1.0\n\
+         Handle 0x0010, DMI type 17, 40 bytes\nMemory Device\n\tSize: 8 GB\n\tLocator: DIMM A\n\
+         Handle 0x0011, DMI type 17, 40 bytes\nMemory Device\n\tSize: 8 GB\n\tLocator: DIMM B\n",
+    );
+    let b = parse_text(
+        "Handle 0x0001, DMI type 0, 24 bytes\nBIOS Information\n\tVersion: 1.1\n\
+         Handle 0x0012, DMI type 17, 40 bytes\nMemory Device\n\tSize: 16 GB\n\tLocator: DIMM A\n\
+         Handle 0x0013, DMI type 17, 40 bytes\nMemory Device\n\tSize: 8 GB\n\tLocator: DIMM C\n",
+    );
+
+    let diff = TableDiff::from_decoded(a.clone(), b);
+    assert_eq!(diff.removed.len(), 1);
+    assert_eq!(diff.removed[0].handle, 0x11);
+    assert_eq!(diff.added.len(), 1);
+    assert_eq!(diff.added[0].handle, 0x13);
+    assert_eq!(diff.changed.len(), 2);
+    assert_eq!(diff.changed[0].fields[0].name, "Version");
+    assert_eq!(diff.changed[1].designation, Some("DIMM A".to_string()));
+    assert_eq!(
+        diff.changed[1].fields[0].b.as_ref().unwrap().value,
+        Some("16 GB".to_string())
+    );
+
+    assert!(TableDiff::from_decoded(a.clone(), a).is_empty());
+}
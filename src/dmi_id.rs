@@ -0,0 +1,288 @@
+//! Linux DMI identification (`/sys/class/dmi/id`)
+//!
+//! Reproduces the values the kernel extracts from the table in
+//! `drivers/firmware/dmi_scan.c` and exposes in `drivers/firmware/dmi-id.c`,
+//! including the `dmi:...` modalias used by udev hwdb and kernel quirk tables.
+
+use smbioslib::*;
+use std::{fs, io::Error, path::Path};
+
+/// A value the kernel saves from the table (`dmi_ident[]`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DmiField {
+    BiosVendor,
+    BiosVersion,
+    BiosDate,
+    BiosRelease,
+    EcFirmwareRelease,
+    SysVendor,
+    ProductName,
+    ProductVersion,
+    ProductSerial,
+    ProductUuid,
+    ProductSku,
+    ProductFamily,
+    BoardVendor,
+    BoardName,
+    BoardVersion,
+    BoardSerial,
+    BoardAssetTag,
+    ChassisVendor,
+    ChassisType,
+    ChassisVersion,
+    ChassisSerial,
+    ChassisAssetTag,
+}
+
+/// Every field in the order of the sysfs attributes
+const DMI_FIELDS: [DmiField; 22] = [
+    DmiField::BiosVendor,
+    DmiField::BiosVersion,
+    DmiField::BiosDate,
+    DmiField::BiosRelease,
+    DmiField::EcFirmwareRelease,
+    DmiField::SysVendor,
+    DmiField::ProductName,
+    DmiField::ProductVersion,
+    DmiField::ProductSerial,
+    DmiField::ProductUuid,
+    DmiField::ProductSku,
+    DmiField::ProductFamily,
+    DmiField::BoardVendor,
+    DmiField::BoardName,
+    DmiField::BoardVersion,
+    DmiField::BoardSerial,
+    DmiField::BoardAssetTag,
+    DmiField::ChassisVendor,
+    DmiField::ChassisType,
+    DmiField::ChassisVersion,
+    DmiField::ChassisSerial,
+    DmiField::ChassisAssetTag,
+];
+
+/// Fields and their prefixes in the order the kernel builds the modalias
+const MODALIAS_FIELDS: [(&str, DmiField); 15] = [
+    ("bvn", DmiField::BiosVendor),
+    ("bvr", DmiField::BiosVersion),
+    ("bd", DmiField::BiosDate),
+    ("br", DmiField::BiosRelease),
+    ("efr", DmiField::EcFirmwareRelease),
+    ("svn", DmiField::SysVendor),
+    ("pn", DmiField::ProductName),
+    ("pvr", DmiField::ProductVersion),
+    ("rvn", DmiField::BoardVendor),
+    ("rn", DmiField::BoardName),
+    ("rvr", DmiField::BoardVersion),
+    ("cvn", DmiField::ChassisVendor),
+    ("ct", DmiField::ChassisType),
+    ("cvr", DmiField::ChassisVersion),
+    ("sku", DmiField::ProductSku),
+];
+
+impl DmiField {
+    /// Name of the attribute file under `/sys/class/dmi/id`
+    pub fn attribute(&self) -> &'static str {
+        match self {
+            DmiField::BiosVendor => "bios_vendor",
+            DmiField::BiosVersion => "bios_version",
+            DmiField::BiosDate => "bios_date",
+            DmiField::BiosRelease => "bios_release",
+            DmiField::EcFirmwareRelease => "ec_firmware_release",
+            DmiField::SysVendor => "sys_vendor",
+            DmiField::ProductName => "product_name",
+            DmiField::ProductVersion => "product_version",
+            DmiField::ProductSerial => "product_serial",
+            DmiField::ProductUuid => "product_uuid",
+            DmiField::ProductSku => "product_sku",
+            DmiField::ProductFamily => "product_family",
+            DmiField::BoardVendor => "board_vendor",
+            DmiField::BoardName => "board_name",
+            DmiField::BoardVersion => "board_version",
+            DmiField::BoardSerial => "board_serial",
+            DmiField::BoardAssetTag => "board_asset_tag",
+            DmiField::ChassisVendor => "chassis_vendor",
+            DmiField::ChassisType => "chassis_type",
+            DmiField::ChassisVersion => "chassis_version",
+            DmiField::ChassisSerial => "chassis_serial",
+            DmiField::ChassisAssetTag => "chassis_asset_tag",
+        }
+    }
+
+    /// Structure type and offset the kernel reads the field from
+    fn location(&self) -> (u8, usize) {
+        match self {
+            DmiField::BiosVendor => (0, 0x04),
+            DmiField::BiosVersion => (0, 0x05),
+            DmiField::BiosDate => (0, 0x08),
+            DmiField::BiosRelease => (0, 0x15),
+            DmiField::EcFirmwareRelease => (0, 0x17),
+            DmiField::SysVendor => (1, 0x04),
+            DmiField::ProductName => (1, 0x05),
+            DmiField::ProductVersion => (1, 0x06),
+            DmiField::ProductSerial => (1, 0x07),
+            DmiField::ProductUuid => (1, 0x08),
+            DmiField::ProductSku => (1, 0x19),
+            DmiField::ProductFamily => (1, 0x1A),
+            DmiField::BoardVendor => (2, 0x04),
+            DmiField::BoardName => (2, 0x05),
+            DmiField::BoardVersion => (2, 0x06),
+            DmiField::BoardSerial => (2, 0x07),
+            DmiField::BoardAssetTag => (2, 0x08),
+            DmiField::ChassisVendor => (3, 0x04),
+            DmiField::ChassisType => (3, 0x05),
+            DmiField::ChassisVersion => (3, 0x06),
+            DmiField::ChassisSerial => (3, 0x07),
+            DmiField::ChassisAssetTag => (3, 0x08),
+        }
+    }
+}
+
+/// Returns the value of `field` as the kernel would save it, or None when
+/// the kernel would not create the attribute.
+///
+/// As in the kernel, only the first structure of each type is consulted.
+pub fn dmi_field_value(data: &SMBiosData, field: DmiField) -> Option<String> {
+    let (struct_type, offset) = field.location();
+    let undefined_struct = data
+        .iter()
+        .find(|undefined_struct| undefined_struct.header.struct_type() == struct_type)?;
+
+    match field {
+        DmiField::BiosRelease | DmiField::EcFirmwareRelease => {
+            let major = undefined_struct.get_field_byte(offset - 1)?;
+            let minor = undefined_struct.get_field_byte(offset)?;
+            // 0xFF 0xFF means the field is not supported
+            match major == 0xFF && minor == 0xFF {
+                true => None,
+                false => Some(format!("{}.{}", major, minor)),
+            }
+        }
+        DmiField::ProductUuid => {
+            let uuid = undefined_struct.get_field_data(offset, offset + 0x10)?;
+            if uuid.iter().all(|&x| x == 0x00) || uuid.iter().all(|&x| x == 0xFF) {
+                return None;
+            }
+            // The first 3 fields are little-endian as of SMBIOS 2.6 (%pUl), and
+            // network byte order before that (%pUb).
+            let little_endian = match data.version {
+                Some(version) => version >= SMBiosVersion::new(2, 6, 0),
+                None => true,
+            };
+            let order: [usize; 16] = match little_endian {
+                true => [3, 2, 1, 0, 5, 4, 7, 6, 8, 9, 10, 11, 12, 13, 14, 15],
+                false => [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+            };
+            let mut output = String::new();
+            for (position, index) in order.iter().enumerate() {
+                if [4, 6, 8, 10].contains(&position) {
+                    output.push('-');
+                }
+                output.push_str(&format!("{:02x}", uuid[*index]));
+            }
+            Some(output)
+        }
+        DmiField::ChassisType => undefined_struct
+            .get_field_byte(offset)
+            .map(|chassis_type| format!("{}", chassis_type & 0x7F)),
+        _ => {
+            let index = undefined_struct.get_field_byte(offset)?;
+            // Missing strings, and strings containing only spaces, are saved as empty
+            let value = undefined_struct
+                .strings
+                .get_string(index)
+                .to_utf8_lossy()
+                .unwrap_or_default();
+            match value.trim_start_matches(' ').is_empty() {
+                true => Some(String::new()),
+                false => Some(value),
+            }
+        }
+    }
+}
+
+/// Builds the `dmi:bvn...:` modalias string.
+///
+/// Characters that are not printable ASCII, as well as spaces and colons, are
+/// removed from the values just like the kernel's `ascii_filter()`.
+pub fn modalias(data: &SMBiosData) -> String {
+    let mut output = String::from("dmi");
+    for (prefix, field) in MODALIAS_FIELDS.iter() {
+        if let Some(value) = dmi_field_value(data, *field) {
+            let filtered: String = value
+                .chars()
+                .filter(|c| *c > ' ' && *c < '\x7F' && *c != ':')
+                .collect();
+            output.push(':');
+            output.push_str(prefix);
+            output.push_str(&filtered);
+        }
+    }
+    output.push(':');
+    output
+}
+
+/// Writes the attribute files of `/sys/class/dmi/id` into `dir`.
+///
+/// Attributes are only created for values present in the table; `modalias`
+/// and `uevent` are always created.
+pub fn export_dmi_id(data: &SMBiosData, dir: &Path) -> Result<(), Error> {
+    fs::create_dir_all(dir)?;
+    for field in DMI_FIELDS.iter() {
+        if let Some(value) = dmi_field_value(data, *field) {
+            fs::write(dir.join(field.attribute()), format!("{}\n", value))?;
+        }
+    }
+    let modalias = modalias(data);
+    fs::write(dir.join("modalias"), format!("{}\n", modalias))?;
+    fs::write(dir.join("uevent"), format!("MODALIAS={}\n", modalias))?;
+    Ok(())
+}
+
+#[cfg(test)]
+fn test_table() -> SMBiosData {
+    let mut table = vec![
+        // BIOS Information, SMBIOS 2.4+ length
+        0x00, 0x18, 0x00, 0x00, 0x01, 0x02, 0x00, 0xF0, 0x03, 0x03, 0x90, 0xDA, 0xCB, 0x7F, 0x00,
+        0x00, 0x00, 0x00, 0x34, 0x01, 0x01, 0x32, 0xFF, 0xFF,
+    ];
+    table.extend_from_slice(b"ACME Corp.\0V1.50 (x:y)\x0012/07/2018\0\0");
+    table.extend_from_slice(&[
+        // System Information with a UUID and an empty version string
+        0x01, 0x1B, 0x01, 0x00, 0x01, 0x02, 0x00, 0x03, 0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66,
+        0x77, 0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF, 0x06, 0x04, 0x05,
+    ]);
+    table.extend_from_slice(b"ACME Corp.\0Road Runner\0   \0SKU-1\0Acme\0\0");
+    table.extend_from_slice(&[
+        // Chassis Information, type Notebook with the lock bit set
+        0x03, 0x09, 0x02, 0x00, 0x01, 0x8A, 0x00, 0x00, 0x00,
+    ]);
+    table.extend_from_slice(b"ACME Corp.\0\0");
+    SMBiosData::from_vec_and_version(table, Some(SMBiosVersion::new(3, 2, 0)))
+}
+
+#[test]
+fn test_dmi_field_values() {
+    let data = test_table();
+    let value = |field| dmi_field_value(&data, field);
+    assert_eq!(value(DmiField::BiosRelease), Some("1.50".to_string()));
+    assert_eq!(value(DmiField::EcFirmwareRelease), None);
+    assert_eq!(
+        value(DmiField::ProductUuid),
+        Some("33221100-5544-7766-8899-aabbccddeeff".to_string())
+    );
+    assert_eq!(value(DmiField::ProductSerial), Some(String::new()));
+    assert_eq!(value(DmiField::ProductVersion), Some(String::new()));
+    assert_eq!(value(DmiField::ChassisType), Some("10".to_string()));
+    assert_eq!(value(DmiField::BoardVendor), None);
+}
+
+#[test]
+fn test_modalias() {
+    assert_eq!(
+        modalias(&test_table()),
+        concat!(
+            "dmi:bvnACMECorp.:bvrV1.50(xy):bd12/07/2018:br1.50:svnACMECorp.:pnRoadRunner:pvr:",
+            "cvnACMECorp.:ct10:cvr:skuSKU-1:"
+        )
+    );
+}
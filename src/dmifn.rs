@@ -1,4 +1,5 @@
 use crate::default_out::{NONE, OTHER, OUT_OF_SPEC, UNKNOWN};
+use crate::output::{print, println};
 use smbioslib::*;
 use std::convert::TryInto;
 use std::net::IpAddr;
@@ -49,6 +50,11 @@ pub fn dmi_smbios_structure_type(code: u8) -> String {
         41 => "Onboard Device",
         42 => "Management Controller Host Interface",
         43 => "TPM Device",
+        44 => "Processor Additional Information",
+        45 => "Firmware Inventory Information",
+        46 => "String Property",
+        126 => "Inactive",
+        127 => "End Of Table",
         _ => "",
     };
 
@@ -106,6 +112,7 @@ pub fn dmi_processor_family(processor_family: ProcessorFamily, raw: u16) -> Stri
         ProcessorFamily::M2Family => "M2",
         ProcessorFamily::IntelCeleronMProcessor => "Celeron M",
         ProcessorFamily::IntelPentium4HTProcessor => "Pentium 4 HT",
+        ProcessorFamily::Intel => "Intel",
         ProcessorFamily::AMDDuronProcessorFamily => "Duron",
         ProcessorFamily::K5Family => "K5",
         ProcessorFamily::K6Family => "K6",
@@ -260,6 +267,7 @@ pub fn dmi_processor_family(processor_family: ProcessorFamily, raw: u16) -> Stri
         ProcessorFamily::IntelCorei5processor => "Core i5",
         ProcessorFamily::IntelCorei3processor => "Core i3",
         ProcessorFamily::IntelCorei9processor => "Core i9",
+        ProcessorFamily::IntelXeonDProcessorFamily => "Xeon D",
         ProcessorFamily::VIAC7MProcessorFamily => "C7-M",
         ProcessorFamily::VIAC7DProcessorFamily => "C7-D",
         ProcessorFamily::VIAC7ProcessorFamily => "C7",
@@ -338,6 +346,14 @@ pub fn dmi_processor_family(processor_family: ProcessorFamily, raw: u16) -> Stri
         ProcessorFamily::MultiCoreLoongson3DProcessor5xxxSeries => {
             "Multi-Core Loongson 3D Processor 5xxx Series"
         }
+        ProcessorFamily::IntelCore3 => "Core 3",
+        ProcessorFamily::IntelCore5 => "Core 5",
+        ProcessorFamily::IntelCore7 => "Core 7",
+        ProcessorFamily::IntelCore9 => "Core 9",
+        ProcessorFamily::IntelCoreUltra3 => "Core Ultra 3",
+        ProcessorFamily::IntelCoreUltra5 => "Core Ultra 5",
+        ProcessorFamily::IntelCoreUltra7 => "Core Ultra 7",
+        ProcessorFamily::IntelCoreUltra9 => "Core Ultra 9",
         ProcessorFamily::None => "",
     };
     match print == "" {
@@ -428,6 +444,14 @@ pub fn dmi_processor_upgrade(processor_upgrade: ProcessorUpgradeData) -> String
         ProcessorUpgrade::SocketBGA4129 => "Socket BGA4129",
         ProcessorUpgrade::SocketLGA4710 => "Socket LGA4710",
         ProcessorUpgrade::SocketLGA7529 => "Socket LGA7529",
+        ProcessorUpgrade::SocketBGA1964 => "Socket BGA1964",
+        ProcessorUpgrade::SocketBGA1792 => "Socket BGA1792",
+        ProcessorUpgrade::SocketBGA2049 => "Socket BGA2049",
+        ProcessorUpgrade::SocketBGA2551 => "Socket BGA2551",
+        ProcessorUpgrade::SocketLGA1851 => "Socket LGA1851",
+        ProcessorUpgrade::SocketBGA2114 => "Socket BGA2114",
+        ProcessorUpgrade::SocketBGA2833 => "Socket BGA2833",
+        ProcessorUpgrade::SeeSocketType => "",
         ProcessorUpgrade::None => "",
     };
     match print == "" {
@@ -443,14 +467,11 @@ pub fn dmi_processor_cache(
 ) {
     print!("\t{}: ", label);
     match *handle == 0xFFFF {
-        true => {
-            if let Some(ver) = version {
-                match ver >= SMBiosVersion::new(2, 3, 0) {
-                    true => println!("Not Provided"),
-                    false => println!("No {} Cache", level),
-                }
-            }
-        }
+        // Without a version, the structure is decoded as the latest SMBIOS
+        true => match version {
+            Some(ver) if ver < SMBiosVersion::new(2, 3, 0) => println!("No {} Cache", level),
+            _ => println!("Not Provided"),
+        },
         false => println!("{:#06X}", *handle),
     }
 }
@@ -510,7 +531,7 @@ pub fn dmi_processor_id(data: &SMBiosProcessorInformation<'_>) {
                 let mut sig = 0;
 
                 if family.0 == ProcessorFamily::Intel386Processor {
-                    let dx = u16::from_le_bytes(p[0..=1].try_into().expect("u16 is 2 bytes"));
+                    let dx = u16::from_le_bytes([p[0], p[1]]);
                     println!(
                         "\tSignature: Type {}, Family {}, Major Stepping {}, Minor Stepping {}",
                         dx >> 12,
@@ -520,7 +541,7 @@ pub fn dmi_processor_id(data: &SMBiosProcessorInformation<'_>) {
                     );
                     return;
                 } else if family.0 == ProcessorFamily::Intel486Processor {
-                    let dx = u16::from_le_bytes(p[0..=1].try_into().expect("u16 is 2 bytes"));
+                    let dx = u16::from_le_bytes([p[0], p[1]]);
 
                     // Not all 80486 CPU support the CPUID instruction, we have to find
                     // whether the one we have here does or not. Note that this trick
@@ -546,7 +567,7 @@ pub fn dmi_processor_id(data: &SMBiosProcessorInformation<'_>) {
                     || family.0 == ProcessorFamily::ARMv8
                     || (family.1 >= 0x118 && family.1 <= 0x119)
                 {
-                    let midr = u32::from_le_bytes(p[4..=7].try_into().expect("u32 is 4 bytes"));
+                    let midr = u32::from_le_bytes([p[4], p[5], p[6], p[7]]);
 
                     // The format of this field was not defined for ARM processors
                     // before version 3.1.0 of the SMBIOS specification, so we
@@ -621,8 +642,8 @@ pub fn dmi_processor_id(data: &SMBiosProcessorInformation<'_>) {
                 // Extra flags are now returned in the ECX register when one calls
                 // the CPUID instruction. Their meaning is explained in table 3-5, but
                 // DMI doesn't support this yet.
-                let eax = u32::from_le_bytes(p[0..=3].try_into().expect("u32 is 4 bytes"));
-                let edx = u32::from_le_bytes(p[4..=7].try_into().expect("u32 is 4 bytes"));
+                let eax = u32::from_le_bytes([p[0], p[1], p[2], p[3]]);
+                let edx = u32::from_le_bytes([p[4], p[5], p[6], p[7]]);
 
                 match sig {
                     // Intel
@@ -1155,6 +1176,9 @@ pub fn dmi_memory_device_form_factor(form_factor: MemoryFormFactorData) -> Strin
         MemoryFormFactor::Srimm => "SRIMM",
         MemoryFormFactor::Fbdimm => "FB-DIMM",
         MemoryFormFactor::Die => "Die",
+        MemoryFormFactor::Camm => "CAMM",
+        MemoryFormFactor::Cudimm => "CUDIMM",
+        MemoryFormFactor::Csodimm => "CSODIMM",
         MemoryFormFactor::None => "",
     };
     match print == "" {
@@ -1205,6 +1229,7 @@ pub fn dmi_memory_device_type(memory_type: MemoryDeviceTypeData) -> String {
         MemoryDeviceType::Ddr5 => "DDR5",
         MemoryDeviceType::Lpddr5 => "LPDDR5",
         MemoryDeviceType::Hbm3 => "HBM3",
+        MemoryDeviceType::Mrdimm => "MRDIMM",
         MemoryDeviceType::None => "",
     };
     match print == "" {
@@ -1328,6 +1353,7 @@ pub fn dmi_memory_technology(technology: MemoryDeviceTechnologyData) {
         MemoryDeviceTechnology::IntelOptaneDcPersistentMemory => {
             "Intel Optane DC persistent memory"
         }
+        MemoryDeviceTechnology::Mrdimm => "MRDIMM",
         MemoryDeviceTechnology::None => "",
     };
     match print == "" {
@@ -2318,7 +2344,8 @@ pub fn dmi_battery_capacity(capacity: &PortableBatteryDesignCapacity, multiplier
     print!("\tDesign Capacity: ");
     match capacity {
         PortableBatteryDesignCapacity::MilliWattHours(mwh) => {
-            println!("{} mwh", mwh * multiplier as u16)
+            // Up to 65535 x 255, more than a u16 holds
+            println!("{} mwh", *mwh as u32 * multiplier as u32)
         }
         PortableBatteryDesignCapacity::Unknown => println!("{}", UNKNOWN),
     }
@@ -2786,6 +2813,13 @@ pub fn dmi_management_controller_host_type(host_type: &HostInterfaceTypeData) ->
         HostInterfaceType::Uart16650 => "16650/16650A UART Register Compatible",
         HostInterfaceType::Uart16750 => "16750/16750A UART Register Compatible",
         HostInterfaceType::Uart16850 => "16850/16850A UART Register Compatible",
+        HostInterfaceType::I2C => "I2C/SMBus",
+        HostInterfaceType::I3C => "I3C",
+        HostInterfaceType::PcieVdm => "PCIe VDM",
+        HostInterfaceType::Mmbi => "MMBI",
+        HostInterfaceType::Pcc => "PCC",
+        HostInterfaceType::Ucie => "UCIe",
+        HostInterfaceType::Usb => "USB",
         HostInterfaceType::NetworkHostInterface => "Network",
         HostInterfaceType::OemDefined => "OEM",
         HostInterfaceType::None => "",
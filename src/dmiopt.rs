@@ -1,5 +1,10 @@
+use crate::build::EntryPointFormat;
+use crate::decoded::{decode_struct, DecodedStruct};
 use crate::default_out::dump_undefined_struct;
-use crate::error::BiosParseError;
+use crate::default_out::OUT_OF_SPEC;
+use crate::dmifn::dmi_smbios_structure_type;
+use crate::error::{BiosParseError, EXIT_STATUS_HELP};
+use crate::query::{normalize, Query};
 use enum_iterator::Sequence;
 use smbioslib::*;
 use std::{
@@ -14,7 +19,8 @@ use structopt::StructOpt;
 #[structopt(
     name = "dmidecode-rs",
     about = "DMI Table Decoder, Rust Edition ⛭",
-    author = "Jeffrey R. Gerber, Juan Zuluaga"
+    author = "Jeffrey R. Gerber, Juan Zuluaga",
+    after_help = EXIT_STATUS_HELP
 )]
 pub struct Opt {
     /// Less verbose output
@@ -26,35 +32,45 @@ pub struct Opt {
     #[structopt(short, long, name = "FILE", parse(from_os_str))]
     pub dev_mem: Option<PathBuf>,
 
-    /// Only display the value of the DMI string identified by `keyword`.
-    ///
-    /// `keyword` must be a keyword from the following list: bios-vendor,
-    /// bios-version, bios-release-date, system-manufacturer, system-
-    /// product-name, system-version, system-serial-number, system-uuid,
-    /// system-family, baseboard-manufacturer, baseboard-product-name,
-    /// baseboard-version, baseboard-serial-number, baseboard-asset-tag,
-    /// chassis-manufacturer, chassis-type, chassis-version, chassis-
-    /// serial-number, chassis-asset-tag, processor-family, processor-
-    /// manufacturer, processor-version, processor-frequency.  Each
-    /// keyword corresponds to a given DMI type and a given offset
-    /// within this entry type.  Not all strings may be meaningful or
-    /// even defined on all systems. Some keywords may return more than
-    /// one result on some systems (e.g.  processor-version on a multi-
-    /// processor system).  If KEYWORD is not provided or not valid, a
-    /// list of all valid keywords is printed and dmidecode exits with
-    /// an error.  This option cannot be used more than once.
-    ///
-    /// Note: on Linux, most of these strings can alternatively be read
-    /// directly from sysfs, typically from files under
-    /// /sys/devices/virtual/dmi/id.  Most of these files are even
-    /// readable by regular users.
-    #[structopt(short = "s", long = "string")]
+    /// Only display the value of the DMI string identified by KEYWORD
+    #[structopt(short = "s", long = "string", long_help = KEYWORD_HELP)]
     pub keyword: Option<Keyword>,
 
+    /// Only display the decoded fields selected by QUERY
+    ///
+    /// QUERY is a structure type, optional filters and an optional field:
+    /// "memory-device[*].part-number", "type17.speed",
+    /// "processor[socket-designation=CPU1].core-count". The type is a
+    /// structure name, typeN or a -t keyword; filters are [*], [INDEX],
+    /// [field=value], [field!=value] and [field~substring]. Names are
+    /// written in lower case with dashes. Displays one value per line, or
+    /// JSON with -j or --json-pretty; a query that matches nothing exits
+    /// with status 11 in both cases.
+    #[structopt(long, name = "QUERY")]
+    pub query: Option<Query>,
+
     /// Read the DMI data from a binary file
     #[structopt(long = "from-dump", parse(from_os_str))]
     pub input: Option<PathBuf>,
 
+    /// Read the DMI data from a hex dump, as printed by -u
+    ///
+    /// Both this tool's and dmidecode's -u output are accepted.
+    #[structopt(long = "from-hexdump", name = "HEXDUMP_FILE", parse(from_os_str))]
+    pub hexdump_input: Option<PathBuf>,
+
+    /// Read decoded values from a text report, as printed by default
+    ///
+    /// Reports from this tool and from any dmidecode version are accepted.
+    /// As a report holds no raw data, it can only be displayed again (as
+    /// text or JSON, filtered with --type or --handle) or compared with diff.
+    /// With -j or --json-pretty each structure is output as the report
+    /// gives it (handle, type, length, name and fields with their printed
+    /// values), not in the schema of -j on a table, whose typed values are
+    /// decoded from the raw bytes.
+    #[structopt(long = "from-text", name = "TEXT_FILE", parse(from_os_str))]
+    pub text_input: Option<PathBuf>,
+
     /// Dump the DMI data to a binary file
     #[structopt(long = "dump-bin", parse(from_os_str))]
     pub output: Option<PathBuf>,
@@ -62,7 +78,10 @@ pub struct Opt {
     /// Only display the entries of given type
     ///
     /// Supply one or more keywords, one or more type values,
-    /// or a combination of the two.
+    /// or a combination of the two.  Any type may also be given by its
+    /// name in lower case with dashes (memory-device, power-supply), as a
+    /// range (128-255), or prefixed with ! to exclude it (!oem).  Short
+    /// names: battery (22), ipmi (38), tpm (43), firmware-inventory (45).
     ///
     ///    Keyword     Types
     ///    ------------------------------
@@ -75,6 +94,7 @@ pub struct Opt {
     ///    cache       7
     ///    connector   8
     ///    slot        9
+    ///    oem         128-255
     #[structopt(short = "t", long = "type", verbatim_doc_comment)]
     pub bios_types: Option<Vec<BiosType>>,
 
@@ -83,6 +103,15 @@ pub struct Opt {
     #[structopt(short = "H", long = "handle")]
     pub handle: Option<Handle>,
 
+    /// Explain the entry whose handle matches `handle` byte by byte
+    ///
+    /// Lists every field of the formatted area with its offset, width,
+    /// specification name, raw value and decoded value, then the strings.
+    /// Bytes that are not described, or not defined by the table's SMBIOS
+    /// version, are flagged.
+    #[structopt(long, name = "EXPLAIN_HANDLE")]
+    pub explain: Option<Handle>,
+
     /// Do not decode the entries, dump their contents as hexadecimal
     /// instead.
     ///
@@ -116,6 +145,260 @@ pub struct Opt {
     /// Display output in JSON compact format.
     #[structopt(short, long)]
     pub json: bool,
+
+    /// Display the decoded fields in FORMAT: csv, tsv, prometheus or redfish
+    ///
+    /// csv and tsv: one row per structure and one column per field, for one
+    /// structure type; use -t to select it. Several types need --output-dir.
+    /// The columns of a type are the same on every system: a field the
+    /// structure does not have leaves its cell empty.
+    ///
+    /// prometheus: inventory and probe metrics in the text exposition
+    /// format. --output-dir writes them to dmi.prom for the node_exporter
+    /// textfile collector.
+    ///
+    /// redfish: DMTF Redfish resources (ComputerSystem, Bios, Processors,
+    /// Memory, Chassis, PCIeSlots) in JSON, keyed by @odata.id.
+    #[structopt(long, name = "FORMAT")]
+    pub format: Option<OutputFormat>,
+
+    /// Write one file per structure type, or dmi.prom, into DIR (with --format)
+    #[structopt(
+        long = "output-dir",
+        name = "OUTPUT_DIR",
+        parse(from_os_str),
+        requires = "FORMAT"
+    )]
+    pub output_dir: Option<PathBuf>,
+
+    /// Display the Computer Hardware IDs (CHIDs) used by Windows Update and fwupd
+    ///
+    /// Prints HardwareID-0 through HardwareID-14, computed the same way as
+    /// ComputerHardwareIds.exe, along with the values they are computed from.
+    #[structopt(long)]
+    pub hwids: bool,
+
+    /// Display the Linux DMI modalias (dmi:bvn...:) as built by the kernel
+    #[structopt(long)]
+    pub modalias: bool,
+
+    /// Write the files the Linux kernel exposes under /sys/class/dmi/id into DIR
+    #[structopt(long = "export-dmi-id", name = "DIR", parse(from_os_str))]
+    pub export_dmi_id: Option<PathBuf>,
+
+    /// Display the Redfish Host Interface (type 42) settings in FORMAT: json,
+    /// networkmanager or networkd
+    ///
+    /// json: the NIC (USB or PCI IDs, and MAC address when given), the host IP
+    /// assignment and the Redfish service address, port, VLAN and hostname.
+    /// networkmanager and networkd: a NetworkManager keyfile or
+    /// systemd-networkd .network file that brings up the in-band link to the
+    /// BMC, matching the NIC by its MAC address when it is known.
+    #[structopt(long = "redfish-host-interface", name = "HI_FORMAT")]
+    pub redfish_host_interface: Option<HostInterfaceFormat>,
+
+    /// Display the IPMI driver parameters from type 38 in FORMAT: modprobe,
+    /// cmdline, freeipmi or json
+    ///
+    /// modprobe: "options ipmi_si type= ports=|addrs= regspacings= irqs="
+    /// lines (ipmi_ssif for SSIF), for the kernel driver that OpenIPMI and
+    /// ipmitool -I open use. cmdline: the same as kernel command line
+    /// parameters. freeipmi: a freeipmi.conf driver configuration.
+    #[structopt(long = "ipmi-config", name = "IPMI_FORMAT")]
+    pub ipmi_config: Option<IpmiConfigFormat>,
+
+    /// Display the PCI devices in each slot (type 9) and at each onboard
+    /// device (type 41)
+    ///
+    /// The bus addresses are looked up under the sysfs tree (see
+    /// --sysfs-root) for the vendor and device IDs, class, driver, link width
+    /// and speed, and network interface names. Use -j or --json-pretty for
+    /// JSON.
+    #[structopt(long)]
+    pub pci: bool,
+
+    /// Report the size and content statistics of the table
+    ///
+    /// Shows the SMBIOS version and entry point, the number of structures
+    /// and the bytes of formatted areas and strings per type, the largest
+    /// structures, duplicated and empty strings, the handle range and its
+    /// gaps, and how much of the entry point's maximum table size is used.
+    /// The entry point is only known when reading the running system
+    /// through sysfs. Use -j or --json-pretty for JSON.
+    #[structopt(long)]
+    pub stats: bool,
+
+    /// Root of the sysfs tree used by --pci (default: /sys)
+    #[structopt(long = "sysfs-root", name = "SYSFS_ROOT", parse(from_os_str))]
+    pub sysfs_root: Option<PathBuf>,
+
+    /// Replace serial numbers, UUIDs, asset tags and addresses with fake values
+    ///
+    /// Applies to every output, including --dump-bin, so that tables can be
+    /// shared in bug reports. Fake values keep the length and format of the
+    /// values they replace, and the same value is always replaced the same
+    /// way. With --dump-bin, the table that was read (from the system or
+    /// --from-dump) is written.
+    ///
+    /// Fake values are derived with a secret key, so that short values
+    /// (a MAC address of a known vendor, a numeric serial number) cannot be
+    /// recovered by trying every candidate. Unless --redact-key is given, a
+    /// random key is created in the configuration directory
+    /// (~/.config/dmidecode-rs/redact.key) and the fake values stay the same
+    /// from one report to the next. Anyone holding the key can check guesses
+    /// of the original values: do not share it with the table.
+    #[structopt(long)]
+    pub redact: bool,
+
+    /// Key for the fake values of --redact, instead of the kept random key
+    #[structopt(long = "redact-key", name = "KEY", requires = "redact")]
+    pub redact_key: Option<String>,
+
+    /// Decode as SMBIOS version X.Y[.Z] instead of the version in the entry point
+    ///
+    /// Fields are decoded or skipped according to the SMBIOS version; use
+    /// this for tables without an entry point or from firmware that claims
+    /// the wrong version.
+    #[structopt(
+        long = "smbios-version",
+        name = "VERSION",
+        parse(try_from_str = parse_smbios_version)
+    )]
+    pub smbios_version: Option<SMBiosVersion>,
+
+    /// Decode what can be decoded of a damaged table
+    ///
+    /// Structures with a wrong length byte or cut short are skipped, and
+    /// decoding resumes at the next well-formed structure. Each damaged
+    /// region is reported with its offset and the reason, in the header of
+    /// the text output, as a warning, and as "corruption" in -j or
+    /// --json-pretty output.
+    #[structopt(long)]
+    pub recover: bool,
+
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Parses "X.Y" or "X.Y.Z"
+fn parse_smbios_version(s: &str) -> Result<SMBiosVersion, String> {
+    let parts = s
+        .split('.')
+        .map(|part| part.parse::<u8>())
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|_| format!("Invalid SMBIOS version: {}", s))?;
+    match parts[..] {
+        [major, minor] => Ok(SMBiosVersion {
+            major,
+            minor,
+            revision: 0,
+        }),
+        [major, minor, revision] => Ok(SMBiosVersion {
+            major,
+            minor,
+            revision,
+        }),
+        _ => Err(format!(
+            "Invalid SMBIOS version: {}, expected X.Y or X.Y.Z",
+            s
+        )),
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub enum Command {
+    /// Compare two SMBIOS tables
+    ///
+    /// Each table is a binary dump (see --dump-bin), a hex dump (see -u), a
+    /// text report, a directory containing the sysfs DMI and
+    /// smbios_entry_point files, or "live" for the running system.
+    /// Structures are matched by type and locator/designation, then by
+    /// handle. Exits with status 1 when the tables differ, as diff(1) does,
+    /// and 9 when a table cannot be loaded or decoded.
+    Diff {
+        /// Display the differences in JSON format
+        #[structopt(short, long)]
+        json: bool,
+
+        /// First (old) table
+        #[structopt(parse(from_os_str))]
+        a: PathBuf,
+
+        /// Second (new) table
+        #[structopt(parse(from_os_str))]
+        b: PathBuf,
+    },
+    /// Decode every table in a directory
+    ///
+    /// Each entry of DIR is one machine's table, in any form the diff
+    /// command accepts. Entries are decoded in parallel; -t and -H select
+    /// structures. Writes one JSON record per line per machine, keyed by file
+    /// name and system UUID, or with --format a combined csv or tsv
+    /// inventory. Entries that fail to load are listed on stderr, or in
+    /// --errors FILE as JSON lines, and make the exit status 10; a directory
+    /// that cannot be read is a failure of its own.
+    Batch {
+        /// Directory of tables
+        #[structopt(name = "DIR", parse(from_os_str))]
+        dir: PathBuf,
+
+        /// Number of threads (default: number of CPUs)
+        #[structopt(long, name = "N")]
+        jobs: Option<usize>,
+
+        /// Write the error report into FILE instead of stderr
+        #[structopt(long, name = "ERRORS_FILE", parse(from_os_str))]
+        errors: Option<PathBuf>,
+    },
+    /// Serve the decoded table over HTTP
+    ///
+    /// The table (from the system, --from-dump or --from-hexdump) is loaded
+    /// once. Endpoints: /summary, /structures, /structures/{handle},
+    /// /types/{type}, /keywords/{keyword} (see -s) and /metrics (see
+    /// --format prometheus).
+    Serve {
+        /// Address to listen on (127.0.0.1:8080), or the path of a Unix socket;
+        /// a socket left by a stopped server is replaced
+        #[structopt(long, name = "ADDRESS")]
+        listen: String,
+    },
+    /// Build a binary SMBIOS table from a JSON or YAML description
+    ///
+    /// The table is written in the --dump-bin format, which QEMU also
+    /// accepts with -smbios file=. The description is read as YAML when its
+    /// name ends in .yaml or .yml. Use --capture to describe an existing
+    /// table, edit the description and build it again: the output of -j
+    /// cannot be built, as it holds decoded values (names of enumerated
+    /// values, sizes, flags) rather than the bytes of the table, and does
+    /// not say which string numbers and reserved bytes the structures hold.
+    Build {
+        /// JSON or YAML description of the table
+        #[structopt(parse(from_os_str), required_unless = "SOURCE")]
+        spec: Option<PathBuf>,
+
+        /// Write the table to FILE
+        #[structopt(
+            short,
+            long,
+            name = "FILE",
+            parse(from_os_str),
+            required_unless = "SOURCE"
+        )]
+        output: Option<PathBuf>,
+
+        /// Also write an entry point for the table to EP_FILE
+        #[structopt(long = "entry-point", name = "EP_FILE", parse(from_os_str))]
+        entry_point: Option<PathBuf>,
+
+        /// Entry point format, 32 or 64 (default: 64 for SMBIOS 3.0 and later)
+        #[structopt(long = "entry-point-format")]
+        entry_point_format: Option<EntryPointFormat>,
+
+        /// Print the description of an existing table (a dump, a sysfs
+        /// directory or "live") instead of building one
+        #[structopt(long, name = "SOURCE", parse(from_os_str), conflicts_with = "spec")]
+        capture: Option<PathBuf>,
+    },
 }
 
 impl Opt {
@@ -124,20 +407,138 @@ impl Opt {
         self.quiet
             && self.dev_mem.is_none()
             && self.keyword.is_none()
+            && self.query.is_none()
             && self.input.is_none()
+            && self.hexdump_input.is_none()
+            && self.text_input.is_none()
             && self.output.is_none()
             && self.bios_types.is_none()
             && self.handle.is_none()
+            && self.explain.is_none()
             && self.oem_string.is_none()
             && !self.no_sysfs
             && !self.undefined_dump
             && !self.list
             && !self.json_pretty
             && !self.json
+            && !self.hwids
+            && !self.modalias
+            && self.export_dmi_id.is_none()
+            && self.redfish_host_interface.is_none()
+            && self.ipmi_config.is_none()
+            && !self.pci
+            && !self.stats
+            && self.sysfs_root.is_none()
+            && !self.redact
+            && self.redact_key.is_none()
+            && self.format.is_none()
+            && self.output_dir.is_none()
+            && self.smbios_version.is_none()
+            && !self.recover
+            && self.command.is_none()
+    }
+
+    /// Keeps the decoded structures selected with -t and -H
+    pub fn select_structs(&self, structs: Vec<DecodedStruct>) -> Vec<DecodedStruct> {
+        let types = self.selected_types();
+        structs
+            .into_iter()
+            .filter(|s| match types.as_ref() {
+                Some(types) => types.contains(&s.struct_type),
+                None => true,
+            })
+            .filter(|s| match self.handle.as_ref() {
+                Some(handle) => s.handle == **handle,
+                None => true,
+            })
+            .collect()
+    }
+
+    /// The DMI types selected with -t, if any
+    pub fn selected_types(&self) -> Option<Vec<u8>> {
+        self.bios_types
+            .as_ref()
+            .map(|types| BiosType::select(types))
+    }
+}
+
+/// Output format of --format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Csv,
+    Tsv,
+    Prometheus,
+    Redfish,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(OutputFormat::Csv),
+            "tsv" => Ok(OutputFormat::Tsv),
+            "prometheus" => Ok(OutputFormat::Prometheus),
+            "redfish" => Ok(OutputFormat::Redfish),
+            _ => Err(format!(
+                "Invalid format {}, expected csv, tsv, prometheus or redfish",
+                s
+            )),
+        }
+    }
+}
+
+/// Output format of --redfish-host-interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostInterfaceFormat {
+    Json,
+    NetworkManager,
+    Networkd,
+}
+
+impl FromStr for HostInterfaceFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(HostInterfaceFormat::Json),
+            "networkmanager" => Ok(HostInterfaceFormat::NetworkManager),
+            "networkd" => Ok(HostInterfaceFormat::Networkd),
+            _ => Err(format!(
+                "Invalid format {}, expected json, networkmanager or networkd",
+                s
+            )),
+        }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Output format of --ipmi-config
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpmiConfigFormat {
+    Modprobe,
+    Cmdline,
+    FreeIpmi,
+    Json,
+}
+
+impl FromStr for IpmiConfigFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "modprobe" => Ok(IpmiConfigFormat::Modprobe),
+            "cmdline" => Ok(IpmiConfigFormat::Cmdline),
+            "freeipmi" => Ok(IpmiConfigFormat::FreeIpmi),
+            "json" => Ok(IpmiConfigFormat::Json),
+            _ => Err(format!(
+                "Invalid format {}, expected modprobe, cmdline, freeipmi or json",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum BiosType {
     Bios,
     System,
@@ -149,24 +550,103 @@ pub enum BiosType {
     Connector,
     Slot,
     Numeric(u8),
+    /// Types from the first to the last, "128-255"
+    Range(u8, u8),
+    /// Every type except the given ones, "!oem"
+    Exclude(Box<BiosType>),
+}
+
+/// Short names of single types, besides the structure names
+const TYPE_ALIASES: &[(&str, u8)] = &[
+    ("battery", 22),
+    ("ipmi", 38),
+    ("tpm", 43),
+    ("firmware-inventory", 45),
+];
+
+/// Structure types with a name in the specification
+fn named_types() -> impl Iterator<Item = (String, u8)> {
+    (0..=127u8)
+        .map(|code| (dmi_smbios_structure_type(code), code))
+        .filter(|(name, _)| !name.starts_with(OUT_OF_SPEC))
+        .map(|(name, code)| (normalize(&name), code))
+}
+
+/// Every name accepted by -t, for the error message
+fn type_names() -> Vec<String> {
+    let mut names: Vec<String> = [
+        "bios",
+        "system",
+        "baseboard",
+        "chassis",
+        "processor",
+        "memory",
+        "cache",
+        "connector",
+        "slot",
+        "oem",
+    ]
+    .iter()
+    .chain(TYPE_ALIASES.iter().map(|(name, _)| name))
+    .map(|name| name.to_string())
+    .collect();
+    for (name, _) in named_types() {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
 }
 
 impl FromStr for BiosType {
-    type Err = std::num::ParseIntError;
+    type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "bios" => Ok(BiosType::Bios),
-            "system" => Ok(BiosType::System),
-            "baseboard" => Ok(BiosType::Baseboard),
-            "chassis" => Ok(BiosType::Chassis),
-            "processor" => Ok(BiosType::Processor),
-            "memory" => Ok(BiosType::Memory),
-            "cache" => Ok(BiosType::Cache),
-            "connector" => Ok(BiosType::Connector),
-            "slot" => Ok(BiosType::Slot),
-            _ => Ok(BiosType::Numeric(u8::from_str(s)?)),
+        if let Some(excluded) = s.strip_prefix('!') {
+            return Ok(BiosType::Exclude(Box::new(BiosType::from_str(excluded)?)));
+        }
+        let name = normalize(s);
+        match name.as_str() {
+            "bios" => return Ok(BiosType::Bios),
+            "system" => return Ok(BiosType::System),
+            "baseboard" => return Ok(BiosType::Baseboard),
+            "chassis" => return Ok(BiosType::Chassis),
+            "processor" => return Ok(BiosType::Processor),
+            "memory" => return Ok(BiosType::Memory),
+            "cache" => return Ok(BiosType::Cache),
+            "connector" => return Ok(BiosType::Connector),
+            "slot" => return Ok(BiosType::Slot),
+            "oem" => return Ok(BiosType::Range(128, 255)),
+            _ => (),
         }
+        if let Ok(number) = u8::from_str(s.trim()) {
+            return Ok(BiosType::Numeric(number));
+        }
+        if let Some((first, last)) = s.trim().split_once('-') {
+            if let (Ok(first), Ok(last)) = (u8::from_str(first), u8::from_str(last)) {
+                return match first <= last {
+                    true => Ok(BiosType::Range(first, last)),
+                    false => Err(format!(
+                        "Invalid type range {}, {} is above {}",
+                        s, first, last
+                    )),
+                };
+            }
+        }
+        TYPE_ALIASES
+            .iter()
+            .map(|(alias, code)| (alias.to_string(), *code))
+            .chain(named_types())
+            .find(|(type_name, _)| *type_name == name)
+            .map(|(_, code)| BiosType::Numeric(code))
+            .ok_or_else(|| {
+                format!(
+                    "Invalid type {}, expected a type number from 0 to 255, a range such as \
+                     128-255, a type prefixed with ! to exclude it, or one of: {}",
+                    s,
+                    type_names().join(", ")
+                )
+            })
     }
 }
 
@@ -182,6 +662,7 @@ impl FromStr for BiosType {
        cache       7
        connector   8
        slot        9
+       oem         128-255
 */
 
 impl IntoIterator for BiosType {
@@ -200,20 +681,48 @@ impl IntoIterator for BiosType {
             BiosType::Connector => vec![8].into_iter(),
             BiosType::Slot => vec![9].into_iter(),
             BiosType::Numeric(number) => vec![number].into_iter(),
+            BiosType::Range(first, last) => (first..=last).collect::<Vec<_>>().into_iter(),
+            BiosType::Exclude(excluded) => {
+                let excluded: Vec<u8> = excluded.into_iter().collect();
+                (0..=255u8)
+                    .filter(|code| !excluded.contains(code))
+                    .collect::<Vec<_>>()
+                    .into_iter()
+            }
         }
     }
 }
 
 impl BiosType {
-    // We could make this return something, or, could create a type as a collection containing Vec<BiosType> and
-    // then implement methods for that type to perform more advanced I/O via state.
-    // More than likely the style of output will be desirable to change (verbose, debug, JSON, etc).
-    #[allow(unused)]
-    pub fn parse_and_display(types: &[BiosType], data: &SMBiosData, quiet: bool) {
-        let unique_types: HashSet<u8> = types
+    /// The types selected by several -t arguments: those included, or all
+    /// types when only exclusions are given, less those excluded
+    pub fn select(types: &[BiosType]) -> Vec<u8> {
+        let included: HashSet<u8> = types
             .iter()
-            .flat_map(|bios_type| bios_type.into_iter())
+            .filter(|bios_type| !matches!(bios_type, BiosType::Exclude(_)))
+            .flat_map(|bios_type| bios_type.clone().into_iter())
             .collect();
+        let excluded: HashSet<u8> = types
+            .iter()
+            .filter_map(|bios_type| match bios_type {
+                BiosType::Exclude(excluded) => Some(excluded.as_ref().clone().into_iter()),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        (0..=255u8)
+            .filter(|code| included.is_empty() || included.contains(code))
+            .filter(|code| !excluded.contains(code))
+            .collect()
+    }
+
+    // We could make this return something, or, could create a type as a collection containing
+    // Vec<BiosType> and then implement methods for that type to perform more advanced I/O via
+    // state. More than likely the style of output will be desirable to change (verbose, debug,
+    // JSON, etc).
+    #[allow(unused)]
+    pub fn parse_and_display(types: &[BiosType], data: &SMBiosData, quiet: bool) {
+        let unique_types: HashSet<u8> = BiosType::select(types).into_iter().collect();
 
         let mut first = true;
         for undefined_struct in data.iter().filter(|undefined_struct| {
@@ -229,444 +738,297 @@ impl BiosType {
     }
 }
 
-#[derive(Debug, StructOpt, Sequence)]
-pub enum Keyword {
-    BiosVendor,
-    BiosVersion,
-    BiosReleaseDate,
-    BiosRevision,
-    FirmwareRevision,
-    SystemManufacturer,
-    SystemProductName,
-    SystemVersion,
-    SystemSerialNumber,
-    SystemUuid,
-    SystemSkuNumber,
-    SystemFamily,
-    BaseboardManufacturer,
-    BaseboardProductName,
-    BaseboardVersion,
-    BaseboardSerialNumber,
-    BaseboardAssetTag,
-    ChassisManufacturer,
-    ChassisType,
-    ChassisVersion,
-    ChassisSerialNumber,
-    ChassisAssetTag,
-    ProcessorFamily,
-    ProcessorManufacturer,
-    ProcessorVersion,
-    ProcessorFrequency,
-}
-
-const BIOS_VENDOR: &'static str = "bios-vendor";
-const BIOS_VERSION: &'static str = "bios-version";
-const BIOS_RELEASE_DATE: &'static str = "bios-release-date";
-const BIOS_REVISION: &'static str = "bios-revision";
-const FIRMWARE_REVISION: &'static str = "firmware-revision";
-const SYSTEM_MANUFACTURER: &'static str = "system-manufacturer";
-const SYSTEM_PRODUCT_NAME: &'static str = "system-product-name";
-const SYSTEM_VERSION: &'static str = "system-version";
-const SYSTEM_SERIAL_NUMBER: &'static str = "system-serial-number";
-const SYSTEM_UUID: &'static str = "system-uuid";
-const SYSTEM_SKU_NUMBER: &'static str = "system-sku-number";
-const SYSTEM_FAMILY: &'static str = "system-family";
-const BASEBOARD_MANUFACTURER: &'static str = "baseboard-manufacturer";
-const BASEBOARD_PRODUCT_NAME: &'static str = "baseboard-product-name";
-const BASEBOARD_VERSION: &'static str = "baseboard-version";
-const BASEBOARD_SERIAL_NUMBER: &'static str = "baseboard-serial-number";
-const BASEBOARD_ASSET_TAG: &'static str = "baseboard-asset-tag";
-const CHASSIS_MANUFACTURER: &'static str = "chassis-manufacturer";
-const CHASSIS_TYPE: &'static str = "chassis-type";
-const CHASSIS_VERSION: &'static str = "chassis-version";
-const CHASSIS_SERIAL_NUMBER: &'static str = "chassis-serial-number";
-const CHASSIS_ASSET_TAG: &'static str = "chassis-asset-tag";
-const PROCESSOR_FAMILY: &'static str = "processor-family";
-const PROCESSOR_MANUFACTURER: &'static str = "processor-manufacturer";
-const PROCESSOR_VERSION: &'static str = "processor-version";
-const PROCESSOR_FREQUENCY: &'static str = "processor-frequency";
-
-impl FromStr for Keyword {
-    type Err = std::io::Error;
+/// Declares the `-s` keywords from one table: the enum, the names used by
+/// FromStr, Display, --list and the help text, and how the value of each
+/// keyword is read from the table: `$value(args)` calls `$value(data, args)`.
+macro_rules! keywords {
+    ($($variant:ident => $name:literal, $value:ident($($arg:expr),*);)*) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum Keyword {
+            $($variant,)*
+            /// OEM string N (type 11), "oem-string-N"
+            OemString(u8),
+        }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            BIOS_VENDOR => Ok(Keyword::BiosVendor),
-            BIOS_VERSION => Ok(Keyword::BiosVersion),
-            BIOS_RELEASE_DATE => Ok(Keyword::BiosReleaseDate),
-            BIOS_REVISION => Ok(Keyword::BiosRevision),
-            FIRMWARE_REVISION => Ok(Keyword::FirmwareRevision),
-            SYSTEM_MANUFACTURER => Ok(Keyword::SystemManufacturer),
-            SYSTEM_PRODUCT_NAME => Ok(Keyword::SystemProductName),
-            SYSTEM_VERSION => Ok(Keyword::SystemVersion),
-            SYSTEM_SERIAL_NUMBER => Ok(Keyword::SystemSerialNumber),
-            SYSTEM_UUID => Ok(Keyword::SystemUuid),
-            SYSTEM_SKU_NUMBER => Ok(Keyword::SystemSkuNumber),
-            SYSTEM_FAMILY => Ok(Keyword::SystemFamily),
-            BASEBOARD_MANUFACTURER => Ok(Keyword::BaseboardManufacturer),
-            BASEBOARD_PRODUCT_NAME => Ok(Keyword::BaseboardProductName),
-            BASEBOARD_VERSION => Ok(Keyword::BaseboardVersion),
-            BASEBOARD_SERIAL_NUMBER => Ok(Keyword::BaseboardSerialNumber),
-            BASEBOARD_ASSET_TAG => Ok(Keyword::BaseboardAssetTag),
-            CHASSIS_MANUFACTURER => Ok(Keyword::ChassisManufacturer),
-            CHASSIS_TYPE => Ok(Keyword::ChassisType),
-            CHASSIS_VERSION => Ok(Keyword::ChassisVersion),
-            CHASSIS_SERIAL_NUMBER => Ok(Keyword::ChassisSerialNumber),
-            CHASSIS_ASSET_TAG => Ok(Keyword::ChassisAssetTag),
-            PROCESSOR_FAMILY => Ok(Keyword::ProcessorFamily),
-            PROCESSOR_MANUFACTURER => Ok(Keyword::ProcessorManufacturer),
-            PROCESSOR_VERSION => Ok(Keyword::ProcessorVersion),
-            PROCESSOR_FREQUENCY => Ok(Keyword::ProcessorFrequency),
-            _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, s)),
+        /// Keywords in --list order; oem-string-N is not listed
+        const KEYWORDS: &[Keyword] = &[$(Keyword::$variant),*];
+
+        /// Long help of -s
+        const KEYWORD_HELP: &str = concat!(
+            "Only display the value of the DMI string identified by `keyword`.\n\n",
+            "`keyword` must be a keyword from the following list: ",
+            $($name, ", ",)*
+            "oem-string-N (the OEM string number N, from 1).  Each keyword corresponds ",
+            "to a given DMI type and a given offset within this entry type.  Not all ",
+            "strings may be meaningful or even defined on all systems. Some keywords ",
+            "may return more than one result on some systems (e.g. processor-version ",
+            "on a multi-processor system).  If KEYWORD is not provided or not valid, a ",
+            "list of all valid keywords is printed and dmidecode exits with an error.  ",
+            "This option cannot be used more than once.\n\n",
+            "Note: on Linux, most of these strings can alternatively be read directly ",
+            "from sysfs, typically from files under /sys/devices/virtual/dmi/id.  Most ",
+            "of these files are even readable by regular users."
+        );
+
+        impl Keyword {
+            /// Value of the keyword, one line per structure for the types
+            /// that may have several
+            fn value(&self, data: &SMBiosData) -> Option<String> {
+                match self {
+                    $(Keyword::$variant => $value(data, $($arg),*),)*
+                    Keyword::OemString(index) => first(data, |oem_strings: SMBiosOemStrings<'_>| {
+                        oem_strings.oem_strings().get_string(*index).to_utf8_lossy()
+                    }),
+                }
+            }
+        }
+
+        impl FromStr for Keyword {
+            type Err = std::io::Error;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $($name => Ok(Keyword::$variant),)*
+                    _ => match s.strip_prefix("oem-string-").map(u8::from_str) {
+                        Some(Ok(index)) if index > 0 => Ok(Keyword::OemString(index)),
+                        _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, s)),
+                    },
+                }
+            }
         }
+
+        impl Display for Keyword {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                match self {
+                    $(Keyword::$variant => write!(f, "{}", $name),)*
+                    Keyword::OemString(index) => write!(f, "oem-string-{}", index),
+                }
+            }
+        }
+    };
+}
+
+// BIOS Information (type 0) and System Information (type 1) are single
+// instance: the first structure gives the value.  Baseboard (type 2), Chassis
+// (type 3) and Processor (type 4) Information may have several instances,
+// and give one line per structure.
+keywords! {
+    BiosVendor => "bios-vendor", first_string(|bios: SMBiosInformation<'_>| bios.vendor());
+    BiosVersion => "bios-version", first_string(|bios: SMBiosInformation<'_>| bios.version());
+    BiosReleaseDate => "bios-release-date",
+        first_string(|bios: SMBiosInformation<'_>| bios.release_date());
+    BiosRevision => "bios-revision", first(|bios: SMBiosInformation<'_>| {
+        release(bios.system_bios_major_release(), bios.system_bios_minor_release())
+    });
+    FirmwareRevision => "firmware-revision", first(|bios: SMBiosInformation<'_>| {
+        release(bios.e_c_firmware_major_release(), bios.e_c_firmware_minor_release())
+    });
+    BiosRomSize => "bios-rom-size", field(0, "ROM Size");
+    BiosCharacteristics => "bios-characteristics", field(0, "Characteristics");
+    SystemManufacturer => "system-manufacturer",
+        first_string(|system: SMBiosSystemInformation<'_>| system.manufacturer());
+    SystemProductName => "system-product-name",
+        first_string(|system: SMBiosSystemInformation<'_>| system.product_name());
+    SystemVersion => "system-version",
+        first_string(|system: SMBiosSystemInformation<'_>| system.version());
+    SystemSerialNumber => "system-serial-number",
+        first_string(|system: SMBiosSystemInformation<'_>| system.serial_number());
+    SystemUuid => "system-uuid",
+        first(|system: SMBiosSystemInformation<'_>| system.uuid().map(|uuid| uuid.to_string()));
+    SystemSkuNumber => "system-sku-number",
+        first_string(|system: SMBiosSystemInformation<'_>| system.sku_number());
+    SystemFamily => "system-family",
+        first_string(|system: SMBiosSystemInformation<'_>| system.family());
+    SystemWakeUpType => "system-wake-up-type", field(1, "Wake-up Type");
+    BaseboardManufacturer => "baseboard-manufacturer",
+        each_string(|baseboard: SMBiosBaseboardInformation<'_>| baseboard.manufacturer());
+    BaseboardProductName => "baseboard-product-name",
+        each_string(|baseboard: SMBiosBaseboardInformation<'_>| baseboard.product());
+    BaseboardVersion => "baseboard-version",
+        each_string(|baseboard: SMBiosBaseboardInformation<'_>| baseboard.version());
+    BaseboardSerialNumber => "baseboard-serial-number",
+        each_string(|baseboard: SMBiosBaseboardInformation<'_>| baseboard.serial_number());
+    BaseboardAssetTag => "baseboard-asset-tag",
+        each_string(|baseboard: SMBiosBaseboardInformation<'_>| baseboard.asset_tag());
+    BaseboardType => "baseboard-type", field(2, "Type");
+    ChassisManufacturer => "chassis-manufacturer",
+        each_string(|chassis: SMBiosSystemChassisInformation<'_>| chassis.manufacturer());
+    ChassisType => "chassis-type", each(|chassis: SMBiosSystemChassisInformation<'_>| {
+        chassis.chassis_type().map(|chassis_type| chassis_type.to_string())
+    });
+    ChassisVersion => "chassis-version",
+        each_string(|chassis: SMBiosSystemChassisInformation<'_>| chassis.version());
+    ChassisSerialNumber => "chassis-serial-number",
+        each_string(|chassis: SMBiosSystemChassisInformation<'_>| chassis.serial_number());
+    ChassisAssetTag => "chassis-asset-tag",
+        each_string(|chassis: SMBiosSystemChassisInformation<'_>| chassis.asset_tag_number());
+    ChassisSku => "chassis-sku", field(3, "SKU Number");
+    ProcessorFamily => "processor-family", each(processor_family);
+    ProcessorManufacturer => "processor-manufacturer", each_string(
+        |processor: SMBiosProcessorInformation<'_>| processor.processor_manufacturer()
+    );
+    ProcessorVersion => "processor-version", each_string(
+        |processor: SMBiosProcessorInformation<'_>| processor.processor_version()
+    );
+    ProcessorFrequency => "processor-frequency", each(|processor: SMBiosProcessorInformation<'_>| {
+        processor.current_speed().map(|speed| match speed {
+            ProcessorSpeed::Unknown => String::from("Unknown"),
+            ProcessorSpeed::MHz(frequency) => format!("{} MHz", frequency),
+        })
+    });
+    ProcessorSocket => "processor-socket", field(4, "Socket Designation");
+    ProcessorMaxSpeed => "processor-max-speed", field(4, "Max Speed");
+    ProcessorCoreCount => "processor-core-count", field(4, "Core Count");
+    ProcessorThreadCount => "processor-thread-count", field(4, "Thread Count");
+    MemoryMaximumCapacity => "memory-maximum-capacity", field(16, "Maximum Capacity");
+    MemoryDeviceCount => "memory-device-count", memory_device_count();
+    MemoryTotalSize => "memory-total-size", memory_total_size();
+    TpmVendor => "tpm-vendor", field(43, "Vendor ID");
+    TpmSpecificationVersion => "tpm-specification-version", field(43, "Specification Version");
+    TpmFirmwareRevision => "tpm-firmware-revision", field(43, "Firmware Revision");
+}
+
+impl Sequence for Keyword {
+    const CARDINALITY: usize = KEYWORDS.len();
+
+    fn next(&self) -> Option<Self> {
+        let index = KEYWORDS.iter().position(|keyword| keyword == self)?;
+        KEYWORDS.get(index + 1).copied()
+    }
+
+    fn previous(&self) -> Option<Self> {
+        let index = KEYWORDS.iter().position(|keyword| keyword == self)?;
+        KEYWORDS.get(index.checked_sub(1)?).copied()
+    }
+
+    fn first() -> Option<Self> {
+        KEYWORDS.first().copied()
+    }
+
+    fn last() -> Option<Self> {
+        KEYWORDS.last().copied()
     }
 }
 
-impl Display for Keyword {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        match self {
-            Keyword::BiosVendor => write!(f, "{}", BIOS_VENDOR),
-            Keyword::BiosVersion => write!(f, "{}", BIOS_VERSION),
-            Keyword::BiosReleaseDate => write!(f, "{}", BIOS_RELEASE_DATE),
-            Keyword::BiosRevision => write!(f, "{}", BIOS_REVISION),
-            Keyword::FirmwareRevision => write!(f, "{}", FIRMWARE_REVISION),
-            Keyword::SystemManufacturer => write!(f, "{}", SYSTEM_MANUFACTURER),
-            Keyword::SystemProductName => write!(f, "{}", SYSTEM_PRODUCT_NAME),
-            Keyword::SystemVersion => write!(f, "{}", SYSTEM_VERSION),
-            Keyword::SystemSerialNumber => write!(f, "{}", SYSTEM_SERIAL_NUMBER),
-            Keyword::SystemUuid => write!(f, "{}", SYSTEM_UUID),
-            Keyword::SystemSkuNumber => write!(f, "{}", SYSTEM_SKU_NUMBER),
-            Keyword::SystemFamily => write!(f, "{}", SYSTEM_FAMILY),
-            Keyword::BaseboardManufacturer => write!(f, "{}", BASEBOARD_MANUFACTURER),
-            Keyword::BaseboardProductName => write!(f, "{}", BASEBOARD_PRODUCT_NAME),
-            Keyword::BaseboardVersion => write!(f, "{}", BASEBOARD_VERSION),
-            Keyword::BaseboardSerialNumber => write!(f, "{}", BASEBOARD_SERIAL_NUMBER),
-            Keyword::BaseboardAssetTag => write!(f, "{}", BASEBOARD_ASSET_TAG),
-            Keyword::ChassisManufacturer => write!(f, "{}", CHASSIS_MANUFACTURER),
-            Keyword::ChassisType => write!(f, "{}", CHASSIS_TYPE),
-            Keyword::ChassisVersion => write!(f, "{}", CHASSIS_VERSION),
-            Keyword::ChassisSerialNumber => write!(f, "{}", CHASSIS_SERIAL_NUMBER),
-            Keyword::ChassisAssetTag => write!(f, "{}", CHASSIS_ASSET_TAG),
-            Keyword::ProcessorFamily => write!(f, "{}", PROCESSOR_FAMILY),
-            Keyword::ProcessorManufacturer => write!(f, "{}", PROCESSOR_MANUFACTURER),
-            Keyword::ProcessorVersion => write!(f, "{}", PROCESSOR_VERSION),
-            Keyword::ProcessorFrequency => write!(f, "{}", PROCESSOR_FREQUENCY),
-        }
+/// Formats a size in bytes the way the decoder does: the largest unit that
+/// divides it ("16 GB", "512 MB")
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 7] = ["bytes", "kB", "MB", "GB", "TB", "PB", "EB"];
+    let mut size = bytes;
+    let mut unit = 0;
+    while size != 0 && size & 0x3FF == 0 && unit + 1 < UNITS.len() {
+        size >>= 10;
+        unit += 1;
     }
+    format!("{} {}", size, UNITS[unit])
 }
 
 impl Keyword {
-    #[allow(unused)]
     pub fn parse(&self, data: &SMBiosData) -> Result<String, BiosParseError> {
-        // Note: Some structures are single instance and some can be multi-instance.
-        // Therefore, multiple strings may be returned in some cases.
-        //
-        // BIOS Information (type 0): single
-        // System Information (type 1): single
-        // Baseboard Information (type 2): multi
-        // Chassis Information (type 3): multi
-        // Processor Information (type 4): multi
+        self.value(data)
+            .ok_or_else(|| BiosParseError::KeywordNotFound(self.to_string()))
+    }
+}
 
-        match self {
-            Keyword::BiosVendor => data
-                .find_map(|bios_info: SMBiosInformation<'_>| bios_info.vendor().to_utf8_lossy())
-                .ok_or(BiosParseError::BiosVendorNotFound),
-            Keyword::BiosVersion => data
-                .find_map(|bios_info: SMBiosInformation<'_>| bios_info.version().to_utf8_lossy())
-                .ok_or(BiosParseError::BiosVersionNotFound),
-            Keyword::BiosReleaseDate => data
-                .find_map(|bios_info: SMBiosInformation<'_>| {
-                    bios_info.release_date().to_utf8_lossy()
-                })
-                .ok_or(BiosParseError::BiosReleaseDateNotFound),
-            Keyword::BiosRevision => data
-                .find_map(|bios_info: SMBiosInformation<'_>| {
-                    match (
-                        bios_info.system_bios_major_release(),
-                        bios_info.system_bios_minor_release(),
-                    ) {
-                        (Some(major), Some(minor)) => Some(format!("{}.{}", major, minor)),
-                        _ => None,
-                    }
-                })
-                .ok_or(BiosParseError::BiosRevisionNotFound),
-            Keyword::FirmwareRevision => data
-                .find_map(|bios_info: SMBiosInformation<'_>| {
-                    match (
-                        bios_info.e_c_firmware_major_release(),
-                        bios_info.e_c_firmware_minor_release(),
-                    ) {
-                        (Some(major), Some(minor)) => Some(format!("{}.{}", major, minor)),
-                        _ => None,
-                    }
-                })
-                .ok_or(BiosParseError::FirmwareRevisionNotFound),
-            Keyword::SystemManufacturer => data
-                .find_map(|system_info: SMBiosSystemInformation<'_>| {
-                    system_info.manufacturer().to_utf8_lossy()
-                })
-                .ok_or(BiosParseError::SystemManufacturerNotFound),
-            Keyword::SystemProductName => data
-                .find_map(|system_info: SMBiosSystemInformation<'_>| {
-                    system_info.product_name().to_utf8_lossy()
-                })
-                .ok_or(BiosParseError::SystemProductNameNotFound),
-            Keyword::SystemVersion => data
-                .find_map(|system_info: SMBiosSystemInformation<'_>| {
-                    system_info.version().to_utf8_lossy()
-                })
-                .ok_or(BiosParseError::SystemVersionNotFound),
-            Keyword::SystemSerialNumber => data
-                .find_map(|system_info: SMBiosSystemInformation<'_>| {
-                    system_info.serial_number().to_utf8_lossy()
-                })
-                .ok_or(BiosParseError::SystemSerialNumberNotFound),
-            Keyword::SystemUuid => {
-                match data.find_map(|system_info: SMBiosSystemInformation<'_>| system_info.uuid()) {
-                    // SystemUuidData is an enum that can be broken down further if desired
-                    Some(uuid) => Ok(format!("{}", uuid)),
-                    None => Err(BiosParseError::SystemUuidNotFound),
-                }
-            }
-            Keyword::SystemSkuNumber => data
-                .find_map(|system_info: SMBiosSystemInformation<'_>| {
-                    system_info.sku_number().to_utf8_lossy()
-                })
-                .ok_or(BiosParseError::SystemSkuNumberNotFound),
-            Keyword::SystemFamily => data
-                .find_map(|system_info: SMBiosSystemInformation<'_>| {
-                    system_info.family().to_utf8_lossy()
-                })
-                .ok_or(BiosParseError::SystemFamilyNotFound),
-            Keyword::BaseboardManufacturer => data
-                .map(|baseboard_info: SMBiosBaseboardInformation<'_>| {
-                    baseboard_info.manufacturer().to_utf8_lossy()
-                })
-                .try_fold(String::new(), |mut acc, item| match item {
-                    Some(val) => Some({
-                        if !acc.is_empty() {
-                            acc.push_str("\n");
-                        };
-                        acc.push_str(&val);
-                        acc
-                    }),
-                    None => None,
-                })
-                .ok_or(BiosParseError::BaseboardManufacturerNotFound),
-            Keyword::BaseboardProductName => data
-                .map(|baseboard_info: SMBiosBaseboardInformation<'_>| {
-                    baseboard_info.product().to_utf8_lossy()
-                })
-                .try_fold(String::new(), |mut acc, item| match item {
-                    Some(val) => Some({
-                        if !acc.is_empty() {
-                            acc.push_str("\n");
-                        };
-                        acc.push_str(&val);
-                        acc
-                    }),
-                    None => None,
-                })
-                .ok_or(BiosParseError::BaseboardProductNameNotFound),
-            Keyword::BaseboardVersion => data
-                .map(|baseboard_info: SMBiosBaseboardInformation<'_>| {
-                    baseboard_info.version().to_utf8_lossy()
-                })
-                .try_fold(String::new(), |mut acc, item| match item {
-                    Some(val) => Some({
-                        if !acc.is_empty() {
-                            acc.push_str("\n");
-                        };
-                        acc.push_str(&val);
-                        acc
-                    }),
-                    None => None,
-                })
-                .ok_or(BiosParseError::BaseboardVersionNotFound),
-            Keyword::BaseboardSerialNumber => data
-                .map(|baseboard_info: SMBiosBaseboardInformation<'_>| {
-                    baseboard_info.serial_number().to_utf8_lossy()
-                })
-                .try_fold(String::new(), |mut acc, item| match item {
-                    Some(val) => Some({
-                        if !acc.is_empty() {
-                            acc.push_str("\n");
-                        };
-                        acc.push_str(&val);
-                        acc
-                    }),
-                    None => None,
-                })
-                .ok_or(BiosParseError::BaseboardSerialNumberNotFound),
-            Keyword::BaseboardAssetTag => data
-                .map(|baseboard_info: SMBiosBaseboardInformation<'_>| {
-                    baseboard_info.asset_tag().to_utf8_lossy()
-                })
-                .try_fold(String::new(), |mut acc, item| match item {
-                    Some(val) => Some({
-                        if !acc.is_empty() {
-                            acc.push_str("\n");
-                        };
-                        acc.push_str(&val);
-                        acc
-                    }),
-                    None => None,
-                })
-                .ok_or(BiosParseError::BaseboardAssetTagNotFound),
-            Keyword::ChassisManufacturer => data
-                .map(|chassis_info: SMBiosSystemChassisInformation<'_>| {
-                    chassis_info.manufacturer().to_utf8_lossy()
-                })
-                .try_fold(String::new(), |mut acc, item| match item {
-                    Some(val) => Some({
-                        if !acc.is_empty() {
-                            acc.push_str("\n");
-                        };
-                        acc.push_str(&val);
-                        acc
-                    }),
-                    None => None,
-                })
-                .ok_or(BiosParseError::ChassisManufacturerNotFound),
-            Keyword::ChassisType => data
-                .map(|chassis_info: SMBiosSystemChassisInformation<'_>| chassis_info.chassis_type())
-                .try_fold(String::new(), |mut acc, item| match item {
-                    Some(val) => Some({
-                        if !acc.is_empty() {
-                            acc.push_str("\n");
-                        };
-                        acc.push_str(&format!("{}", &val).to_string());
-                        acc
-                    }),
-                    None => None,
-                })
-                .ok_or(BiosParseError::ChassisTypeNotFound),
-            Keyword::ChassisVersion => data
-                .map(|chassis_info: SMBiosSystemChassisInformation<'_>| {
-                    chassis_info.version().to_utf8_lossy()
-                })
-                .try_fold(String::new(), |mut acc, item| match item {
-                    Some(val) => Some({
-                        if !acc.is_empty() {
-                            acc.push_str("\n");
-                        };
-                        acc.push_str(&val);
-                        acc
-                    }),
-                    None => None,
-                })
-                .ok_or(BiosParseError::ChassisVersionNotFound),
-            Keyword::ChassisSerialNumber => data
-                .map(|chassis_info: SMBiosSystemChassisInformation<'_>| {
-                    chassis_info.serial_number().to_utf8_lossy()
-                })
-                .try_fold(String::new(), |mut acc, item| match item {
-                    Some(val) => Some({
-                        if !acc.is_empty() {
-                            acc.push_str("\n");
-                        };
-                        acc.push_str(&val);
-                        acc
-                    }),
-                    None => None,
-                })
-                .ok_or(BiosParseError::ChassisSerialNumberNotFound),
-            Keyword::ChassisAssetTag => data
-                .map(|chassis_info: SMBiosSystemChassisInformation<'_>| {
-                    chassis_info.asset_tag_number().to_utf8_lossy()
-                })
-                .try_fold(String::new(), |mut acc, item| match item {
-                    Some(val) => Some({
-                        if !acc.is_empty() {
-                            acc.push_str("\n");
-                        };
-                        acc.push_str(&val);
-                        acc
-                    }),
-                    None => None,
-                })
-                .ok_or(BiosParseError::ChassisAssetTagNotFound),
-            Keyword::ProcessorFamily => data
-                .map(|processor_info: SMBiosProcessorInformation<'_>| {
-                    if let Some(family) = processor_info.processor_family() {
-                        match family.value {
-                            ProcessorFamily::SeeProcessorFamily2 => {
-                                if let Some(family2) = processor_info.processor_family_2() {
-                                    Some(format!("{}", family2))
-                                } else {
-                                    None
-                                }
-                            }
-                            _ => Some(format!("{}", family)),
-                        }
-                    } else {
-                        None
-                    }
-                })
-                .try_fold(String::new(), |mut acc, item| match item {
-                    Some(val) => Some({
-                        if !acc.is_empty() {
-                            acc.push_str("\n");
-                        };
-                        acc.push_str(&val);
-                        acc
-                    }),
-                    None => None,
-                })
-                .ok_or(BiosParseError::ProcessorFamilyNotFound),
-            Keyword::ProcessorManufacturer => data
-                .map(|processor_info: SMBiosProcessorInformation<'_>| {
-                    processor_info.processor_manufacturer().to_utf8_lossy()
-                })
-                .try_fold(String::new(), |mut acc, item| match item {
-                    Some(val) => Some({
-                        if !acc.is_empty() {
-                            acc.push_str("\n");
-                        };
-                        acc.push_str(&val);
-                        acc
-                    }),
-                    None => None,
-                })
-                .ok_or(BiosParseError::ProcessorManufacturerNotFound),
-            Keyword::ProcessorVersion => data
-                .map(|processor_info: SMBiosProcessorInformation<'_>| {
-                    processor_info.processor_version().to_utf8_lossy()
-                })
-                .try_fold(String::new(), |mut acc, item| match item {
-                    Some(val) => Some({
-                        if !acc.is_empty() {
-                            acc.push_str("\n");
-                        };
-                        acc.push_str(&val);
-                        acc
-                    }),
-                    None => None,
-                })
-                .ok_or(BiosParseError::ProcessorVersionNotFound),
-            Keyword::ProcessorFrequency => data
-                .map(|processor_info: SMBiosProcessorInformation<'_>| {
-                    processor_info.current_speed()
-                })
-                .try_fold(String::new(), |mut acc, item| match item {
-                    Some(val) => Some({
-                        if !acc.is_empty() {
-                            acc.push_str("\n");
-                        };
-                        let output = match &val {
-                            ProcessorSpeed::Unknown => String::from("Unknown"),
-                            ProcessorSpeed::MHz(frequency) => format!("{} MHz", frequency),
-                        };
-                        acc.push_str(output.as_str());
-                        acc
-                    }),
-                    None => None,
-                })
-                .ok_or(BiosParseError::ProcessorFrequencyNotFound),
-        }
+/// Value given by the first structure of a type
+fn first<'a, T, F>(data: &'a SMBiosData, value: F) -> Option<String>
+where
+    T: SMBiosStruct<'a>,
+    F: FnMut(T) -> Option<String>,
+{
+    data.find_map(value)
+}
+
+/// Values given by every structure of a type, one per line; none if a
+/// structure has no value, and empty if the table has no structure of the
+/// type
+fn each<'a, T, F>(data: &'a SMBiosData, value: F) -> Option<String>
+where
+    T: SMBiosStruct<'a> + 'a,
+    F: FnMut(T) -> Option<String> + 'a,
+{
+    let values = data.map(value).collect::<Option<Vec<String>>>()?;
+    Some(values.join("\n"))
+}
+
+/// String given by the first structure of a type
+fn first_string<'a, T, F>(data: &'a SMBiosData, mut string: F) -> Option<String>
+where
+    T: SMBiosStruct<'a>,
+    F: FnMut(T) -> SMBiosString,
+{
+    first(data, |smbios_struct| string(smbios_struct).to_utf8_lossy())
+}
+
+/// Strings given by every structure of a type, one per line
+fn each_string<'a, T, F>(data: &'a SMBiosData, mut string: F) -> Option<String>
+where
+    T: SMBiosStruct<'a> + 'a,
+    F: FnMut(T) -> SMBiosString + 'a,
+{
+    each(data, move |smbios_struct| {
+        string(smbios_struct).to_utf8_lossy()
+    })
+}
+
+/// A "major.minor" release
+fn release(major: Option<u8>, minor: Option<u8>) -> Option<String> {
+    Some(format!("{}.{}", major?, minor?))
+}
+
+/// Processor family, from Processor Family 2 when the first one refers to it
+fn processor_family(processor: SMBiosProcessorInformation<'_>) -> Option<String> {
+    let family = processor.processor_family()?;
+    match family.value {
+        ProcessorFamily::SeeProcessorFamily2 => processor
+            .processor_family_2()
+            .map(|family2| family2.to_string()),
+        _ => Some(family.to_string()),
+    }
+}
+
+/// Number of populated memory devices
+fn memory_device_count(data: &SMBiosData) -> Option<String> {
+    let sizes = memory_device_sizes(data);
+    match sizes.is_empty() {
+        true => None,
+        false => Some(sizes.iter().flatten().count().to_string()),
     }
 }
 
+/// Total size of the memory devices
+fn memory_total_size(data: &SMBiosData) -> Option<String> {
+    let sizes = memory_device_sizes(data);
+    match sizes.is_empty() {
+        true => None,
+        false => Some(format_size(sizes.iter().flatten().sum())),
+    }
+}
+
+/// Values of a decoded field in every structure of a type, one per line;
+/// fields that list their content (Characteristics) give one line per item
+fn field(data: &SMBiosData, struct_type: u8, name: &str) -> Option<String> {
+    let lines: Vec<String> = data
+        .iter()
+        .filter(|undefined_struct| undefined_struct.header.struct_type() == struct_type)
+        .map(|undefined_struct| decode_struct(undefined_struct, data.version))
+        .filter_map(|decoded| decoded.field(name).cloned())
+        .flat_map(|field| field.value.into_iter().chain(field.items))
+        .collect();
+    match lines.is_empty() {
+        true => None,
+        false => Some(lines.join("\n")),
+    }
+}
+
+/// Sizes of the memory devices (type 17) in bytes, None for empty sockets
+fn memory_device_sizes(data: &SMBiosData) -> Vec<Option<u64>> {
+    data.iter()
+        .filter(|undefined_struct| undefined_struct.header.struct_type() == 17)
+        .map(|undefined_struct| decode_struct(undefined_struct, data.version).size_bytes("Size"))
+        .collect()
+}
+
 #[test]
 fn test_enum_display_exist_in_opt_string_keyword() -> Result<(), Box<dyn std::error::Error>> {
     use enum_iterator::all;
@@ -679,6 +1041,54 @@ fn test_enum_display_exist_in_opt_string_keyword() -> Result<(), Box<dyn std::er
     Ok(())
 }
 
+#[test]
+fn test_keyword_table() {
+    use enum_iterator::all;
+
+    let keywords = all::<Keyword>().collect::<Vec<_>>();
+    assert_eq!(keywords.len(), KEYWORDS.len());
+    assert_eq!(keywords.first(), Some(&Keyword::BiosVendor));
+    for keyword in keywords {
+        assert!(KEYWORD_HELP.contains(&format!(" {},", keyword)));
+    }
+    assert_eq!(
+        Keyword::from_str("oem-string-3").unwrap(),
+        Keyword::OemString(3)
+    );
+    assert_eq!(Keyword::OemString(3).to_string(), "oem-string-3");
+    assert!(Keyword::from_str("oem-string-0").is_err());
+    assert_eq!(format_size(16 << 30), "16 GB");
+    assert_eq!(format_size(1536 << 20), "1536 MB");
+
+    use crate::build::push_struct;
+    let mut table = Vec::new();
+    push_struct(
+        &mut table,
+        &[1, 8, 0x00, 0x00, 1, 0, 0, 0],
+        &[b"ACME".to_vec()],
+    );
+    for (handle, product) in [(1, b"Board A"), (2, b"Board B")].iter() {
+        push_struct(
+            &mut table,
+            &[2, 8, *handle, 0x00, 0, 1, 0, 0],
+            &[product.to_vec()],
+        );
+    }
+    let data = SMBiosData::from_vec_and_version(table, None);
+    assert_eq!(Keyword::SystemManufacturer.parse(&data).unwrap(), "ACME");
+    assert_eq!(
+        Keyword::BaseboardProductName.parse(&data).unwrap(),
+        "Board A\nBoard B"
+    );
+    // A keyword of the first structure of a type needs one; a keyword of
+    // every structure of a type is empty without any, as it always was
+    match Keyword::BiosVendor.parse(&data) {
+        Err(BiosParseError::KeywordNotFound(keyword)) => assert_eq!(keyword, "bios-vendor"),
+        other => panic!("{:?}", other),
+    }
+    assert_eq!(Keyword::ChassisManufacturer.parse(&data).unwrap(), "");
+}
+
 #[test]
 fn test_keyword_invalid_error_expected() {
     let result = Keyword::from_str("invalid");
@@ -687,3 +1097,24 @@ fn test_keyword_invalid_error_expected() {
     let want = std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid");
     assert_eq!(want.to_string(), got.to_string());
 }
+
+#[test]
+fn test_bios_type() {
+    let select = |args: &[&str]| {
+        let types: Vec<BiosType> = args.iter().map(|arg| arg.parse().unwrap()).collect();
+        BiosType::select(&types)
+    };
+    assert_eq!(select(&["tpm"]), vec![43]);
+    assert_eq!(select(&["Power Supply", "ipmi"]), vec![38, 39]);
+    assert_eq!(select(&["memory-device"]), vec![17]);
+    assert_eq!(select(&["firmware-inventory"]), vec![45]);
+    assert_eq!(select(&["bios", "2-3"]), vec![0, 2, 3, 13]);
+    assert_eq!(select(&["!oem"]).len(), 128);
+    assert_eq!(select(&["memory", "!17"]), vec![5, 6, 16]);
+    assert_eq!(select(&["oem"]), (128..=255).collect::<Vec<u8>>());
+
+    let error = "300".parse::<BiosType>().unwrap_err();
+    assert!(error.contains("power-supply"));
+    assert!("3-1".parse::<BiosType>().is_err());
+    assert!("!nothing".parse::<BiosType>().is_err());
+}
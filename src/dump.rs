@@ -0,0 +1,188 @@
+//! Parsers of saved tables, apart from the files they are read from
+//!
+//! A binary dump is a raw table (as written by `--dump-bin`) or a buffer of
+//! the Windows GetSystemFirmwareTable call; a hex dump is the `-u` output of
+//! this tool or of dmidecode.
+
+use crate::build::push_struct;
+use smbioslib::*;
+use std::io::{Error, ErrorKind};
+
+/// Splits a binary dump into its table and, for a Windows buffer, the
+/// version it gives
+pub fn split_dump(data: Vec<u8>) -> (Vec<u8>, Option<SMBiosVersion>) {
+    match WinSMBiosData::is_valid_win_smbios_data(&data) {
+        true => (
+            data[WinSMBiosData::SMBIOS_TABLE_DATA_OFFSET..].to_vec(),
+            Some(SMBiosVersion {
+                major: data[WinSMBiosData::SMBIOS_MAJOR_VERSION_OFFSET],
+                minor: data[WinSMBiosData::SMBIOS_MINOR_VERSION_OFFSET],
+                revision: data[WinSMBiosData::DMI_REVISION_OFFSET],
+            }),
+        ),
+        false => (data, None),
+    }
+}
+
+/// Parses the `-u` text output back into a raw table
+///
+/// Lines are trimmed, so indentation lost when pasting into tickets does not
+/// matter.  The version is taken from an "SMBIOS x.y[.z] present." line if
+/// there is one.
+pub fn parse_hexdump(text: &str) -> Result<(Vec<u8>, Option<SMBiosVersion>), Error> {
+    enum Section {
+        None,
+        Data,
+        Strings,
+    }
+
+    fn push(table: &mut Vec<u8>, fields: &[u8], strings: &[u8], handle: u16) -> Result<(), Error> {
+        if fields.len() < 4 || fields[1] as usize != fields.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Handle {:#06X}: the header does not match the {} bytes of data",
+                    handle,
+                    fields.len()
+                ),
+            ));
+        }
+        let strings: Vec<Vec<u8>> = strings
+            .split(|&byte| byte == 0)
+            .filter(|string| !string.is_empty())
+            .map(|string| string.to_vec())
+            .collect();
+        push_struct(table, fields, &strings);
+        Ok(())
+    }
+
+    let mut table = Vec::new();
+    let mut version = None;
+    let mut current: Option<(u16, Vec<u8>, Vec<u8>)> = None;
+    let mut section = Section::None;
+    // The rendered string follows the hex row that ends the string
+    let mut rendered_string_next = false;
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if rendered_string_next {
+            rendered_string_next = false;
+            // Quoted by this tool, not by dmidecode; may be blank or read as hex
+            if !line.starts_with("Handle 0x") {
+                continue;
+            }
+        }
+        if let Some(rest) = line.strip_prefix("Handle 0x") {
+            if let Some((handle, fields, strings)) = current.take() {
+                push(&mut table, &fields, &strings, handle)?;
+            }
+            let handle = rest.split(',').next().unwrap_or_default();
+            let handle = u16::from_str_radix(handle, 16).map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Line {}: invalid handle", number + 1),
+                )
+            })?;
+            current = Some((handle, Vec::new(), Vec::new()));
+            section = Section::None;
+        } else if line == "Header and Data:" {
+            section = Section::Data;
+        } else if line == "Strings:" {
+            section = Section::Strings;
+        } else if let Some(present) = line
+            .strip_prefix("SMBIOS ")
+            .and_then(|rest| rest.strip_suffix(" present."))
+        {
+            let parts: Vec<u8> = present.split('.').filter_map(|p| p.parse().ok()).collect();
+            if parts.len() >= 2 {
+                version = Some(SMBiosVersion::new(
+                    parts[0],
+                    parts[1],
+                    parts.get(2).copied().unwrap_or(0),
+                ));
+            }
+        } else if line.is_empty() {
+            continue;
+        } else if let Some((_, fields, strings)) = current.as_mut() {
+            let bytes = match section {
+                Section::Data => fields,
+                Section::Strings => strings,
+                Section::None => continue,
+            };
+            for byte in line.split_whitespace() {
+                bytes.push(u8::from_str_radix(byte, 16).map_err(|_| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Line {}: invalid hex byte {:?}", number + 1, byte),
+                    )
+                })?);
+            }
+            rendered_string_next = matches!(section, Section::Strings) && bytes.last() == Some(&0);
+        }
+    }
+    if let Some((handle, fields, strings)) = current.take() {
+        push(&mut table, &fields, &strings, handle)?;
+    }
+
+    if table.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "No structures found in the hex dump",
+        ));
+    }
+    Ok((table, version))
+}
+
+#[test]
+fn test_parse_hexdump() {
+    // Upstream dmidecode output: strings are printed unquoted after their
+    // hex rows, so "AB" and "00" read as hex and "    " as a blank line
+    let text = "# dmidecode 3.3\n\
+                Getting SMBIOS data from sysfs.\n\
+                SMBIOS 2.7 present.\n\
+                \n\
+                Handle 0x0000, DMI type 0, 24 bytes\n\
+                \tHeader and Data:\n\
+                \t\t00 18 00 00 01 02 00 F0 03 7F 80 98 8B 3F 01 00\n\
+                \t\t00 00 03 0D 01 22 FF FF\n\
+                \tStrings:\n\
+                \t\t41 43 4D 45 20 43 6F 72 70 6F 72 61 74 69 6F 6E\n\
+                \t\t00\n\
+                \t\tACME Corporation\n\
+                \t\t41 42 00\n\
+                \t\tAB\n\
+                \t\t30 30 00\n\
+                \t\t00\n\
+                \t\t20 20 20 20 00\n\
+                \t\t    \n\
+                \n\
+                Handle 0x0001, DMI type 127, 4 bytes\n\
+                \tHeader and Data:\n\
+                \t\t7F 04 01 00\n";
+    let (table, version) = parse_hexdump(text).unwrap();
+    assert_eq!(version, Some(SMBiosVersion::new(2, 7, 0)));
+
+    let mut expected = vec![
+        0x00, 0x18, 0x00, 0x00, 0x01, 0x02, 0x00, 0xF0, 0x03, 0x7F, 0x80, 0x98, 0x8B, 0x3F, 0x01,
+        0x00, 0x00, 0x00, 0x03, 0x0D, 0x01, 0x22, 0xFF, 0xFF,
+    ];
+    expected.extend_from_slice(b"ACME Corporation\0AB\0");
+    expected.extend_from_slice(b"00\0    \0\0");
+    expected.extend_from_slice(&[0x7F, 0x04, 0x01, 0x00, 0x00, 0x00]);
+    assert_eq!(table, expected);
+
+    // The output of -u by this tool quotes the strings
+    let text = "Handle 0x0000, DMI type 1, 5 bytes\n\
+                \tHeader and Data:\n\
+                \t\t01 05 00 00 01\n\
+                \tStrings:\n\
+                \t\t41 42 00\n\
+                \t\t\"AB\"\n";
+    let (table, _) = parse_hexdump(text).unwrap();
+    assert_eq!(table, b"\x01\x05\x00\x00\x01AB\0\0".to_vec());
+
+    assert!(
+        parse_hexdump("Handle 0x0000, DMI type 0, 24 bytes\n\tHeader and Data:\n\t\t00 18\n")
+            .is_err()
+    );
+}
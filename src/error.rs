@@ -1,33 +1,13 @@
-use std::{error::Error, fmt::Display};
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    io::{self, ErrorKind},
+};
 
+/// A keyword of -s has no value in the table
 #[derive(Debug)]
 pub enum BiosParseError {
-    BiosVendorNotFound,
-    BiosVersionNotFound,
-    BiosReleaseDateNotFound,
-    BiosRevisionNotFound,
-    FirmwareRevisionNotFound,
-    SystemManufacturerNotFound,
-    SystemProductNameNotFound,
-    SystemVersionNotFound,
-    SystemSerialNumberNotFound,
-    SystemUuidNotFound,
-    SystemSkuNumberNotFound,
-    SystemFamilyNotFound,
-    BaseboardManufacturerNotFound,
-    BaseboardProductNameNotFound,
-    BaseboardVersionNotFound,
-    BaseboardSerialNumberNotFound,
-    BaseboardAssetTagNotFound,
-    ChassisManufacturerNotFound,
-    ChassisTypeNotFound,
-    ChassisVersionNotFound,
-    ChassisSerialNumberNotFound,
-    ChassisAssetTagNotFound,
-    ProcessorFamilyNotFound,
-    ProcessorManufacturerNotFound,
-    ProcessorVersionNotFound,
-    ProcessorFrequencyNotFound,
+    KeywordNotFound(String),
 }
 
 impl Error for BiosParseError {
@@ -38,8 +18,274 @@ impl Error for BiosParseError {
 
 impl Display for BiosParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        // Here we can match and turn each arm into a human readable statement.
-        // We have other variants to add so we will wait before doing so.
-        write!(f, "{:?}", &self)
+        match self {
+            BiosParseError::KeywordNotFound(keyword) => {
+                write!(f, "No value for {} in the SMBIOS table", keyword)
+            }
+        }
+    }
+}
+
+/// Exit status of an unclassified failure
+pub const EXIT_FAILURE: i32 = 1;
+/// Exit status when the SMBIOS table cannot be read for lack of privileges
+pub const EXIT_ACCESS_DENIED: i32 = 2;
+/// Exit status when the system provides no SMBIOS table
+pub const EXIT_NO_SMBIOS: i32 = 3;
+/// Exit status when the entry point checksum is wrong
+pub const EXIT_CHECKSUM: i32 = 4;
+/// Exit status when the table is shorter than announced
+pub const EXIT_TRUNCATED_TABLE: i32 = 5;
+/// Exit status when a -s or --oem-string value is not in the table
+pub const EXIT_KEYWORD_NOT_FOUND: i32 = 6;
+/// Exit status when no structure has the handle of -H or --explain
+pub const EXIT_INVALID_HANDLE: i32 = 7;
+/// Exit status when the table has none of the structures an option decodes
+pub const EXIT_STRUCTURE_NOT_FOUND: i32 = 8;
+/// Exit status of diff when a table cannot be loaded or decoded
+pub const EXIT_DIFF_ERROR: i32 = 9;
+/// Exit status of batch when some of the tables fail to load; the others
+/// are still output
+pub const EXIT_BATCH_PARTIAL: i32 = 10;
+/// Exit status when --query selects nothing, whether output as text or JSON
+pub const EXIT_QUERY_NO_MATCH: i32 = 11;
+/// Exit status of diff when the tables differ, as diff(1) has it; diff
+/// never exits with it for a failure
+pub const EXIT_TABLES_DIFFER: i32 = 1;
+
+/// Help text listing the exit statuses
+pub const EXIT_STATUS_HELP: &str = "EXIT STATUS:
+    0  Success
+    1  Other failure
+    2  Access denied to the SMBIOS table (run as root or use --from-dump)
+    3  No SMBIOS table found
+    4  Entry point checksum failure
+    5  Truncated table
+    6  Keyword (-s) or OEM string (--oem-string) not present
+    7  No structure with the handle of -H or --explain
+    8  No structure of the type --redfish-host-interface or --ipmi-config decodes
+    9  diff: a table could not be loaded or decoded
+    10 batch: some tables failed to load, the others were output
+    11 --query matched nothing
+
+diff exits with 1 when the tables differ, as diff(1) does, and never for a
+failure.";
+
+/// Failures reported to the user, each with a message saying what to try
+/// next and its own exit status
+#[derive(Debug)]
+pub enum DmiError {
+    /// The device (or sysfs file) could not be opened
+    AccessDenied(String),
+    /// No entry point was found, or there is nothing to read it from
+    NoSmbios(String),
+    /// The entry point does not add up
+    Checksum(String),
+    /// The table ends before the length given by the entry point
+    TruncatedTable(String),
+    /// A -s or --oem-string value missing from the table
+    KeywordNotFound(BiosParseError),
+    /// No structure has the handle
+    InvalidHandle(u16),
+    /// The table has no structure of the type, given with its description
+    StructureNotFound(&'static str, u8),
+    /// The --query, given as written, selects nothing
+    QueryNoMatch(String),
+    /// diff could not load or decode one of the tables
+    Diff(Box<DmiError>),
+    /// Anything else
+    Other(Box<dyn Error>),
+}
+
+impl DmiError {
+    /// Classifies a failure to read the table of the running system from
+    /// `device` (the memory device, or sysfs)
+    pub fn from_live_load(error: io::Error, device: &str) -> Self {
+        let message = error.to_string();
+        match error.kind() {
+            ErrorKind::PermissionDenied => DmiError::AccessDenied(device.to_string()),
+            ErrorKind::NotFound => DmiError::NoSmbios(format!("{}: {}", device, message)),
+            // smbios-lib reports a scan that found no anchor as "Not found"
+            ErrorKind::UnexpectedEof if message == "Not found" => {
+                DmiError::NoSmbios(format!("no entry point in {}", device))
+            }
+            ErrorKind::UnexpectedEof => DmiError::TruncatedTable(message),
+            ErrorKind::InvalidData if message.contains("checksum") => DmiError::Checksum(message),
+            _ => DmiError::Other(Box::new(error)),
+        }
+    }
+
+    /// Exit status of the tool for this failure
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            DmiError::AccessDenied(_) => EXIT_ACCESS_DENIED,
+            DmiError::NoSmbios(_) => EXIT_NO_SMBIOS,
+            DmiError::Checksum(_) => EXIT_CHECKSUM,
+            DmiError::TruncatedTable(_) => EXIT_TRUNCATED_TABLE,
+            DmiError::KeywordNotFound(_) => EXIT_KEYWORD_NOT_FOUND,
+            DmiError::InvalidHandle(_) => EXIT_INVALID_HANDLE,
+            DmiError::StructureNotFound(..) => EXIT_STRUCTURE_NOT_FOUND,
+            DmiError::QueryNoMatch(_) => EXIT_QUERY_NO_MATCH,
+            DmiError::Diff(_) => EXIT_DIFF_ERROR,
+            DmiError::Other(_) => EXIT_FAILURE,
+        }
+    }
+}
+
+impl Error for DmiError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DmiError::KeywordNotFound(error) => Some(error),
+            DmiError::Diff(error) => Some(error.as_ref()),
+            DmiError::Other(error) => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl Display for DmiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DmiError::AccessDenied(device) => write!(
+                f,
+                "Permission denied reading {}. Run as root, or dump the table on a machine \
+                 where you can (--dump-bin FILE) and read it with --from-dump FILE",
+                device
+            ),
+            DmiError::NoSmbios(reason) => write!(
+                f,
+                "No SMBIOS table found ({}). The firmware may not provide one, as on some \
+                 virtual machines and boards; use --from-dump FILE to decode a saved table",
+                reason
+            ),
+            DmiError::Checksum(reason) => write!(
+                f,
+                "The SMBIOS entry point is corrupt ({}). Report it to the firmware vendor; \
+                 the table may still be read from sysfs, or with --from-dump FILE",
+                reason
+            ),
+            DmiError::TruncatedTable(reason) => write!(
+                f,
+                "The SMBIOS table is truncated ({}). Decode what can be read with --recover",
+                reason
+            ),
+            DmiError::KeywordNotFound(error) => write!(
+                f,
+                "{}. The firmware does not provide it; run without -s or --oem-string to see \
+                 the whole table",
+                error
+            ),
+            DmiError::InvalidHandle(handle) => write!(
+                f,
+                "No structure has handle {:#06X}. Run without -H to see the handles in the table",
+                handle
+            ),
+            DmiError::StructureNotFound(description, struct_type) => write!(
+                f,
+                "No {} found (DMI type {}). The firmware does not provide one; \
+                 run with -t {} to check",
+                description, struct_type, struct_type
+            ),
+            DmiError::QueryNoMatch(query) => write!(
+                f,
+                "No match for query {}. Check the names with -t and the type, \
+                 or widen the filters",
+                query
+            ),
+            DmiError::Diff(error) => write!(f, "{}", error),
+            DmiError::Other(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<Box<dyn Error>> for DmiError {
+    fn from(error: Box<dyn Error>) -> Self {
+        let error = match error.downcast::<DmiError>() {
+            Ok(error) => return *error,
+            Err(error) => error,
+        };
+        match error.downcast::<BiosParseError>() {
+            Ok(error) => DmiError::KeywordNotFound(*error),
+            Err(error) => DmiError::Other(error),
+        }
+    }
+}
+
+#[test]
+fn test_dmi_error() {
+    let denied = io::Error::new(ErrorKind::PermissionDenied, "Permission denied");
+    let error = DmiError::from_live_load(denied, "/dev/mem");
+    assert_eq!(error.exit_code(), EXIT_ACCESS_DENIED);
+    assert!(error.to_string().contains("/dev/mem"));
+    assert!(error.to_string().contains("--from-dump"));
+
+    let classify = |kind, message: &str| {
+        DmiError::from_live_load(io::Error::new(kind, message.to_string()), "/dev/mem").exit_code()
+    };
+    assert_eq!(classify(ErrorKind::NotFound, "missing"), EXIT_NO_SMBIOS);
+    assert_eq!(
+        classify(ErrorKind::UnexpectedEof, "Not found"),
+        EXIT_NO_SMBIOS
+    );
+    assert_eq!(
+        classify(ErrorKind::UnexpectedEof, "failed to fill whole buffer"),
+        EXIT_TRUNCATED_TABLE
+    );
+    assert_eq!(
+        classify(
+            ErrorKind::InvalidData,
+            "Entry Point checksum verification failed"
+        ),
+        EXIT_CHECKSUM
+    );
+    assert_eq!(classify(ErrorKind::Other, "other"), EXIT_FAILURE);
+
+    // Errors raised as boxes keep their status
+    let boxed: Box<dyn Error> =
+        Box::new(BiosParseError::KeywordNotFound("system-uuid".to_string()));
+    let error = DmiError::from(boxed);
+    assert_eq!(error.exit_code(), EXIT_KEYWORD_NOT_FOUND);
+    assert!(error.to_string().starts_with("No value for system-uuid"));
+    let boxed: Box<dyn Error> = Box::new(DmiError::InvalidHandle(0x999));
+    let error = DmiError::from(boxed);
+    assert_eq!(error.exit_code(), EXIT_INVALID_HANDLE);
+    assert!(error.to_string().contains("0x0999"));
+    let error = DmiError::StructureNotFound("IPMI Device Information", 38);
+    assert_eq!(error.exit_code(), EXIT_STRUCTURE_NOT_FOUND);
+    assert!(error
+        .to_string()
+        .starts_with("No IPMI Device Information found (DMI type 38)"));
+    let error = DmiError::QueryNoMatch("processor[socket-designation=CPU9]".to_string());
+    assert_eq!(error.exit_code(), EXIT_QUERY_NO_MATCH);
+    assert!(error
+        .to_string()
+        .starts_with("No match for query processor[socket-designation=CPU9]"));
+    let loading: Box<dyn Error> = Box::new(DmiError::NoSmbios("no entry point".to_string()));
+    let error = DmiError::Diff(Box::new(DmiError::from(loading)));
+    assert_eq!(error.exit_code(), EXIT_DIFF_ERROR);
+    assert!(error.to_string().starts_with("No SMBIOS table found"));
+    let boxed: Box<dyn Error> = "--format redfish does not apply to batch".into();
+    assert_eq!(DmiError::from(boxed).exit_code(), EXIT_FAILURE);
+
+    // Every outcome has its own status; diff alone exits with 1 when the
+    // tables differ, and never for a failure
+    let mut statuses = vec![
+        EXIT_FAILURE,
+        EXIT_ACCESS_DENIED,
+        EXIT_NO_SMBIOS,
+        EXIT_CHECKSUM,
+        EXIT_TRUNCATED_TABLE,
+        EXIT_KEYWORD_NOT_FOUND,
+        EXIT_INVALID_HANDLE,
+        EXIT_STRUCTURE_NOT_FOUND,
+        EXIT_DIFF_ERROR,
+        EXIT_BATCH_PARTIAL,
+        EXIT_QUERY_NO_MATCH,
+    ];
+    statuses.sort_unstable();
+    statuses.dedup();
+    assert_eq!(statuses.len(), 11);
+    for status in statuses.iter() {
+        assert!(EXIT_STATUS_HELP.contains(&format!("\n    {:<2} ", status)));
     }
 }
@@ -0,0 +1,1592 @@
+//! Byte-level view of a structure
+//!
+//! Lists every byte range of the formatted area with its offset, width,
+//! specification field name, raw value and decoded value, followed by the
+//! string set.  Field layouts come from the SMBIOS specification (DSP0134);
+//! each field records the version that introduced it, so that bytes the
+//! declared version does not define can be flagged, and so that structure
+//! lengths can be checked against the declared version.
+
+use crate::decoded::{decode_struct, DecodedStruct};
+use smbioslib::*;
+use std::fmt::Write;
+
+/// How a field is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Byte,
+    Word,
+    Dword,
+    Qword,
+    /// String number
+    Str,
+    /// Raw bytes of the given width
+    Bytes(usize),
+}
+
+impl Kind {
+    fn width(&self) -> usize {
+        match self {
+            Kind::Byte | Kind::Str => 1,
+            Kind::Word => 2,
+            Kind::Dword => 4,
+            Kind::Qword => 8,
+            Kind::Bytes(width) => *width,
+        }
+    }
+}
+
+/// A field of the formatted area
+#[derive(Debug, Clone, Copy)]
+struct FieldLayout {
+    offset: usize,
+    kind: Kind,
+    /// Name in the specification
+    name: &'static str,
+    /// Name in the decoded output, when it differs from `name`
+    decoded: Option<&'static str>,
+    /// SMBIOS version that introduced the field
+    since: (u8, u8),
+}
+
+const fn field(offset: usize, kind: Kind, name: &'static str, since: (u8, u8)) -> FieldLayout {
+    FieldLayout {
+        offset,
+        kind,
+        name,
+        decoded: None,
+        since,
+    }
+}
+
+const fn field_as(
+    offset: usize,
+    kind: Kind,
+    name: &'static str,
+    decoded: &'static str,
+    since: (u8, u8),
+) -> FieldLayout {
+    FieldLayout {
+        offset,
+        kind,
+        name,
+        decoded: Some(decoded),
+        since,
+    }
+}
+
+use Kind::*;
+
+const HEADER: [FieldLayout; 3] = [
+    field(0x00, Byte, "Type", (2, 0)),
+    field(0x01, Byte, "Length", (2, 0)),
+    field(0x02, Word, "Handle", (2, 0)),
+];
+
+const BIOS_INFORMATION: [FieldLayout; 13] = [
+    field(0x04, Str, "Vendor", (2, 0)),
+    field_as(0x05, Str, "BIOS Version", "Version", (2, 0)),
+    field_as(
+        0x06,
+        Word,
+        "BIOS Starting Address Segment",
+        "Address",
+        (2, 0),
+    ),
+    field_as(0x08, Str, "BIOS Release Date", "Release Date", (2, 0)),
+    field_as(0x09, Byte, "BIOS ROM Size", "ROM Size", (2, 0)),
+    field_as(
+        0x0A,
+        Qword,
+        "BIOS Characteristics",
+        "Characteristics",
+        (2, 0),
+    ),
+    field_as(
+        0x12,
+        Byte,
+        "BIOS Characteristics Extension Byte 1",
+        "Characteristics",
+        (2, 4),
+    ),
+    field_as(
+        0x13,
+        Byte,
+        "BIOS Characteristics Extension Byte 2",
+        "Characteristics",
+        (2, 4),
+    ),
+    field_as(
+        0x14,
+        Byte,
+        "System BIOS Major Release",
+        "BIOS Revision",
+        (2, 4),
+    ),
+    field_as(
+        0x15,
+        Byte,
+        "System BIOS Minor Release",
+        "BIOS Revision",
+        (2, 4),
+    ),
+    field_as(
+        0x16,
+        Byte,
+        "Embedded Controller Firmware Major Release",
+        "Firmware Revision",
+        (2, 4),
+    ),
+    field_as(
+        0x17,
+        Byte,
+        "Embedded Controller Firmware Minor Release",
+        "Firmware Revision",
+        (2, 4),
+    ),
+    field_as(0x18, Word, "Extended BIOS ROM Size", "ROM Size", (3, 1)),
+];
+
+const SYSTEM_INFORMATION: [FieldLayout; 8] = [
+    field(0x04, Str, "Manufacturer", (2, 0)),
+    field(0x05, Str, "Product Name", (2, 0)),
+    field(0x06, Str, "Version", (2, 0)),
+    field(0x07, Str, "Serial Number", (2, 0)),
+    field(0x08, Bytes(16), "UUID", (2, 1)),
+    field(0x18, Byte, "Wake-up Type", (2, 1)),
+    field(0x19, Str, "SKU Number", (2, 4)),
+    field(0x1A, Str, "Family", (2, 4)),
+];
+
+const BASEBOARD_INFORMATION: [FieldLayout; 10] = [
+    field(0x04, Str, "Manufacturer", (2, 0)),
+    field_as(0x05, Str, "Product", "Product Name", (2, 0)),
+    field(0x06, Str, "Version", (2, 0)),
+    field(0x07, Str, "Serial Number", (2, 0)),
+    field(0x08, Str, "Asset Tag", (2, 0)),
+    field_as(0x09, Byte, "Feature Flags", "Features", (2, 0)),
+    field(0x0A, Str, "Location in Chassis", (2, 0)),
+    field(0x0B, Word, "Chassis Handle", (2, 0)),
+    field_as(0x0D, Byte, "Board Type", "Type", (2, 0)),
+    field_as(
+        0x0E,
+        Byte,
+        "Number of Contained Object Handles",
+        "Contained Object Handles",
+        (2, 0),
+    ),
+];
+
+const CHASSIS_INFORMATION: [FieldLayout; 14] = [
+    field(0x04, Str, "Manufacturer", (2, 0)),
+    field(0x05, Byte, "Type", (2, 0)),
+    field(0x06, Str, "Version", (2, 0)),
+    field(0x07, Str, "Serial Number", (2, 0)),
+    field_as(0x08, Str, "Asset Tag Number", "Asset Tag", (2, 0)),
+    field(0x09, Byte, "Boot-up State", (2, 1)),
+    field(0x0A, Byte, "Power Supply State", (2, 1)),
+    field(0x0B, Byte, "Thermal State", (2, 1)),
+    field(0x0C, Byte, "Security Status", (2, 1)),
+    field_as(0x0D, Dword, "OEM-defined", "OEM Information", (2, 3)),
+    field(0x11, Byte, "Height", (2, 3)),
+    field_as(
+        0x12,
+        Byte,
+        "Number of Power Cords",
+        "Number Of Power Cords",
+        (2, 3),
+    ),
+    field_as(
+        0x13,
+        Byte,
+        "Contained Element Count",
+        "Contained Elements",
+        (2, 3),
+    ),
+    field(0x14, Byte, "Contained Element Record Length", (2, 3)),
+];
+
+const PROCESSOR_INFORMATION: [FieldLayout; 28] = [
+    field(0x04, Str, "Socket Designation", (2, 0)),
+    field_as(0x05, Byte, "Processor Type", "Type", (2, 0)),
+    field_as(0x06, Byte, "Processor Family", "Family", (2, 0)),
+    field_as(0x07, Str, "Processor Manufacturer", "Manufacturer", (2, 0)),
+    field_as(0x08, Qword, "Processor ID", "ID", (2, 0)),
+    field_as(0x10, Str, "Processor Version", "Version", (2, 0)),
+    field(0x11, Byte, "Voltage", (2, 0)),
+    field(0x12, Word, "External Clock", (2, 0)),
+    field(0x14, Word, "Max Speed", (2, 0)),
+    field(0x16, Word, "Current Speed", (2, 0)),
+    field(0x18, Byte, "Status", (2, 0)),
+    field_as(0x19, Byte, "Processor Upgrade", "Upgrade", (2, 0)),
+    field(0x1A, Word, "L1 Cache Handle", (2, 1)),
+    field(0x1C, Word, "L2 Cache Handle", (2, 1)),
+    field(0x1E, Word, "L3 Cache Handle", (2, 1)),
+    field(0x20, Str, "Serial Number", (2, 3)),
+    field(0x21, Str, "Asset Tag", (2, 3)),
+    field(0x22, Str, "Part Number", (2, 3)),
+    field(0x23, Byte, "Core Count", (2, 5)),
+    field(0x24, Byte, "Core Enabled", (2, 5)),
+    field(0x25, Byte, "Thread Count", (2, 5)),
+    field_as(
+        0x26,
+        Word,
+        "Processor Characteristics",
+        "Characteristics",
+        (2, 5),
+    ),
+    field_as(0x28, Word, "Processor Family 2", "Family", (2, 6)),
+    field_as(0x2A, Word, "Core Count 2", "Core Count", (3, 0)),
+    field_as(0x2C, Word, "Core Enabled 2", "Core Enabled", (3, 0)),
+    field_as(0x2E, Word, "Thread Count 2", "Thread Count", (3, 0)),
+    field(0x30, Word, "Thread Enabled", (3, 6)),
+    field(0x32, Str, "Socket Type", (3, 8)),
+];
+
+const CACHE_INFORMATION: [FieldLayout; 12] = [
+    field(0x04, Str, "Socket Designation", (2, 0)),
+    field_as(0x05, Word, "Cache Configuration", "Configuration", (2, 0)),
+    field_as(0x07, Word, "Maximum Cache Size", "Maximum Size", (2, 0)),
+    field(0x09, Word, "Installed Size", (2, 0)),
+    field_as(
+        0x0B,
+        Word,
+        "Supported SRAM Type",
+        "Supported SRAM Types",
+        (2, 0),
+    ),
+    field_as(
+        0x0D,
+        Word,
+        "Current SRAM Type",
+        "Installed SRAM Type",
+        (2, 0),
+    ),
+    field_as(0x0F, Byte, "Cache Speed", "Speed", (2, 1)),
+    field(0x10, Byte, "Error Correction Type", (2, 1)),
+    field_as(0x11, Byte, "System Cache Type", "System Type", (2, 1)),
+    field(0x12, Byte, "Associativity", (2, 1)),
+    field_as(0x13, Dword, "Maximum Cache Size 2", "Maximum Size", (3, 1)),
+    field_as(
+        0x17,
+        Dword,
+        "Installed Cache Size 2",
+        "Installed Size",
+        (3, 1),
+    ),
+];
+
+const PORT_CONNECTOR_INFORMATION: [FieldLayout; 5] = [
+    field(0x04, Str, "Internal Reference Designator", (2, 0)),
+    field(0x05, Byte, "Internal Connector Type", (2, 0)),
+    field(0x06, Str, "External Reference Designator", (2, 0)),
+    field(0x07, Byte, "External Connector Type", (2, 0)),
+    field(0x08, Byte, "Port Type", (2, 0)),
+];
+
+const SYSTEM_SLOTS: [FieldLayout; 13] = [
+    field_as(0x04, Str, "Slot Designation", "Designation", (2, 0)),
+    field_as(0x05, Byte, "Slot Type", "Type", (2, 0)),
+    field_as(0x06, Byte, "Slot Data Bus Width", "Type", (2, 0)),
+    field(0x07, Byte, "Current Usage", (2, 0)),
+    field_as(0x08, Byte, "Slot Length", "Length", (2, 0)),
+    field_as(0x09, Word, "Slot ID", "ID", (2, 0)),
+    field_as(
+        0x0B,
+        Byte,
+        "Slot Characteristics 1",
+        "Characteristics",
+        (2, 0),
+    ),
+    field_as(
+        0x0C,
+        Byte,
+        "Slot Characteristics 2",
+        "Characteristics",
+        (2, 1),
+    ),
+    field_as(0x0D, Word, "Segment Group Number", "Bus Address", (2, 6)),
+    field_as(0x0F, Byte, "Bus Number", "Bus Address", (2, 6)),
+    field_as(0x10, Byte, "Device/Function Number", "Bus Address", (2, 6)),
+    field(0x11, Byte, "Data Bus Width", (3, 2)),
+    field_as(
+        0x12,
+        Byte,
+        "Peer (S/B/D/F/Width) Grouping Count",
+        "Peer Devices",
+        (3, 2),
+    ),
+];
+
+const BIOS_LANGUAGE_INFORMATION: [FieldLayout; 4] = [
+    field(0x04, Byte, "Installable Languages", (2, 0)),
+    field_as(0x05, Byte, "Flags", "Language Description Format", (2, 1)),
+    field(0x06, Bytes(15), "Reserved", (2, 0)),
+    field_as(
+        0x15,
+        Str,
+        "Current Language",
+        "Currently Installed Language",
+        (2, 0),
+    ),
+];
+
+const SYSTEM_EVENT_LOG: [FieldLayout; 10] = [
+    field_as(0x04, Word, "Log Area Length", "Area Length", (2, 0)),
+    field_as(
+        0x06,
+        Word,
+        "Log Header Start Offset",
+        "Header Start Offset",
+        (2, 0),
+    ),
+    field_as(
+        0x08,
+        Word,
+        "Log Data Start Offset",
+        "Data Start Offset",
+        (2, 0),
+    ),
+    field(0x0A, Byte, "Access Method", (2, 0)),
+    field_as(0x0B, Byte, "Log Status", "Status", (2, 0)),
+    field_as(0x0C, Dword, "Log Change Token", "Change Token", (2, 0)),
+    field_as(
+        0x10,
+        Dword,
+        "Access Method Address",
+        "Access Address",
+        (2, 0),
+    ),
+    field_as(0x14, Byte, "Log Header Format", "Header Format", (2, 1)),
+    field_as(
+        0x15,
+        Byte,
+        "Number of Supported Log Type Descriptors",
+        "Supported Log Type Descriptors",
+        (2, 1),
+    ),
+    field(0x16, Byte, "Length of each Log Type Descriptor", (2, 1)),
+];
+
+const PHYSICAL_MEMORY_ARRAY: [FieldLayout; 7] = [
+    field(0x04, Byte, "Location", (2, 1)),
+    field(0x05, Byte, "Use", (2, 1)),
+    field_as(
+        0x06,
+        Byte,
+        "Memory Error Correction",
+        "Error Correction Type",
+        (2, 1),
+    ),
+    field(0x07, Dword, "Maximum Capacity", (2, 1)),
+    field_as(
+        0x0B,
+        Word,
+        "Memory Error Information Handle",
+        "Error Information Handle",
+        (2, 1),
+    ),
+    field_as(
+        0x0D,
+        Word,
+        "Number of Memory Devices",
+        "Number Of Devices",
+        (2, 1),
+    ),
+    field_as(
+        0x0F,
+        Qword,
+        "Extended Maximum Capacity",
+        "Maximum Capacity",
+        (2, 7),
+    ),
+];
+
+const MEMORY_DEVICE: [FieldLayout; 39] = [
+    field_as(
+        0x04,
+        Word,
+        "Physical Memory Array Handle",
+        "Array Handle",
+        (2, 1),
+    ),
+    field_as(
+        0x06,
+        Word,
+        "Memory Error Information Handle",
+        "Error Information Handle",
+        (2, 1),
+    ),
+    field(0x08, Word, "Total Width", (2, 1)),
+    field(0x0A, Word, "Data Width", (2, 1)),
+    field(0x0C, Word, "Size", (2, 1)),
+    field(0x0E, Byte, "Form Factor", (2, 1)),
+    field_as(0x0F, Byte, "Device Set", "Set", (2, 1)),
+    field_as(0x10, Str, "Device Locator", "Locator", (2, 1)),
+    field(0x11, Str, "Bank Locator", (2, 1)),
+    field_as(0x12, Byte, "Memory Type", "Type", (2, 1)),
+    field(0x13, Word, "Type Detail", (2, 1)),
+    field(0x15, Word, "Speed", (2, 3)),
+    field(0x17, Str, "Manufacturer", (2, 3)),
+    field(0x18, Str, "Serial Number", (2, 3)),
+    field(0x19, Str, "Asset Tag", (2, 3)),
+    field(0x1A, Str, "Part Number", (2, 3)),
+    field_as(0x1B, Byte, "Attributes", "Rank", (2, 6)),
+    field_as(0x1C, Dword, "Extended Size", "Size", (2, 7)),
+    field(0x20, Word, "Configured Memory Speed", (2, 7)),
+    field(0x22, Word, "Minimum Voltage", (2, 8)),
+    field(0x24, Word, "Maximum Voltage", (2, 8)),
+    field(0x26, Word, "Configured Voltage", (2, 8)),
+    field(0x28, Byte, "Memory Technology", (3, 2)),
+    field(0x29, Word, "Memory Operating Mode Capability", (3, 2)),
+    field(0x2B, Str, "Firmware Version", (3, 2)),
+    field(0x2C, Word, "Module Manufacturer ID", (3, 2)),
+    field(0x2E, Word, "Module Product ID", (3, 2)),
+    field(
+        0x30,
+        Word,
+        "Memory Subsystem Controller Manufacturer ID",
+        (3, 2),
+    ),
+    field(0x32, Word, "Memory Subsystem Controller Product ID", (3, 2)),
+    field(0x34, Qword, "Non-volatile Size", (3, 2)),
+    field(0x3C, Qword, "Volatile Size", (3, 2)),
+    field(0x44, Qword, "Cache Size", (3, 2)),
+    field(0x4C, Qword, "Logical Size", (3, 2)),
+    field_as(0x54, Dword, "Extended Speed", "Speed", (3, 3)),
+    field_as(
+        0x58,
+        Dword,
+        "Extended Configured Memory Speed",
+        "Configured Memory Speed",
+        (3, 3),
+    ),
+    field(0x5C, Word, "PMIC0 Manufacturer ID", (3, 7)),
+    field(0x5E, Word, "PMIC0 Revision Number", (3, 7)),
+    field(0x60, Word, "RCD Manufacturer ID", (3, 7)),
+    field(0x62, Word, "RCD Revision Number", (3, 7)),
+];
+
+const MEMORY_ERROR_INFORMATION_32: [FieldLayout; 7] = [
+    field_as(0x04, Byte, "Error Type", "Type", (2, 1)),
+    field_as(0x05, Byte, "Error Granularity", "Granularity", (2, 1)),
+    field_as(0x06, Byte, "Error Operation", "Operation", (2, 1)),
+    field(0x07, Dword, "Vendor Syndrome", (2, 1)),
+    field_as(
+        0x0B,
+        Dword,
+        "Memory Array Error Address",
+        "Memory Array Address",
+        (2, 1),
+    ),
+    field_as(
+        0x0F,
+        Dword,
+        "Device Error Address",
+        "Device Address",
+        (2, 1),
+    ),
+    field_as(0x13, Dword, "Error Resolution", "Resolution", (2, 1)),
+];
+
+const MEMORY_ARRAY_MAPPED_ADDRESS: [FieldLayout; 6] = [
+    field(0x04, Dword, "Starting Address", (2, 1)),
+    field(0x08, Dword, "Ending Address", (2, 1)),
+    field_as(
+        0x0C,
+        Word,
+        "Memory Array Handle",
+        "Physical Array Handle",
+        (2, 1),
+    ),
+    field(0x0E, Byte, "Partition Width", (2, 1)),
+    field_as(
+        0x0F,
+        Qword,
+        "Extended Starting Address",
+        "Starting Address",
+        (2, 7),
+    ),
+    field_as(
+        0x17,
+        Qword,
+        "Extended Ending Address",
+        "Ending Address",
+        (2, 7),
+    ),
+];
+
+const MEMORY_DEVICE_MAPPED_ADDRESS: [FieldLayout; 9] = [
+    field(0x04, Dword, "Starting Address", (2, 1)),
+    field(0x08, Dword, "Ending Address", (2, 1)),
+    field_as(
+        0x0C,
+        Word,
+        "Memory Device Handle",
+        "Physical Device Handle",
+        (2, 1),
+    ),
+    field(0x0E, Word, "Memory Array Mapped Address Handle", (2, 1)),
+    field(0x10, Byte, "Partition Row Position", (2, 1)),
+    field(0x11, Byte, "Interleave Position", (2, 1)),
+    field(0x12, Byte, "Interleaved Data Depth", (2, 1)),
+    field_as(
+        0x13,
+        Qword,
+        "Extended Starting Address",
+        "Starting Address",
+        (2, 7),
+    ),
+    field_as(
+        0x1B,
+        Qword,
+        "Extended Ending Address",
+        "Ending Address",
+        (2, 7),
+    ),
+];
+
+const HARDWARE_SECURITY: [FieldLayout; 1] =
+    [field(0x04, Byte, "Hardware Security Settings", (2, 2))];
+
+const PROBE: [FieldLayout; 9] = [
+    field(0x04, Str, "Description", (2, 2)),
+    field_as(0x05, Byte, "Location and Status", "Location", (2, 2)),
+    field(0x06, Word, "Maximum Value", (2, 2)),
+    field(0x08, Word, "Minimum Value", (2, 2)),
+    field(0x0A, Word, "Resolution", (2, 2)),
+    field(0x0C, Word, "Tolerance", (2, 2)),
+    field(0x0E, Word, "Accuracy", (2, 2)),
+    field_as(
+        0x10,
+        Dword,
+        "OEM-defined",
+        "OEM-specific Information",
+        (2, 2),
+    ),
+    field(0x14, Word, "Nominal Value", (2, 2)),
+];
+
+const COOLING_DEVICE: [FieldLayout; 6] = [
+    field(0x04, Word, "Temperature Probe Handle", (2, 2)),
+    field_as(0x06, Byte, "Device Type and Status", "Type", (2, 2)),
+    field(0x07, Byte, "Cooling Unit Group", (2, 2)),
+    field_as(
+        0x08,
+        Dword,
+        "OEM-defined",
+        "OEM-specific Information",
+        (2, 2),
+    ),
+    field(0x0C, Word, "Nominal Speed", (2, 2)),
+    field(0x0E, Str, "Description", (2, 7)),
+];
+
+const SYSTEM_BOOT_INFORMATION: [FieldLayout; 2] = [
+    field(0x04, Bytes(6), "Reserved", (2, 3)),
+    field_as(0x0A, Byte, "Boot Status", "Status", (2, 3)),
+];
+
+const IPMI_DEVICE_INFORMATION: [FieldLayout; 7] = [
+    field(0x04, Byte, "Interface Type", (2, 3)),
+    field_as(
+        0x05,
+        Byte,
+        "IPMI Specification Revision",
+        "Specification Version",
+        (2, 3),
+    ),
+    field_as(
+        0x06,
+        Byte,
+        "I2C Target Address",
+        "I2C Slave Address",
+        (2, 3),
+    ),
+    field(0x07, Byte, "NV Storage Device Address", (2, 3)),
+    field(0x08, Qword, "Base Address", (2, 3)),
+    field_as(
+        0x10,
+        Byte,
+        "Base Address Modifier / Interrupt Info",
+        "Register Spacing",
+        (2, 3),
+    ),
+    field(0x11, Byte, "Interrupt Number", (2, 3)),
+];
+
+const SYSTEM_POWER_SUPPLY: [FieldLayout; 13] = [
+    field(0x04, Byte, "Power Unit Group", (2, 3)),
+    field(0x05, Str, "Location", (2, 3)),
+    field_as(0x06, Str, "Device Name", "Name", (2, 3)),
+    field(0x07, Str, "Manufacturer", (2, 3)),
+    field(0x08, Str, "Serial Number", (2, 3)),
+    field_as(0x09, Str, "Asset Tag Number", "Asset Tag", (2, 3)),
+    field(0x0A, Str, "Model Part Number", (2, 3)),
+    field_as(0x0B, Str, "Revision Level", "Revision", (2, 3)),
+    field(0x0C, Word, "Max Power Capacity", (2, 3)),
+    field_as(0x0E, Word, "Power Supply Characteristics", "Type", (2, 3)),
+    field(0x10, Word, "Input Voltage Probe Handle", (2, 3)),
+    field(0x12, Word, "Cooling Device Handle", (2, 3)),
+    field(0x14, Word, "Input Current Probe Handle", (2, 3)),
+];
+
+const ONBOARD_DEVICES_EXTENDED: [FieldLayout; 6] = [
+    field(0x04, Str, "Reference Designation", (2, 6)),
+    field_as(0x05, Byte, "Device Type", "Type", (2, 6)),
+    field_as(0x06, Byte, "Device Type Instance", "Type Instance", (2, 6)),
+    field_as(0x07, Word, "Segment Group Number", "Bus Address", (2, 6)),
+    field_as(0x09, Byte, "Bus Number", "Bus Address", (2, 6)),
+    field_as(0x0A, Byte, "Device/Function Number", "Bus Address", (2, 6)),
+];
+
+const TPM_DEVICE: [FieldLayout; 8] = [
+    field(0x04, Bytes(4), "Vendor ID", (3, 1)),
+    field_as(
+        0x08,
+        Byte,
+        "Major Spec Version",
+        "Specification Version",
+        (3, 1),
+    ),
+    field_as(
+        0x09,
+        Byte,
+        "Minor Spec Version",
+        "Specification Version",
+        (3, 1),
+    ),
+    field_as(
+        0x0A,
+        Dword,
+        "Firmware Version 1",
+        "Firmware Revision",
+        (3, 1),
+    ),
+    field_as(
+        0x0E,
+        Dword,
+        "Firmware Version 2",
+        "Firmware Revision",
+        (3, 1),
+    ),
+    field(0x12, Str, "Description", (3, 1)),
+    field(0x13, Qword, "Characteristics", (3, 1)),
+    field_as(
+        0x1B,
+        Dword,
+        "OEM-defined",
+        "OEM-specific Information",
+        (3, 1),
+    ),
+];
+
+const MEMORY_CONTROLLER_INFORMATION: [FieldLayout; 9] = [
+    field(0x04, Byte, "Error Detecting Method", (2, 0)),
+    field_as(
+        0x05,
+        Byte,
+        "Error Correcting Capability",
+        "Error Correcting Capabilities",
+        (2, 0),
+    ),
+    field(0x06, Byte, "Supported Interleave", (2, 0)),
+    field(0x07, Byte, "Current Interleave", (2, 0)),
+    field(0x08, Byte, "Maximum Memory Module Size", (2, 0)),
+    field(0x09, Word, "Supported Speeds", (2, 0)),
+    field(0x0B, Word, "Supported Memory Types", (2, 0)),
+    field(0x0D, Byte, "Memory Module Voltage", (2, 0)),
+    field_as(
+        0x0E,
+        Byte,
+        "Number of Associated Memory Slots",
+        "Associated Memory Slots",
+        (2, 0),
+    ),
+];
+
+const MEMORY_MODULE_INFORMATION: [FieldLayout; 7] = [
+    field(0x04, Str, "Socket Designation", (2, 0)),
+    field(0x05, Byte, "Bank Connections", (2, 0)),
+    field(0x06, Byte, "Current Speed", (2, 0)),
+    field_as(0x07, Word, "Current Memory Type", "Type", (2, 0)),
+    field(0x09, Byte, "Installed Size", (2, 0)),
+    field(0x0A, Byte, "Enabled Size", (2, 0)),
+    field(0x0B, Byte, "Error Status", (2, 0)),
+];
+
+const GROUP_ASSOCIATIONS: [FieldLayout; 1] = [field_as(0x04, Str, "Group Name", "Name", (2, 0))];
+
+const BUILT_IN_POINTING_DEVICE: [FieldLayout; 3] = [
+    field(0x04, Byte, "Type", (2, 1)),
+    field(0x05, Byte, "Interface", (2, 1)),
+    field_as(0x06, Byte, "Number of Buttons", "Buttons", (2, 1)),
+];
+
+const PORTABLE_BATTERY: [FieldLayout; 15] = [
+    field(0x04, Str, "Location", (2, 1)),
+    field(0x05, Str, "Manufacturer", (2, 1)),
+    field(0x06, Str, "Manufacture Date", (2, 1)),
+    field(0x07, Str, "Serial Number", (2, 1)),
+    field_as(0x08, Str, "Device Name", "Name", (2, 1)),
+    field_as(0x09, Byte, "Device Chemistry", "Chemistry", (2, 1)),
+    field(0x0A, Word, "Design Capacity", (2, 1)),
+    field(0x0C, Word, "Design Voltage", (2, 1)),
+    field_as(0x0E, Str, "SBDS Version Number", "SBDS Version", (2, 1)),
+    field_as(
+        0x0F,
+        Byte,
+        "Maximum Error in Battery Data",
+        "Maximum Error",
+        (2, 1),
+    ),
+    field(0x10, Word, "SBDS Serial Number", (2, 2)),
+    field(0x12, Word, "SBDS Manufacture Date", (2, 2)),
+    field_as(0x14, Str, "SBDS Device Chemistry", "SBDS Chemistry", (2, 2)),
+    field_as(
+        0x15,
+        Byte,
+        "Design Capacity Multiplier",
+        "Design Capacity",
+        (2, 2),
+    ),
+    field_as(
+        0x16,
+        Dword,
+        "OEM-specific",
+        "OEM-specific Information",
+        (2, 2),
+    ),
+];
+
+const SYSTEM_RESET: [FieldLayout; 5] = [
+    field_as(0x04, Byte, "Capabilities", "Status", (2, 2)),
+    field(0x05, Word, "Reset Count", (2, 2)),
+    field(0x07, Word, "Reset Limit", (2, 2)),
+    field(0x09, Word, "Timer Interval", (2, 2)),
+    field(0x0B, Word, "Timeout", (2, 2)),
+];
+
+const SYSTEM_POWER_CONTROLS: [FieldLayout; 5] = [
+    field_as(
+        0x04,
+        Byte,
+        "Next Scheduled Power-on Month",
+        "Next Scheduled Power-on",
+        (2, 2),
+    ),
+    field_as(
+        0x05,
+        Byte,
+        "Next Scheduled Power-on Day-of-month",
+        "Next Scheduled Power-on",
+        (2, 2),
+    ),
+    field_as(
+        0x06,
+        Byte,
+        "Next Scheduled Power-on Hour",
+        "Next Scheduled Power-on",
+        (2, 2),
+    ),
+    field_as(
+        0x07,
+        Byte,
+        "Next Scheduled Power-on Minute",
+        "Next Scheduled Power-on",
+        (2, 2),
+    ),
+    field_as(
+        0x08,
+        Byte,
+        "Next Scheduled Power-on Second",
+        "Next Scheduled Power-on",
+        (2, 2),
+    ),
+];
+
+const OUT_OF_BAND_REMOTE_ACCESS: [FieldLayout; 2] = [
+    field(0x04, Str, "Manufacturer Name", (2, 2)),
+    field_as(0x05, Byte, "Connections", "Inbound Connection", (2, 2)),
+];
+
+const BIS_ENTRY_POINT: [FieldLayout; 7] = [
+    field(0x04, Byte, "Checksum", (2, 3)),
+    field(0x05, Byte, "Reserved", (2, 3)),
+    field(0x06, Word, "Reserved", (2, 3)),
+    field(0x08, Dword, "BIS Entry Point 16", (2, 3)),
+    field(0x0C, Dword, "BIS Entry Point 32", (2, 3)),
+    field(0x10, Qword, "Reserved", (2, 3)),
+    field(0x18, Dword, "Reserved", (2, 3)),
+];
+
+const MEMORY_ERROR_INFORMATION_64: [FieldLayout; 7] = [
+    field_as(0x04, Byte, "Error Type", "Type", (2, 3)),
+    field_as(0x05, Byte, "Error Granularity", "Granularity", (2, 3)),
+    field_as(0x06, Byte, "Error Operation", "Operation", (2, 3)),
+    field(0x07, Dword, "Vendor Syndrome", (2, 3)),
+    field_as(
+        0x0B,
+        Qword,
+        "Memory Array Error Address",
+        "Memory Array Address",
+        (2, 3),
+    ),
+    field_as(
+        0x13,
+        Qword,
+        "Device Error Address",
+        "Device Address",
+        (2, 3),
+    ),
+    field_as(0x1B, Dword, "Error Resolution", "Resolution", (2, 3)),
+];
+
+const MANAGEMENT_DEVICE: [FieldLayout; 4] = [
+    field(0x04, Str, "Description", (2, 3)),
+    field(0x05, Byte, "Type", (2, 3)),
+    field(0x06, Dword, "Address", (2, 3)),
+    field(0x0A, Byte, "Address Type", (2, 3)),
+];
+
+const MANAGEMENT_DEVICE_COMPONENT: [FieldLayout; 4] = [
+    field(0x04, Str, "Description", (2, 3)),
+    field(0x05, Word, "Management Device Handle", (2, 3)),
+    field(0x07, Word, "Component Handle", (2, 3)),
+    field(0x09, Word, "Threshold Handle", (2, 3)),
+];
+
+const MANAGEMENT_DEVICE_THRESHOLD_DATA: [FieldLayout; 6] = [
+    field_as(
+        0x04,
+        Word,
+        "Lower Threshold - Non-critical",
+        "Lower Non-critical Threshold",
+        (2, 3),
+    ),
+    field_as(
+        0x06,
+        Word,
+        "Upper Threshold - Non-critical",
+        "Upper Non-critical Threshold",
+        (2, 3),
+    ),
+    field_as(
+        0x08,
+        Word,
+        "Lower Threshold - Critical",
+        "Lower Critical Threshold",
+        (2, 3),
+    ),
+    field_as(
+        0x0A,
+        Word,
+        "Upper Threshold - Critical",
+        "Upper Critical Threshold",
+        (2, 3),
+    ),
+    field_as(
+        0x0C,
+        Word,
+        "Lower Threshold - Non-recoverable",
+        "Lower Non-recoverable Threshold",
+        (2, 3),
+    ),
+    field_as(
+        0x0E,
+        Word,
+        "Upper Threshold - Non-recoverable",
+        "Upper Non-recoverable Threshold",
+        (2, 3),
+    ),
+];
+
+const MEMORY_CHANNEL: [FieldLayout; 3] = [
+    field_as(0x04, Byte, "Channel Type", "Type", (2, 3)),
+    field_as(0x05, Byte, "Maximum Channel Load", "Maximal Load", (2, 3)),
+    field_as(0x06, Byte, "Memory Device Count", "Devices", (2, 3)),
+];
+
+const ADDITIONAL_INFORMATION: [FieldLayout; 1] = [field(
+    0x04,
+    Byte,
+    "Number of Additional Information entries",
+    (2, 6),
+)];
+
+const MANAGEMENT_CONTROLLER_HOST_INTERFACE: [FieldLayout; 2] = [
+    field(0x04, Byte, "Interface Type", (2, 7)),
+    field(0x05, Byte, "Interface Type Specific Data Length", (3, 2)),
+];
+
+const PROCESSOR_ADDITIONAL_INFORMATION: [FieldLayout; 3] = [
+    field(0x04, Word, "Referenced Handle", (3, 3)),
+    field(0x06, Byte, "Block Length", (3, 3)),
+    field(0x07, Byte, "Processor Type", (3, 3)),
+];
+
+const FIRMWARE_INVENTORY_INFORMATION: [FieldLayout; 12] = [
+    field(0x04, Str, "Firmware Component Name", (3, 5)),
+    field(0x05, Str, "Firmware Version", (3, 5)),
+    field(0x06, Byte, "Version Format", (3, 5)),
+    field(0x07, Str, "Firmware ID", (3, 5)),
+    field(0x08, Byte, "Firmware ID Format", (3, 5)),
+    field(0x09, Str, "Release Date", (3, 5)),
+    field(0x0A, Str, "Manufacturer", (3, 5)),
+    field(0x0B, Str, "Lowest Supported Firmware Version", (3, 5)),
+    field(0x0C, Qword, "Image Size", (3, 5)),
+    field(0x14, Word, "Characteristics", (3, 5)),
+    field(0x16, Byte, "State", (3, 5)),
+    field(0x17, Byte, "Number of Associated Components", (3, 5)),
+];
+
+const STRING_PROPERTY: [FieldLayout; 3] = [
+    field(0x04, Word, "String Property ID", (3, 5)),
+    field(0x06, Str, "String Property Value", (3, 5)),
+    field(0x07, Word, "Parent Handle", (3, 5)),
+];
+
+/// Fixed fields of a structure type (after the header)
+fn fixed_fields(struct_type: u8) -> &'static [FieldLayout] {
+    match struct_type {
+        0 => &BIOS_INFORMATION,
+        1 => &SYSTEM_INFORMATION,
+        2 => &BASEBOARD_INFORMATION,
+        3 => &CHASSIS_INFORMATION,
+        4 => &PROCESSOR_INFORMATION,
+        5 => &MEMORY_CONTROLLER_INFORMATION,
+        6 => &MEMORY_MODULE_INFORMATION,
+        7 => &CACHE_INFORMATION,
+        8 => &PORT_CONNECTOR_INFORMATION,
+        9 => &SYSTEM_SLOTS,
+        13 => &BIOS_LANGUAGE_INFORMATION,
+        14 => &GROUP_ASSOCIATIONS,
+        15 => &SYSTEM_EVENT_LOG,
+        16 => &PHYSICAL_MEMORY_ARRAY,
+        17 => &MEMORY_DEVICE,
+        18 => &MEMORY_ERROR_INFORMATION_32,
+        19 => &MEMORY_ARRAY_MAPPED_ADDRESS,
+        20 => &MEMORY_DEVICE_MAPPED_ADDRESS,
+        21 => &BUILT_IN_POINTING_DEVICE,
+        22 => &PORTABLE_BATTERY,
+        23 => &SYSTEM_RESET,
+        24 => &HARDWARE_SECURITY,
+        25 => &SYSTEM_POWER_CONTROLS,
+        26 | 28 | 29 => &PROBE,
+        27 => &COOLING_DEVICE,
+        30 => &OUT_OF_BAND_REMOTE_ACCESS,
+        31 => &BIS_ENTRY_POINT,
+        32 => &SYSTEM_BOOT_INFORMATION,
+        33 => &MEMORY_ERROR_INFORMATION_64,
+        34 => &MANAGEMENT_DEVICE,
+        35 => &MANAGEMENT_DEVICE_COMPONENT,
+        36 => &MANAGEMENT_DEVICE_THRESHOLD_DATA,
+        37 => &MEMORY_CHANNEL,
+        38 => &IPMI_DEVICE_INFORMATION,
+        39 => &SYSTEM_POWER_SUPPLY,
+        40 => &ADDITIONAL_INFORMATION,
+        41 => &ONBOARD_DEVICES_EXTENDED,
+        42 => &MANAGEMENT_CONTROLLER_HOST_INTERFACE,
+        43 => &TPM_DEVICE,
+        44 => &PROCESSOR_ADDITIONAL_INFORMATION,
+        45 => &FIRMWARE_INVENTORY_INFORMATION,
+        46 => &STRING_PROPERTY,
+        _ => &[],
+    }
+}
+
+/// Fields whose offset or count depends on the content of the structure
+fn variable_fields(fields: &[u8]) -> Vec<FieldLayout> {
+    let byte = |offset: usize| fields.get(offset).copied().unwrap_or(0) as usize;
+    let mut variable = Vec::new();
+    match fields[0] {
+        // Base Board Information: contained object handles
+        2 => {
+            for index in 0..byte(0x0E) {
+                variable.push(field(
+                    0x0F + 2 * index,
+                    Word,
+                    "Contained Object Handle",
+                    (2, 0),
+                ));
+            }
+        }
+        // Memory Controller Information: slot handles, then the enabled
+        // error correcting capabilities
+        5 => {
+            let slots = byte(0x0E);
+            for index in 0..slots {
+                variable.push(field(
+                    0x0F + 2 * index,
+                    Word,
+                    "Memory Module Configuration Handle",
+                    (2, 0),
+                ));
+            }
+            variable.push(field_as(
+                0x0F + 2 * slots,
+                Byte,
+                "Enabled Error Correcting Capabilities",
+                "Enabled Error Correcting Capabilities",
+                (2, 1),
+            ));
+        }
+        // Chassis Information: contained elements, then SKU and rack fields
+        3 => {
+            let elements = byte(0x13) * byte(0x14);
+            let end = 0x15 + elements;
+            if elements > 0 {
+                variable.push(field(0x15, Bytes(elements), "Contained Elements", (2, 3)));
+            }
+            variable.push(field(end, Str, "SKU Number", (2, 7)));
+            variable.push(field(end + 1, Byte, "Rack Type", (3, 9)));
+            variable.push(field(end + 2, Byte, "Rack Height", (3, 9)));
+        }
+        // System Slots: peer groups, then the SMBIOS 3.4 fields
+        9 => {
+            let peers = byte(0x12);
+            for index in 0..peers {
+                variable.push(field_as(
+                    0x13 + 5 * index,
+                    Bytes(5),
+                    "Peer Group",
+                    "Peer Devices",
+                    (3, 2),
+                ));
+            }
+            let end = 0x13 + 5 * peers;
+            variable.push(field(end, Byte, "Slot Information", (3, 4)));
+            variable.push(field(end + 1, Byte, "Slot Physical Width", (3, 4)));
+            variable.push(field(end + 2, Word, "Slot Pitch", (3, 4)));
+            variable.push(field(end + 4, Byte, "Slot Height", (3, 5)));
+        }
+        // On Board Devices Information: one pair per device
+        10 => {
+            for index in 0..(fields.len().saturating_sub(4) / 2) {
+                let offset = 0x04 + 2 * index;
+                variable.push(field_as(offset, Byte, "Device Type", "Type", (2, 0)));
+                variable.push(field(offset + 1, Str, "Description String", (2, 0)));
+            }
+        }
+        // OEM Strings, System Configuration Options
+        11 | 12 => variable.push(field(0x04, Byte, "Count", (2, 0))),
+        // Group Associations: one type and handle per item
+        14 => {
+            for index in 0..(fields.len().saturating_sub(5) / 3) {
+                let offset = 0x05 + 3 * index;
+                variable.push(field(offset, Byte, "Item Type", (2, 0)));
+                variable.push(field(offset + 1, Word, "Item Handle", (2, 0)));
+            }
+        }
+        // System Event Log: supported log type descriptors
+        15 => {
+            let descriptors = byte(0x15) * byte(0x16);
+            if descriptors > 0 {
+                variable.push(field(
+                    0x17,
+                    Bytes(descriptors),
+                    "List of Supported Event Log Type Descriptors",
+                    (2, 1),
+                ));
+            }
+        }
+        // Memory Channel: load and handle of each device
+        37 => {
+            for index in 0..byte(0x06) {
+                let offset = 0x07 + 3 * index;
+                variable.push(field(offset, Byte, "Memory Device Load", (2, 3)));
+                variable.push(field(offset + 1, Word, "Memory Device Handle", (2, 3)));
+            }
+        }
+        // Additional Information: entries, each with its own length
+        40 => {
+            let mut offset = 0x05;
+            for _ in 0..byte(0x04) {
+                let length = byte(offset);
+                if length < 5 || offset + length > fields.len() {
+                    break;
+                }
+                variable.push(field(offset, Byte, "Entry Length", (2, 6)));
+                variable.push(field(offset + 1, Word, "Referenced Handle", (2, 6)));
+                variable.push(field(offset + 3, Byte, "Referenced Offset", (2, 6)));
+                variable.push(field(offset + 4, Str, "String", (2, 6)));
+                if length > 5 {
+                    variable.push(field(offset + 5, Bytes(length - 5), "Value", (2, 6)));
+                }
+                offset += length;
+            }
+        }
+        // Management Controller Host Interface: the interface data, then
+        // the protocol records, each with its own length
+        42 => {
+            let specific_length = byte(0x05);
+            if specific_length > 0 {
+                variable.push(field(0x06, Byte, "Device Type", (3, 2)));
+            }
+            if specific_length > 1 {
+                variable.push(field(
+                    0x07,
+                    Bytes(specific_length - 1),
+                    "Interface Type Specific Data",
+                    (3, 2),
+                ));
+            }
+            let mut offset = 0x06 + specific_length;
+            variable.push(field(offset, Byte, "Number of Protocol Records", (3, 2)));
+            offset += 1;
+            for _ in 0..byte(offset - 1) {
+                if offset + 2 > fields.len() {
+                    break;
+                }
+                let length = byte(offset + 1);
+                variable.push(field_as(
+                    offset,
+                    Byte,
+                    "Protocol Type",
+                    "Protocol ID",
+                    (3, 2),
+                ));
+                variable.push(field(
+                    offset + 1,
+                    Byte,
+                    "Protocol Type Specific Data Length",
+                    (3, 2),
+                ));
+                if length > 0 {
+                    variable.push(field(
+                        offset + 2,
+                        Bytes(length),
+                        "Protocol Type Specific Data",
+                        (3, 2),
+                    ));
+                }
+                offset += 2 + length;
+            }
+        }
+        // Processor Additional Information: the processor-specific data
+        44 => {
+            let length = byte(0x06);
+            if length > 2 {
+                variable.push(field(
+                    0x08,
+                    Bytes(length - 2),
+                    "Processor-Specific Data",
+                    (3, 3),
+                ));
+            }
+        }
+        // Firmware Inventory Information: associated component handles
+        45 => {
+            for index in 0..byte(0x17) {
+                variable.push(field(
+                    0x18 + 2 * index,
+                    Word,
+                    "Associated Component Handle",
+                    (3, 5),
+                ));
+            }
+        }
+        _ => (),
+    }
+    variable
+}
+
+/// All fields known for a structure, header included, in offset order
+fn known_fields(fields: &[u8]) -> Vec<FieldLayout> {
+    let mut layouts: Vec<FieldLayout> = HEADER.to_vec();
+    layouts.extend_from_slice(fixed_fields(fields[0]));
+    layouts.extend(variable_fields(fields));
+    layouts.sort_by_key(|layout| layout.offset);
+    layouts
+}
+
+/// Checks the structure lengths of a table against its SMBIOS version
+///
+/// A structure holding fields introduced after `version` means the version
+/// is understated, and fields after the claimed version are not decoded.
+/// Structures lacking fields that `version` defines are not reported:
+/// firmware commonly keeps older layouts, which decode fine.
+pub fn version_warning(data: &SMBiosData, version: SMBiosVersion) -> Option<String> {
+    let claimed = (version.major, version.minor);
+    let mut implied: Option<((u8, u8), &UndefinedStruct)> = None;
+
+    for undefined_struct in data.iter() {
+        let fields = &undefined_struct.fields;
+        let present = |layout: &&FieldLayout| layout.offset + layout.kind.width() <= fields.len();
+        if let Some(since) = known_fields(fields)
+            .iter()
+            .filter(present)
+            .map(|layout| layout.since)
+            .max()
+        {
+            match implied {
+                Some((newest, _)) if newest >= since => (),
+                _ => implied = Some((since, undefined_struct)),
+            }
+        }
+    }
+
+    let (since, undefined_struct) = implied.filter(|(since, _)| claimed < *since)?;
+    Some(format!(
+        concat!(
+            "Structure lengths imply SMBIOS {}.{} or later (handle {:#06X}, DMI type {}, ",
+            "{} bytes), but decoding as {}.{}.{}; try --smbios-version {}.{}"
+        ),
+        since.0,
+        since.1,
+        *undefined_struct.header.handle(),
+        undefined_struct.header.struct_type(),
+        undefined_struct.fields.len(),
+        version.major,
+        version.minor,
+        version.revision,
+        since.0,
+        since.1
+    ))
+}
+
+/// Returns the byte-level view of a structure
+pub fn explain_struct(
+    undefined_struct: &UndefinedStruct,
+    version: Option<SMBiosVersion>,
+) -> String {
+    let fields = &undefined_struct.fields;
+    let decoded = decode_struct(undefined_struct, version);
+    let struct_type = undefined_struct.header.struct_type();
+
+    let layouts = known_fields(fields);
+    let has_layout = layouts.len() > HEADER.len();
+
+    let defined = |since: (u8, u8)| match version {
+        Some(version) => since <= (version.major, version.minor),
+        None => true,
+    };
+    let beyond = match version {
+        Some(version) => format!(
+            "[beyond the fields known for SMBIOS {}.{}]",
+            version.major, version.minor
+        ),
+        None => "[beyond the known fields]".to_string(),
+    };
+
+    let mut rows: Vec<(usize, usize, String, String, String)> = Vec::new();
+    let mut offset = 0;
+    for layout in layouts.iter() {
+        if layout.offset < offset || offset >= fields.len() {
+            continue;
+        }
+        if layout.offset > offset {
+            let end = layout.offset.min(fields.len());
+            rows.push((
+                offset,
+                end - offset,
+                "(undescribed)".to_string(),
+                hex_bytes(&fields[offset..end]),
+                "[not described]".to_string(),
+            ));
+            offset = end;
+            if offset >= fields.len() {
+                break;
+            }
+        }
+        let end = layout.offset + layout.kind.width();
+        if end > fields.len() {
+            let (name, flag) = match defined(layout.since) {
+                true => (layout.name, "[truncated]".to_string()),
+                false => ("(unknown)", beyond.clone()),
+            };
+            rows.push((
+                offset,
+                fields.len() - offset,
+                name.to_string(),
+                hex_bytes(&fields[offset..]),
+                flag,
+            ));
+            offset = fields.len();
+            break;
+        }
+        let raw = &fields[layout.offset..end];
+        let mut explanation = describe(layout, raw, undefined_struct, &decoded);
+        if !defined(layout.since) {
+            if !explanation.is_empty() {
+                explanation.push(' ');
+            }
+            write!(
+                &mut explanation,
+                "[not defined before SMBIOS {}.{}]",
+                layout.since.0, layout.since.1
+            )
+            .unwrap();
+        }
+        rows.push((
+            layout.offset,
+            layout.kind.width(),
+            layout.name.to_string(),
+            raw_value(layout.kind, raw),
+            explanation,
+        ));
+        offset = end;
+    }
+    if offset < fields.len() {
+        let (name, flag) = match has_layout {
+            true => ("(unknown)", beyond),
+            false => ("(no field layout for this type)", String::new()),
+        };
+        rows.push((
+            offset,
+            fields.len() - offset,
+            name.to_string(),
+            hex_bytes(&fields[offset..]),
+            flag,
+        ));
+    }
+
+    let mut output = String::new();
+    writeln!(
+        &mut output,
+        "Handle {:#06X}, DMI type {}, {} bytes",
+        *undefined_struct.header.handle(),
+        struct_type,
+        fields.len()
+    )
+    .unwrap();
+    writeln!(&mut output, "{}", decoded.name).unwrap();
+
+    let name_width = rows.iter().map(|row| row.2.len()).max().unwrap_or(0);
+    let raw_width = rows
+        .iter()
+        .map(|row| row.3.len())
+        .filter(|width| *width <= 18)
+        .max()
+        .unwrap_or(0);
+    for (offset, width, name, raw, explanation) in rows.iter() {
+        let line = format!(
+            "\t0x{:02X} {:>3}  {:<name_width$}  {:<raw_width$}  {}",
+            offset,
+            width,
+            name,
+            raw,
+            explanation,
+            name_width = name_width,
+            raw_width = raw_width
+        );
+        writeln!(&mut output, "{}", line.trim_end()).unwrap();
+    }
+
+    let referenced: Vec<u8> = layouts
+        .iter()
+        .filter(|layout| layout.kind == Str)
+        .filter_map(|layout| fields.get(layout.offset).copied())
+        .collect();
+    writeln!(&mut output, "\tStrings:").unwrap();
+    for (index, string) in undefined_struct.strings.iter().enumerate() {
+        let number = index + 1;
+        let unreferenced =
+            has_layout && !matches!(struct_type, 11 | 12) && !referenced.contains(&(number as u8));
+        writeln!(
+            &mut output,
+            "\t\t#{} \"{}\"{}",
+            number,
+            String::from_utf8_lossy(string),
+            match unreferenced {
+                true => " [not referenced]",
+                false => "",
+            }
+        )
+        .unwrap();
+    }
+    output
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn raw_value(kind: Kind, raw: &[u8]) -> String {
+    let mut value = 0u64;
+    for byte in raw.iter().rev() {
+        value = (value << 8) | *byte as u64;
+    }
+    match kind {
+        Byte | Str => format!("0x{:02X}", value),
+        Word => format!("0x{:04X}", value),
+        Dword => format!("0x{:08X}", value),
+        Qword => format!("0x{:016X}", value),
+        Bytes(_) => hex_bytes(raw),
+    }
+}
+
+/// Decoded meaning of a field: its string, or the decoded field of the same name
+fn describe(
+    layout: &FieldLayout,
+    raw: &[u8],
+    undefined_struct: &UndefinedStruct,
+    decoded: &DecodedStruct,
+) -> String {
+    match (layout.offset, layout.kind) {
+        (0x00, _) => return format!("\u{2192} {}", decoded.name),
+        (0x01, _) => return format!("\u{2192} {} bytes", raw[0]),
+        (0x02, _) => return String::new(),
+        (_, Str) => {
+            let number = raw[0];
+            if number == 0 {
+                return "\u{2192} no string".to_string();
+            }
+            return match undefined_struct.strings.get_string(number).to_utf8_lossy() {
+                Some(string) => format!("\u{2192} string #{} \"{}\"", number, string),
+                None => format!("\u{2192} string #{} <BAD INDEX>", number),
+            };
+        }
+        _ => (),
+    }
+    match decoded.field(layout.decoded.unwrap_or(layout.name)) {
+        Some(field) => match &field.value {
+            Some(value) => format!("\u{2192} {}", value),
+            None if !field.items.is_empty() => format!("\u{2192} {}", field.items.join(", ")),
+            None => String::new(),
+        },
+        None => String::new(),
+    }
+}
+
+#[test]
+fn test_explain_struct() {
+    let mut raw = vec![
+        // Memory Device, SMBIOS 2.3 length plus two bytes the version does not define
+        0x11, 0x1D, 0x08, 0x00, 0x01, 0x00, 0xFE, 0xFF, 0x40, 0x00, 0x40, 0x00, 0x00, 0x20, 0x0D,
+        0x00, 0x01, 0x02, 0x1A, 0x80, 0x00, 0x60, 0x09, 0x03, 0x00, 0x00, 0x00, 0xAB, 0xCD,
+    ];
+    raw.extend_from_slice(b"DIMM 0\0P0 CHANNEL A\0Hynix\0Unused\0\0");
+    let data = SMBiosData::from_vec_and_version(raw, None);
+    let undefined_struct = data.iter().next().unwrap();
+
+    let text = explain_struct(undefined_struct, Some(SMBiosVersion::new(2, 3, 0)));
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "Handle 0x0008, DMI type 17, 29 bytes");
+    assert_eq!(lines[1], "Memory Device");
+    let line = |offset: &str| {
+        lines
+            .iter()
+            .find(|line| line.starts_with(&format!("\t{}", offset)))
+            .unwrap()
+            .to_string()
+    };
+    assert!(line("0x11").contains("Bank Locator"));
+    assert!(line("0x11").ends_with("\u{2192} string #2 \"P0 CHANNEL A\""));
+    assert!(line("0x0C").ends_with("\u{2192} 8 GB"));
+    assert!(line("0x19").ends_with("\u{2192} no string"));
+    assert!(line("0x1B").contains("[not defined before SMBIOS 2.6]"));
+    assert!(line("0x1C").ends_with("[beyond the fields known for SMBIOS 2.3]"));
+    assert!(text.contains("\t\t#4 \"Unused\" [not referenced]"));
+}
+
+#[test]
+fn test_version_warnings() {
+    let mut raw = vec![
+        // Memory Device, SMBIOS 2.6 length
+        0x11, 0x1C, 0x08, 0x00, 0x01, 0x00, 0xFE, 0xFF, 0x40, 0x00, 0x40, 0x00, 0x00, 0x20, 0x0D,
+        0x00, 0x01, 0x02, 0x1A, 0x80, 0x00, 0x60, 0x09, 0x03, 0x00, 0x00, 0x00, 0x01,
+    ];
+    raw.extend_from_slice(b"DIMM 0\0P0 CHANNEL A\0Hynix\0\0");
+    let data = SMBiosData::from_vec_and_version(raw, None);
+
+    let warning = version_warning(&data, SMBiosVersion::new(2, 3, 0)).unwrap();
+    assert!(warning.starts_with("Structure lengths imply SMBIOS 2.6 or later"));
+    assert!(warning.ends_with("try --smbios-version 2.6"));
+
+    assert_eq!(version_warning(&data, SMBiosVersion::new(2, 6, 0)), None);
+    // Older layouts than the version defines are common and decode fine
+    assert_eq!(version_warning(&data, SMBiosVersion::new(2, 7, 0)), None);
+}
+
+#[test]
+fn test_explain_layouts() {
+    // Fields, strings, and the offset and name of fields the layout must name
+    type Structure<'a> = (&'a [u8], &'a [&'a str], &'a [(&'a str, &'a str)]);
+    let structures: [Structure<'_>; 4] = [
+        (
+            // Portable Battery, SMBIOS 2.2 length
+            &[
+                0x16, 0x1A, 0x30, 0x00, 0x01, 0x02, 0x00, 0x00, 0x03, 0x02, 0xB8, 0x0B, 0x30, 0x2A,
+                0x04, 0xFF, 0x34, 0x12, 0x4A, 0x51, 0x05, 0x0A, 0x00, 0x00, 0x00, 0x00,
+            ],
+            &["Rear", "Maker", "Battery", "3.0", "LION"],
+            &[
+                ("0x08", "Device Name"),
+                ("0x0A", "Design Capacity"),
+                ("0x15", "Design Capacity Multiplier"),
+                ("0x16", "OEM-specific"),
+            ],
+        ),
+        (
+            // Additional Information with one entry of a one byte value
+            &[
+                0x28, 0x0B, 0x31, 0x00, 0x01, 0x06, 0x08, 0x00, 0x05, 0x01, 0x42,
+            ],
+            &["Wake Source"],
+            &[
+                ("0x05", "Entry Length"),
+                ("0x06", "Referenced Handle"),
+                ("0x09", "String"),
+                ("0x0A", "Value"),
+            ],
+        ),
+        (
+            // Management Controller Host Interface with one protocol record
+            &[
+                0x2A, 0x0C, 0x32, 0x00, 0x40, 0x01, 0x02, 0x01, 0x04, 0x02, 0xAA, 0xBB,
+            ],
+            &[],
+            &[
+                ("0x05", "Interface Type Specific Data Length"),
+                ("0x06", "Device Type"),
+                ("0x07", "Number of Protocol Records"),
+                ("0x08", "Protocol Type"),
+                ("0x0A", "Protocol Type Specific Data"),
+            ],
+        ),
+        (
+            // Firmware Inventory Information with one associated component
+            &[
+                0x2D, 0x1A, 0x33, 0x00, 0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x10,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x01, 0x30, 0x00,
+            ],
+            &["BIOS", "1.2.3", "Maker"],
+            &[
+                ("0x04", "Firmware Component Name"),
+                ("0x0C", "Image Size"),
+                ("0x17", "Number of Associated Components"),
+                ("0x18", "Associated Component Handle"),
+            ],
+        ),
+    ];
+    for (fields, strings, expected) in structures.iter() {
+        let strings: Vec<Vec<u8>> = strings
+            .iter()
+            .map(|string| string.as_bytes().to_vec())
+            .collect();
+        let mut raw = Vec::new();
+        crate::build::push_struct(&mut raw, fields, &strings);
+        let data = SMBiosData::from_vec_and_version(raw, None);
+        let undefined_struct = data.iter().next().unwrap();
+
+        let text = explain_struct(undefined_struct, None);
+        assert!(
+            !text.contains("(no field layout for this type)"),
+            "{}",
+            text
+        );
+        assert!(!text.contains("(unknown)"), "{}", text);
+        for (offset, name) in expected.iter() {
+            let line = text
+                .lines()
+                .find(|line| line.starts_with(&format!("\t{}", offset)))
+                .unwrap();
+            assert!(line.contains(name), "{}", line);
+        }
+    }
+}
@@ -0,0 +1,717 @@
+//! Redfish Host Interface connection settings
+//!
+//! A Management Controller Host Interface (type 42) of the Network Host
+//! Interface type describes the in-band network link to the BMC: the NIC
+//! that carries it (USB or PCI, per DSP0270, with its MAC address in the v2
+//! descriptors of DSP0270 1.3) and, in its Redfish over IP
+//! protocol records, how the host side gets its address and where the
+//! Redfish service listens.  This module turns those into a JSON object or
+//! into a NetworkManager keyfile or systemd-networkd `.network` file that
+//! brings the link up.
+
+use serde::Serialize;
+use smbioslib::*;
+use std::{convert::TryInto, fmt::Write, net::IpAddr};
+
+/// Length of the Redfish over IP protocol specific data, without the hostname
+/// (DSP0270: 8.6)
+const REDFISH_OVER_IP_LENGTH: usize = 91;
+
+/// The NIC that carries the interface (DSP0270: 8.2)
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum NetworkDevice {
+    Usb {
+        #[serde(serialize_with = "hex_id")]
+        vendor_id: u16,
+        #[serde(serialize_with = "hex_id")]
+        product_id: u16,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        serial_number: Option<String>,
+        #[serde(serialize_with = "hex_mac", skip_serializing_if = "Option::is_none")]
+        mac_address: Option<[u8; 6]>,
+    },
+    Pci {
+        #[serde(serialize_with = "hex_id")]
+        vendor_id: u16,
+        #[serde(serialize_with = "hex_id")]
+        device_id: u16,
+        #[serde(serialize_with = "hex_id")]
+        subsystem_vendor_id: u16,
+        #[serde(serialize_with = "hex_id")]
+        subsystem_id: u16,
+        #[serde(serialize_with = "hex_mac", skip_serializing_if = "Option::is_none")]
+        mac_address: Option<[u8; 6]>,
+    },
+    Oem {
+        #[serde(serialize_with = "hex_bytes")]
+        vendor_id: [u8; 4],
+    },
+}
+
+/// USB and PCI IDs as lsusb and lspci print them, "046b"
+fn hex_id<S: serde::Serializer>(id: &u16, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{:04x}", id))
+}
+
+/// OEM vendor IDs, "00:00:01:57"
+fn hex_bytes<S: serde::Serializer>(bytes: &[u8; 4], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&colon_hex(bytes))
+}
+
+/// MAC addresses, "02:00:00:00:00:01"
+fn hex_mac<S: serde::Serializer>(mac: &Option<[u8; 6]>, serializer: S) -> Result<S::Ok, S::Error> {
+    match mac {
+        Some(mac) => serializer.serialize_str(&colon_hex(mac)),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Lowercase hex bytes separated by colons
+fn colon_hex(bytes: &[u8]) -> String {
+    let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    hex.join(":")
+}
+
+impl NetworkDevice {
+    /// Short description, "USB 046b:ffb0" or "PCI 8086:1533"
+    fn describe(&self) -> String {
+        let mut description = match self {
+            NetworkDevice::Usb {
+                vendor_id,
+                product_id,
+                serial_number,
+                ..
+            } => match serial_number {
+                Some(serial) => {
+                    format!("USB {:04x}:{:04x} serial {}", vendor_id, product_id, serial)
+                }
+                None => format!("USB {:04x}:{:04x}", vendor_id, product_id),
+            },
+            NetworkDevice::Pci {
+                vendor_id,
+                device_id,
+                subsystem_vendor_id,
+                subsystem_id,
+                ..
+            } => format!(
+                "PCI {:04x}:{:04x} (subsystem {:04x}:{:04x})",
+                vendor_id, device_id, subsystem_vendor_id, subsystem_id
+            ),
+            NetworkDevice::Oem { vendor_id } => format!("OEM {}", colon_hex(vendor_id)),
+        };
+        if let Some(mac) = self.mac_address() {
+            let _ = write!(description, ", MAC {}", mac);
+        }
+        description
+    }
+
+    /// MAC address of the NIC, given by the v2 descriptors
+    fn mac_address(&self) -> Option<String> {
+        match self {
+            NetworkDevice::Usb { mac_address, .. } | NetworkDevice::Pci { mac_address, .. } => {
+                mac_address.as_ref().map(|mac| colon_hex(mac))
+            }
+            NetworkDevice::Oem { .. } => None,
+        }
+    }
+}
+
+/// Host IP assignment or service IP discovery type (DSP0270: 8.6)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Assignment {
+    Unknown,
+    Static,
+    Dhcp,
+    Auto,
+    HostSelected,
+}
+
+impl Assignment {
+    fn from_raw(raw: u8) -> Self {
+        match raw {
+            0x1 => Assignment::Static,
+            0x2 => Assignment::Dhcp,
+            0x3 => Assignment::Auto,
+            0x4 => Assignment::HostSelected,
+            _ => Assignment::Unknown,
+        }
+    }
+
+    /// Whether the address and mask fields are meaningful, as dmidecode
+    /// decides it
+    fn has_address(&self) -> bool {
+        matches!(self, Assignment::Static | Assignment::Auto)
+    }
+}
+
+/// An address, its mask and prefix length
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Network {
+    pub address: IpAddr,
+    pub mask: IpAddr,
+    pub prefix_length: u32,
+}
+
+impl Network {
+    /// Address and mask of the given format (1: IPv4, 2: IPv6) at the start
+    /// of `data`: 16 bytes each, IPv4 in the first 4
+    fn decode(data: &[u8], format: u8) -> Option<Self> {
+        let (address, mask) = match format {
+            0x1 => {
+                let address: [u8; 4] = data.get(0..4)?.try_into().ok()?;
+                let mask: [u8; 4] = data.get(16..20)?.try_into().ok()?;
+                (IpAddr::from(address), IpAddr::from(mask))
+            }
+            0x2 => {
+                let address: [u8; 16] = data.get(0..16)?.try_into().ok()?;
+                let mask: [u8; 16] = data.get(16..32)?.try_into().ok()?;
+                (IpAddr::from(address), IpAddr::from(mask))
+            }
+            _ => return None,
+        };
+        let prefix_length = match mask {
+            IpAddr::V4(mask) => u32::from(mask).count_ones(),
+            IpAddr::V6(mask) => u128::from(mask).count_ones(),
+        };
+        Some(Network {
+            address,
+            mask,
+            prefix_length,
+        })
+    }
+}
+
+/// IP family of an address format (1: IPv4, 2: IPv6)
+fn family(format: u8) -> Option<&'static str> {
+    match format {
+        0x1 => Some("ipv4"),
+        0x2 => Some("ipv6"),
+        _ => None,
+    }
+}
+
+/// Host side settings
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct HostSettings {
+    pub assignment: Assignment,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub family: Option<&'static str>,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub network: Option<Network>,
+}
+
+/// Where the Redfish service listens
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct ServiceSettings {
+    pub discovery: Assignment,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub family: Option<&'static str>,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub network: Option<Network>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vlan: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+}
+
+/// A Redfish over IP protocol record
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct RedfishService {
+    pub service_uuid: String,
+    pub host: HostSettings,
+    pub service: ServiceSettings,
+}
+
+impl RedfishService {
+    /// Decodes the protocol specific data of a Redfish over IP record
+    fn decode(rdata: &[u8]) -> Option<Self> {
+        if rdata.len() < REDFISH_OVER_IP_LENGTH {
+            return None;
+        }
+        // The first three UUID fields are little-endian
+        let mut service_uuid = String::new();
+        for (index, &offset) in [3, 2, 1, 0, 5, 4, 7, 6, 8, 9, 10, 11, 12, 13, 14, 15]
+            .iter()
+            .enumerate()
+        {
+            if [4, 6, 8, 10].contains(&index) {
+                service_uuid.push('-');
+            }
+            service_uuid.push_str(&format!("{:02X}", rdata[offset]));
+        }
+
+        let assignment = Assignment::from_raw(rdata[16]);
+        let host = HostSettings {
+            assignment,
+            family: family(rdata[17]),
+            network: match assignment.has_address() {
+                true => Network::decode(&rdata[18..50], rdata[17]),
+                false => None,
+            },
+        };
+
+        let discovery = Assignment::from_raw(rdata[50]);
+        let (network, port, vlan) = match discovery.has_address() {
+            true => (
+                Network::decode(&rdata[52..84], rdata[51]),
+                Some(u16::from_le_bytes([rdata[84], rdata[85]])),
+                Some(u32::from_le_bytes([
+                    rdata[86], rdata[87], rdata[88], rdata[89],
+                ])),
+            ),
+            false => (None, None, None),
+        };
+        let hostname = rdata
+            .get(REDFISH_OVER_IP_LENGTH..REDFISH_OVER_IP_LENGTH + rdata[90] as usize)
+            .map(|name| {
+                name.iter()
+                    .take_while(|&&c| c != 0)
+                    .map(|&c| c as char)
+                    .collect::<String>()
+            })
+            .filter(|name| !name.is_empty());
+        Some(RedfishService {
+            service_uuid,
+            host,
+            service: ServiceSettings {
+                discovery,
+                family: family(rdata[51]),
+                network,
+                port,
+                vlan,
+                hostname,
+            },
+        })
+    }
+
+    /// https://address:port/ of the service, when its address is known
+    fn url(&self) -> Option<String> {
+        let address = match self.service.network.as_ref()?.address {
+            IpAddr::V4(address) => address.to_string(),
+            IpAddr::V6(address) => format!("[{}]", address),
+        };
+        Some(match self.service.port {
+            Some(port) if port != 443 => format!("https://{}:{}/", address, port),
+            _ => format!("https://{}/", address),
+        })
+    }
+
+    /// VLAN of the service, when there is one
+    fn vlan(&self) -> Option<u32> {
+        self.service.vlan.filter(|&vlan| vlan != 0)
+    }
+}
+
+/// A Network Host Interface
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct HostInterface {
+    pub handle: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device: Option<NetworkDevice>,
+    pub services: Vec<RedfishService>,
+}
+
+/// Decodes the USB or PCI device descriptor (DSP0270: 8.2); `strings` are
+/// those of the structure, which the USB v2 descriptor refers to
+fn decode_device(specific_data: &[u8], strings: &SMBiosStringSet) -> Option<NetworkDevice> {
+    let (&device_type, descriptor) = specific_data.split_first()?;
+    // The v2 descriptors start with their length, counting that byte
+    let descriptor = match device_type {
+        0x4 | 0x5 => descriptor.get(..*descriptor.first()? as usize)?,
+        _ => descriptor,
+    };
+    let word = |offset: usize| -> Option<u16> {
+        let bytes = descriptor.get(offset..offset + 2)?;
+        Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+    };
+    let mac =
+        |offset: usize| -> Option<[u8; 6]> { descriptor.get(offset..offset + 6)?.try_into().ok() };
+    match device_type {
+        0x2 => Some(NetworkDevice::Usb {
+            vendor_id: word(0)?,
+            product_id: word(2)?,
+            serial_number: usb_serial_number(descriptor.get(4..)?),
+            mac_address: None,
+        }),
+        0x3 => Some(NetworkDevice::Pci {
+            vendor_id: word(0)?,
+            device_id: word(2)?,
+            subsystem_vendor_id: word(4)?,
+            subsystem_id: word(6)?,
+            mac_address: None,
+        }),
+        // USB Network Interface v2: the serial number is a string of the
+        // structure
+        0x4 => Some(NetworkDevice::Usb {
+            vendor_id: word(1)?,
+            product_id: word(3)?,
+            serial_number: strings
+                .get_string(*descriptor.get(5)?)
+                .ok()
+                .map(|serial| serial.trim().to_string())
+                .filter(|serial| !serial.is_empty()),
+            mac_address: Some(mac(6)?),
+        }),
+        // PCI/PCIe Network Interface v2
+        0x5 => Some(NetworkDevice::Pci {
+            vendor_id: word(1)?,
+            device_id: word(3)?,
+            subsystem_vendor_id: word(5)?,
+            subsystem_id: word(7)?,
+            mac_address: Some(mac(9)?),
+        }),
+        0x80..=0xFF => Some(NetworkDevice::Oem {
+            vendor_id: descriptor.get(0..4)?.try_into().ok()?,
+        }),
+        _ => None,
+    }
+}
+
+/// Decodes a USB string descriptor: bLength, bDescriptorType (3), then
+/// UTF-16LE characters
+fn usb_serial_number(descriptor: &[u8]) -> Option<String> {
+    let length = *descriptor.first()? as usize;
+    if length < 2 || descriptor.get(1) != Some(&0x03) {
+        return None;
+    }
+    let units: Vec<u16> = descriptor
+        .get(2..length)?
+        .chunks_exact(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+        .collect();
+    let serial = String::from_utf16_lossy(&units);
+    let serial = serial.trim_end_matches('\0').trim();
+    match serial.is_empty() {
+        true => None,
+        false => Some(serial.to_string()),
+    }
+}
+
+/// Every Network Host Interface of the table
+pub fn host_interfaces(data: &SMBiosData) -> Vec<HostInterface> {
+    let mut interfaces = Vec::new();
+    for undefined_struct in data.iter() {
+        let interface = match undefined_struct.defined_struct() {
+            DefinedStruct::ManagementControllerHostInterface(interface) => interface,
+            _ => continue,
+        };
+        match interface.interface_type() {
+            Some(interface_type)
+                if interface_type.value == HostInterfaceType::NetworkHostInterface => {}
+            _ => continue,
+        }
+        let device = interface
+            .interface_type_specific_data()
+            .and_then(|specific_data| decode_device(specific_data, &undefined_struct.strings));
+        let services = interface
+            .protocol_record_iterator()
+            .filter(|record| {
+                record
+                    .protocol_type()
+                    .is_some_and(|protocol| protocol.value == HostProtocolType::RedfishOverIP)
+            })
+            .filter_map(|record| RedfishService::decode(record.protocol_type_specific_data()?))
+            .collect();
+        interfaces.push(HostInterface {
+            handle: *undefined_struct.header.handle(),
+            device,
+            services,
+        });
+    }
+    interfaces
+}
+
+/// Comment lines that identify the interface and the service
+fn write_header(output: &mut String, interface: &HostInterface, service: &RedfishService) {
+    let _ = write!(
+        output,
+        "# Redfish Host Interface, handle {:#06X}",
+        interface.handle
+    );
+    if let Some(device) = interface.device.as_ref() {
+        let _ = write!(output, ": {}", device.describe());
+    }
+    output.push('\n');
+    if let Some(url) = service.url() {
+        let _ = write!(output, "# Redfish service: {}", url);
+        if let Some(hostname) = service.service.hostname.as_ref() {
+            let _ = write!(output, " ({})", hostname);
+        }
+        output.push('\n');
+    }
+    if let Some(vlan) = service.vlan() {
+        let _ = writeln!(output, "# The service is on VLAN {}", vlan);
+    }
+}
+
+/// Writes a NetworkManager keyfile for each interface
+pub fn write_networkmanager(interfaces: &[HostInterface]) -> String {
+    let mut output = String::new();
+    for interface in interfaces.iter() {
+        let service = match interface.services.first() {
+            Some(service) => service,
+            None => continue,
+        };
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        write_header(&mut output, interface, service);
+        output.push_str("[connection]\n");
+        let _ = writeln!(output, "id=redfish-host-interface-{:04x}", interface.handle);
+        let device = interface
+            .device
+            .as_ref()
+            .map(|device| device.describe())
+            .unwrap_or_else(|| "the host interface".to_string());
+        let mac = interface
+            .device
+            .as_ref()
+            .and_then(NetworkDevice::mac_address);
+        match (service.vlan(), mac.as_ref()) {
+            // The parent of a VLAN may be given by the MAC address instead
+            (Some(vlan), Some(mac)) => {
+                output.push_str("type=vlan\n");
+                let _ = writeln!(output, "\n[vlan]\nid={}", vlan);
+                let _ = writeln!(output, "\n[ethernet]\nmac-address={}", mac);
+            }
+            (Some(vlan), None) => {
+                output.push_str("type=vlan\n");
+                let _ = writeln!(
+                    output,
+                    "\n[vlan]\nid={}\n# Set parent to the network device of {}\n#parent=",
+                    vlan, device
+                );
+            }
+            (None, Some(mac)) => {
+                output.push_str("type=ethernet\n");
+                let _ = writeln!(output, "\n[ethernet]\nmac-address={}", mac);
+            }
+            (None, None) => {
+                output.push_str("type=ethernet\n");
+                let _ = writeln!(
+                    output,
+                    "# Set interface-name to the network device of {}\n#interface-name=",
+                    device
+                );
+            }
+        }
+
+        let (ipv4, ipv6) = match (service.host.family, host_method_nm(service)) {
+            (Some("ipv6"), method) => ("disabled".to_string(), method),
+            (_, method) => (method, "ignore".to_string()),
+        };
+        for (section, method) in [("ipv4", ipv4), ("ipv6", ipv6)] {
+            let _ = writeln!(output, "\n[{}]\nmethod={}", section, method);
+            if method == "manual" && service.host.family == Some(section) {
+                if let Some(network) = service.host.network.as_ref() {
+                    let _ = writeln!(
+                        output,
+                        "address1={}/{}",
+                        network.address, network.prefix_length
+                    );
+                }
+            }
+            if method != "disabled" && method != "ignore" {
+                output.push_str("never-default=true\n");
+            }
+        }
+    }
+    output
+}
+
+/// NetworkManager method for the host side of a service
+fn host_method_nm(service: &RedfishService) -> String {
+    let method = match (service.host.assignment, service.host.network.is_some()) {
+        (Assignment::Static, true) => "manual",
+        (Assignment::Dhcp, _) => "auto",
+        (Assignment::Auto, _) if service.host.family == Some("ipv6") => "auto",
+        _ => "link-local",
+    };
+    method.to_string()
+}
+
+/// Writes a systemd-networkd `.network` file for each interface
+pub fn write_networkd(interfaces: &[HostInterface]) -> String {
+    let mut output = String::new();
+    for interface in interfaces.iter() {
+        let service = match interface.services.first() {
+            Some(service) => service,
+            None => continue,
+        };
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        write_header(&mut output, interface, service);
+        output.push_str("[Match]\n");
+        let mac = interface
+            .device
+            .as_ref()
+            .and_then(NetworkDevice::mac_address);
+        match (interface.device.as_ref(), mac) {
+            (_, Some(mac)) => {
+                let _ = writeln!(output, "MACAddress={}", mac);
+            }
+            (
+                Some(NetworkDevice::Usb {
+                    vendor_id,
+                    product_id,
+                    ..
+                }),
+                None,
+            ) => {
+                let _ = writeln!(
+                    output,
+                    "Property=ID_VENDOR_ID={:04x} ID_MODEL_ID={:04x}",
+                    vendor_id, product_id
+                );
+            }
+            (device, None) => {
+                let device = device
+                    .map(|device| device.describe())
+                    .unwrap_or_else(|| "the host interface".to_string());
+                let _ = writeln!(
+                    output,
+                    "# Set Name to the network device of {}\n#Name=",
+                    device
+                );
+            }
+        }
+
+        output.push_str("\n[Network]\n");
+        let ipv6 = service.host.family == Some("ipv6");
+        match (service.host.assignment, service.host.network.as_ref()) {
+            (Assignment::Static, Some(network)) => {
+                let _ = writeln!(
+                    output,
+                    "Address={}/{}",
+                    network.address, network.prefix_length
+                );
+                output.push_str("LinkLocalAddressing=no\n");
+            }
+            (Assignment::Dhcp, _) => {
+                let _ = writeln!(output, "DHCP={}", if ipv6 { "ipv6" } else { "ipv4" });
+            }
+            (Assignment::Auto, _) if ipv6 => output.push_str("IPv6AcceptRA=yes\n"),
+            _ => output.push_str("LinkLocalAddressing=ipv4\n"),
+        }
+        if let Some(vlan) = service.vlan() {
+            let _ = writeln!(
+                output,
+                "# The service is on VLAN {}: add VLAN= and a .netdev with [VLAN] Id={}",
+                vlan, vlan
+            );
+        }
+    }
+    output
+}
+
+#[test]
+fn test_redfish_service() {
+    let mut rdata = vec![0u8; REDFISH_OVER_IP_LENGTH];
+    rdata[0..16].copy_from_slice(&[
+        0x33, 0x22, 0x11, 0x00, 0x55, 0x44, 0x77, 0x66, 0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE,
+        0xFF,
+    ]);
+    // Host: static 169.254.0.10/16
+    rdata[16] = 0x1;
+    rdata[17] = 0x1;
+    rdata[18..22].copy_from_slice(&[169, 254, 0, 10]);
+    rdata[34..38].copy_from_slice(&[255, 255, 0, 0]);
+    // Service: static 169.254.0.17/16, port 8443, VLAN 0
+    rdata[50] = 0x1;
+    rdata[51] = 0x1;
+    rdata[52..56].copy_from_slice(&[169, 254, 0, 17]);
+    rdata[68..72].copy_from_slice(&[255, 255, 0, 0]);
+    rdata[84..86].copy_from_slice(&8443u16.to_le_bytes());
+    rdata[90] = 3;
+    rdata.extend_from_slice(b"bmc");
+
+    let service = RedfishService::decode(&rdata).unwrap();
+    assert_eq!(service.service_uuid, "00112233-4455-6677-8899-AABBCCDDEEFF");
+    assert_eq!(service.host.assignment, Assignment::Static);
+    assert_eq!(
+        service.host.network.as_ref().unwrap().address.to_string(),
+        "169.254.0.10"
+    );
+    assert_eq!(service.host.network.as_ref().unwrap().prefix_length, 16);
+    assert_eq!(service.service.port, Some(8443));
+    assert_eq!(service.service.hostname.as_deref(), Some("bmc"));
+    assert_eq!(service.url().as_deref(), Some("https://169.254.0.17:8443/"));
+    assert!(RedfishService::decode(&rdata[..90]).is_none());
+
+    let mut usb = vec![0x2, 0x6B, 0x04, 0xB0, 0xFF, 0x08, 0x03];
+    usb.extend_from_slice(&[b'A', 0, b'B', 0, b'C', 0]);
+    let no_strings = SMBiosStringSet::new(Vec::new());
+    assert_eq!(
+        decode_device(&usb, &no_strings),
+        Some(NetworkDevice::Usb {
+            vendor_id: 0x046B,
+            product_id: 0xFFB0,
+            serial_number: Some("ABC".to_string()),
+            mac_address: None,
+        })
+    );
+
+    // USB v2: serial number string 1, MAC address, characteristics and
+    // credential bootstrapping handle
+    let usb_v2 = [
+        0x4, 0x10, 0x6B, 0x04, 0xB0, 0xFF, 0x01, 0x02, 0x00, 0x00, 0x5E, 0x00, 0x53, 0x00, 0x00,
+        0xFF, 0xFF,
+    ];
+    let strings = SMBiosStringSet::new(b"XYZ\0\0".to_vec());
+    assert_eq!(
+        decode_device(&usb_v2, &strings),
+        Some(NetworkDevice::Usb {
+            vendor_id: 0x046B,
+            product_id: 0xFFB0,
+            serial_number: Some("XYZ".to_string()),
+            mac_address: Some([0x02, 0x00, 0x00, 0x5E, 0x00, 0x53]),
+        })
+    );
+    assert_eq!(decode_device(&usb_v2[..12], &strings), None);
+
+    // PCI v2: IDs, MAC address, segment, bus, device/function,
+    // characteristics and credential bootstrapping handle
+    let pci_v2 = [
+        0x5, 0x17, 0x86, 0x80, 0x33, 0x15, 0x86, 0x80, 0x00, 0x00, 0x02, 0x00, 0x00, 0x5E, 0x00,
+        0x54, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0xFF, 0xFF,
+    ];
+    let pci = decode_device(&pci_v2, &no_strings).unwrap();
+    assert_eq!(pci.mac_address().as_deref(), Some("02:00:00:5e:00:54"));
+    assert_eq!(
+        pci.describe(),
+        "PCI 8086:1533 (subsystem 8086:0000), MAC 02:00:00:5e:00:54"
+    );
+    assert_eq!(
+        serde_json::to_value(&pci).unwrap()["mac_address"],
+        "02:00:00:5e:00:54"
+    );
+
+    let interfaces = vec![HostInterface {
+        handle: 0x2A,
+        device: decode_device(&usb, &no_strings),
+        services: vec![service],
+    }];
+    let networkd = write_networkd(&interfaces);
+    assert!(networkd.contains("Property=ID_VENDOR_ID=046b ID_MODEL_ID=ffb0\n"));
+    assert!(networkd.contains("Address=169.254.0.10/16\n"));
+    let keyfile = write_networkmanager(&interfaces);
+    assert!(keyfile.contains("[ipv4]\nmethod=manual\naddress1=169.254.0.10/16\n"));
+    assert!(keyfile.contains("[ipv6]\nmethod=ignore\n"));
+    assert!(keyfile.contains("#interface-name=\n"));
+
+    // With a MAC address, the interface is matched by it
+    let interfaces = vec![HostInterface {
+        device: decode_device(&usb_v2, &strings),
+        ..interfaces.into_iter().next().unwrap()
+    }];
+    let networkd = write_networkd(&interfaces);
+    assert!(networkd.contains("[Match]\nMACAddress=02:00:00:5e:00:53\n"));
+    let keyfile = write_networkmanager(&interfaces);
+    assert!(keyfile.contains("type=ethernet\n\n[ethernet]\nmac-address=02:00:00:5e:00:53\n"));
+    assert!(!keyfile.contains("interface-name"));
+}
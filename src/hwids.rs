@@ -0,0 +1,434 @@
+//! Computer Hardware IDs (CHIDs)
+//!
+//! Windows Update and fwupd match firmware to machines using "hardware ID"
+//! GUIDs.  Each one is a name-based (version 5, SHA-1) UUID computed over a
+//! combination of SMBIOS strings, exactly as `ComputerHardwareIds.exe` does.
+
+use smbioslib::*;
+use std::fmt::Write;
+
+/// Namespace used by `ComputerHardwareIds.exe` (and fwupd) for every CHID.
+const CHID_NAMESPACE: [u8; 16] = [
+    0x70, 0xff, 0xd8, 0x12, 0x4c, 0x7f, 0x4c, 0x7d, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+/// Keys used to build the hardware ID strings, named as fwupd names them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HwidKey {
+    Manufacturer,
+    Family,
+    ProductName,
+    ProductSku,
+    BiosVendor,
+    BiosVersion,
+    BiosMajorRelease,
+    BiosMinorRelease,
+    EnclosureKind,
+    BaseboardManufacturer,
+    BaseboardProduct,
+}
+
+impl HwidKey {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HwidKey::Manufacturer => "Manufacturer",
+            HwidKey::Family => "Family",
+            HwidKey::ProductName => "ProductName",
+            HwidKey::ProductSku => "ProductSku",
+            HwidKey::BiosVendor => "BiosVendor",
+            HwidKey::BiosVersion => "BiosVersion",
+            HwidKey::BiosMajorRelease => "BiosMajorRelease",
+            HwidKey::BiosMinorRelease => "BiosMinorRelease",
+            HwidKey::EnclosureKind => "EnclosureKind",
+            HwidKey::BaseboardManufacturer => "BaseboardManufacturer",
+            HwidKey::BaseboardProduct => "BaseboardProduct",
+        }
+    }
+}
+
+/// The order in which the computer information is displayed
+const INFORMATION_KEYS: [HwidKey; 11] = [
+    HwidKey::BiosVendor,
+    HwidKey::BiosVersion,
+    HwidKey::BiosMajorRelease,
+    HwidKey::BiosMinorRelease,
+    HwidKey::Manufacturer,
+    HwidKey::Family,
+    HwidKey::ProductName,
+    HwidKey::ProductSku,
+    HwidKey::EnclosureKind,
+    HwidKey::BaseboardManufacturer,
+    HwidKey::BaseboardProduct,
+];
+
+/// HardwareID-0 through HardwareID-14, most specific first
+const HARDWARE_IDS: [&[HwidKey]; 15] = [
+    &[
+        HwidKey::Manufacturer,
+        HwidKey::Family,
+        HwidKey::ProductName,
+        HwidKey::ProductSku,
+        HwidKey::BiosVendor,
+        HwidKey::BiosVersion,
+        HwidKey::BiosMajorRelease,
+        HwidKey::BiosMinorRelease,
+    ],
+    &[
+        HwidKey::Manufacturer,
+        HwidKey::Family,
+        HwidKey::ProductName,
+        HwidKey::BiosVendor,
+        HwidKey::BiosVersion,
+        HwidKey::BiosMajorRelease,
+        HwidKey::BiosMinorRelease,
+    ],
+    &[
+        HwidKey::Manufacturer,
+        HwidKey::ProductName,
+        HwidKey::BiosVendor,
+        HwidKey::BiosVersion,
+        HwidKey::BiosMajorRelease,
+        HwidKey::BiosMinorRelease,
+    ],
+    &[
+        HwidKey::Manufacturer,
+        HwidKey::Family,
+        HwidKey::ProductName,
+        HwidKey::ProductSku,
+        HwidKey::BaseboardManufacturer,
+        HwidKey::BaseboardProduct,
+    ],
+    &[
+        HwidKey::Manufacturer,
+        HwidKey::Family,
+        HwidKey::ProductName,
+        HwidKey::ProductSku,
+    ],
+    &[HwidKey::Manufacturer, HwidKey::Family, HwidKey::ProductName],
+    &[
+        HwidKey::Manufacturer,
+        HwidKey::ProductSku,
+        HwidKey::BaseboardManufacturer,
+        HwidKey::BaseboardProduct,
+    ],
+    &[HwidKey::Manufacturer, HwidKey::ProductSku],
+    &[
+        HwidKey::Manufacturer,
+        HwidKey::ProductName,
+        HwidKey::BaseboardManufacturer,
+        HwidKey::BaseboardProduct,
+    ],
+    &[HwidKey::Manufacturer, HwidKey::ProductName],
+    &[
+        HwidKey::Manufacturer,
+        HwidKey::Family,
+        HwidKey::BaseboardManufacturer,
+        HwidKey::BaseboardProduct,
+    ],
+    &[HwidKey::Manufacturer, HwidKey::Family],
+    &[HwidKey::Manufacturer, HwidKey::EnclosureKind],
+    &[
+        HwidKey::Manufacturer,
+        HwidKey::BaseboardManufacturer,
+        HwidKey::BaseboardProduct,
+    ],
+    &[HwidKey::Manufacturer],
+];
+
+/// A computed hardware ID
+pub struct HardwareId {
+    /// N of HardwareID-N
+    pub index: usize,
+    /// The GUID, formatted without braces
+    pub guid: String,
+    /// The keys the GUID was computed over
+    pub keys: &'static [HwidKey],
+}
+
+/// Looks up the value used for `key`, normalized the way `ComputerHardwareIds.exe` does.
+///
+/// Strings have leading and trailing whitespace removed. BIOS release numbers
+/// are two digit hex values and the enclosure kind is hex without padding.
+pub fn hwid_value(data: &SMBiosData, key: HwidKey) -> Option<String> {
+    let value = match key {
+        HwidKey::Manufacturer => data
+            .find_map(|system: SMBiosSystemInformation<'_>| system.manufacturer().to_utf8_lossy()),
+        HwidKey::Family => {
+            data.find_map(|system: SMBiosSystemInformation<'_>| system.family().to_utf8_lossy())
+        }
+        HwidKey::ProductName => data
+            .find_map(|system: SMBiosSystemInformation<'_>| system.product_name().to_utf8_lossy()),
+        HwidKey::ProductSku => {
+            data.find_map(|system: SMBiosSystemInformation<'_>| system.sku_number().to_utf8_lossy())
+        }
+        HwidKey::BiosVendor => {
+            data.find_map(|bios: SMBiosInformation<'_>| bios.vendor().to_utf8_lossy())
+        }
+        HwidKey::BiosVersion => {
+            data.find_map(|bios: SMBiosInformation<'_>| bios.version().to_utf8_lossy())
+        }
+        HwidKey::BiosMajorRelease => data
+            .find_map(|bios: SMBiosInformation<'_>| bios.system_bios_major_release())
+            .map(|major| format!("{:02x}", major)),
+        HwidKey::BiosMinorRelease => data
+            .find_map(|bios: SMBiosInformation<'_>| bios.system_bios_minor_release())
+            .map(|minor| format!("{:02x}", minor)),
+        HwidKey::EnclosureKind => data
+            .find_map(|chassis: SMBiosSystemChassisInformation<'_>| chassis.chassis_type())
+            // Bit 7 is the chassis lock, not part of the type
+            .map(|chassis_type| format!("{:x}", chassis_type.raw & 0x7F)),
+        HwidKey::BaseboardManufacturer => {
+            data.find_map(|baseboard: SMBiosBaseboardInformation<'_>| {
+                baseboard.manufacturer().to_utf8_lossy()
+            })
+        }
+        HwidKey::BaseboardProduct => data.find_map(|baseboard: SMBiosBaseboardInformation<'_>| {
+            baseboard.product().to_utf8_lossy()
+        }),
+    };
+
+    value.map(|value| value.trim().to_string())
+}
+
+/// Computes every hardware ID for which all of the keys are present.
+pub fn hardware_ids(data: &SMBiosData) -> Vec<HardwareId> {
+    HARDWARE_IDS
+        .iter()
+        .enumerate()
+        .filter_map(|(index, keys)| {
+            let values = keys
+                .iter()
+                .map(|key| hwid_value(data, *key))
+                .collect::<Option<Vec<_>>>()?;
+            Some(HardwareId {
+                index,
+                guid: chid_guid(&values.join("&")),
+                keys,
+            })
+        })
+        .collect()
+}
+
+/// Prints the computer information and hardware IDs in the style of `fwupdtool hwids`
+pub fn print_hwids(data: &SMBiosData) {
+    println!("Computer Information");
+    println!("--------------------");
+    for key in INFORMATION_KEYS.iter() {
+        if let Some(value) = hwid_value(data, *key) {
+            println!("{}: {}", key.name(), value);
+        }
+    }
+    println!();
+    println!("Hardware IDs");
+    println!("------------");
+    for hardware_id in hardware_ids(data) {
+        let keys = hardware_id
+            .keys
+            .iter()
+            .map(|key| key.name())
+            .collect::<Vec<_>>()
+            .join(" + ");
+        println!(
+            "{:<15}{{{}}}   <- {}",
+            format!("HardwareID-{}:", hardware_id.index),
+            hardware_id.guid,
+            keys
+        );
+    }
+}
+
+/// Hashes `value` into a CHID GUID.
+///
+/// The string is hashed as UTF-16LE after the namespace, then the version
+/// and variant bits are set as for any version 5 UUID.
+pub fn chid_guid(value: &str) -> String {
+    let mut message = CHID_NAMESPACE.to_vec();
+    for unit in value.encode_utf16() {
+        message.extend_from_slice(&unit.to_le_bytes());
+    }
+    let hash = sha1(&message);
+
+    let mut guid = [0u8; 16];
+    guid.copy_from_slice(&hash[..16]);
+    guid[6] = (guid[6] & 0x0F) | 0x50;
+    guid[8] = (guid[8] & 0x3F) | 0x80;
+
+    let mut output = String::new();
+    for (index, byte) in guid.iter().enumerate() {
+        if [4, 6, 8, 10].contains(&index) {
+            output.push('-');
+        }
+        write!(&mut output, "{:02x}", byte).unwrap();
+    }
+    output
+}
+
+/// SHA-1 digest of `message` (FIPS 180-4)
+pub(crate) fn sha1(message: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    let mut padded = message.to_vec();
+    padded.push(0x80);
+    while padded.len() % 64 != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&((message.len() as u64) * 8).to_be_bytes());
+
+    for block in padded.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        state[0] = state[0].wrapping_add(a);
+        state[1] = state[1].wrapping_add(b);
+        state[2] = state[2].wrapping_add(c);
+        state[3] = state[3].wrapping_add(d);
+        state[4] = state[4].wrapping_add(e);
+    }
+
+    let mut digest = [0u8; 20];
+    for (i, word) in state.iter().enumerate() {
+        digest[i * 4..i * 4 + 4].copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+#[test]
+fn test_sha1_known_answers() {
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+    assert_eq!(
+        hex(&sha1(b"abc")),
+        "a9993e364706816aba3e25717850c26c9cd0d89d"
+    );
+    assert_eq!(
+        hex(&sha1(
+            b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+        )),
+        "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+    );
+}
+
+#[test]
+fn test_chid_matches_fwupd() {
+    // HardwareID-14 (Manufacturer) from the fwupd self tests
+    assert_eq!(chid_guid("LENOVO"), "6de5d951-d755-576b-bd09-c5cf66b27234");
+}
+
+#[test]
+fn test_hardware_ids() {
+    use crate::build::push_struct;
+
+    // The ThinkPad T440s of the fwupd self tests, with padded strings and
+    // the chassis lock bit set
+    let mut table = Vec::new();
+    let mut bios = vec![0u8; 0x18];
+    bios[..6].copy_from_slice(&[0, 0x18, 0x00, 0x00, 1, 2]);
+    bios[0x14..0x16].copy_from_slice(&[2, 25]);
+    push_struct(
+        &mut table,
+        &bios,
+        &[b"LENOVO".to_vec(), b" GJET75WW (2.25 )  ".to_vec()],
+    );
+    let mut system = vec![0u8; 0x1B];
+    system[..6].copy_from_slice(&[1, 0x1B, 0x01, 0x00, 1, 2]);
+    system[0x19..0x1B].copy_from_slice(&[3, 4]);
+    push_struct(
+        &mut table,
+        &system,
+        &[
+            b"LENOVO  ".to_vec(),
+            b"20ARS19C0C".to_vec(),
+            b"LENOVO_MT_20AR_BU_Think_FM_ThinkPad T440s".to_vec(),
+            b"\tThinkPad T440s".to_vec(),
+        ],
+    );
+    push_struct(
+        &mut table,
+        &[2, 0x08, 0x02, 0x00, 1, 2, 0, 0],
+        &[b"LENOVO".to_vec(), b"20ARS19C0C ".to_vec()],
+    );
+    push_struct(
+        &mut table,
+        &[3, 0x09, 0x03, 0x00, 1, 0x8A, 0, 0, 0],
+        &[b"LENOVO".to_vec()],
+    );
+    push_struct(&mut table, &[127, 4, 0x04, 0x00], &[]);
+    let data = SMBiosData::from_vec_and_version(table, None);
+
+    let values = [
+        (HwidKey::Manufacturer, "LENOVO"),
+        (HwidKey::Family, "ThinkPad T440s"),
+        (HwidKey::ProductName, "20ARS19C0C"),
+        (
+            HwidKey::ProductSku,
+            "LENOVO_MT_20AR_BU_Think_FM_ThinkPad T440s",
+        ),
+        (HwidKey::BiosVendor, "LENOVO"),
+        (HwidKey::BiosVersion, "GJET75WW (2.25 )"),
+        (HwidKey::BiosMajorRelease, "02"),
+        (HwidKey::BiosMinorRelease, "19"),
+        (HwidKey::EnclosureKind, "a"),
+        (HwidKey::BaseboardManufacturer, "LENOVO"),
+        (HwidKey::BaseboardProduct, "20ARS19C0C"),
+    ];
+    for (key, value) in values.iter() {
+        assert_eq!(
+            hwid_value(&data, *key).as_deref(),
+            Some(*value),
+            "{:?}",
+            key
+        );
+    }
+
+    // As fwupd computes them for this machine
+    let expected = [
+        "147efce9-f201-5fc8-ab0c-c859751c3440",
+        "b7cceb67-774c-537e-bf8b-22c6107e9a74",
+        "f5ff077f-3eeb-5bae-be1c-e98ffe8ce5f8",
+        "3faec92a-3ae3-5744-be88-495e90a7d541",
+        "660ccba8-1b78-5a33-80e6-9fb8354ee873",
+        "8dc9b7c5-f5d5-5850-9ab3-bd6f0549d814",
+        "178cd22d-ad9f-562d-ae0a-34009822cdbe",
+        "da1da9b6-62f5-5f22-8aaa-14db7eeda2a4",
+        "059eb22d-6dc7-59af-abd3-94bbe017f67c",
+        "0cf8618d-9eff-537c-9f35-46861406eb9c",
+        "f4275c1f-6130-5191-845c-3426247eb6a1",
+        "db73af4c-4612-50f7-b8a7-787cf4871847",
+        "e093d715-70f7-51f4-b6c8-b4a7e31def85",
+        "f8e1de5f-b68c-5f52-9d1a-f1ba52f1f773",
+        "6de5d951-d755-576b-bd09-c5cf66b27234",
+    ];
+    let hardware_ids = hardware_ids(&data);
+    assert_eq!(hardware_ids.len(), expected.len());
+    for (index, (hardware_id, guid)) in hardware_ids.iter().zip(expected.iter()).enumerate() {
+        assert_eq!(hardware_id.index, index);
+        assert_eq!(hardware_id.guid, *guid, "HardwareID-{}", index);
+    }
+}
@@ -0,0 +1,327 @@
+//! IPMI driver parameters
+//!
+//! IPMI Device Information (type 38) describes the system interface to the
+//! BMC.  This module resolves it the way the Linux kernel does
+//! (drivers/char/ipmi/ipmi_dmi.c) and writes the parameters of the
+//! `ipmi_si` or `ipmi_ssif` driver, as modprobe options or on the kernel
+//! command line, or a FreeIPMI configuration.
+//!
+//! Address rules:
+//! - Bit 0 of the base address selects the space: 1 for I/O, 0 for memory.
+//!   The address itself has that bit cleared, then set back from the "LS-bit
+//!   for addresses" bit of the Base Address Modifier.
+//! - Structures older than SMBIOS 2.3 (no Base Address Modifier) got bit 0
+//!   wrong too often to trust it: the address is taken as an I/O port with
+//!   bit 0 clear.
+//! - SSIF: the low byte of the base address is the SMBus address of the BMC,
+//!   shifted left by one.
+
+use serde::Serialize;
+use smbioslib::*;
+use std::fmt::Write;
+
+/// Mask of an I/O port address in pre-2.3 structures
+const IO_MASK: u64 = 0xFFFE;
+
+/// Type of the system interface, as the drivers name it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InterfaceKind {
+    Kcs,
+    Smic,
+    Bt,
+    Ssif,
+}
+
+impl InterfaceKind {
+    fn name(&self) -> &'static str {
+        match self {
+            InterfaceKind::Kcs => "kcs",
+            InterfaceKind::Smic => "smic",
+            InterfaceKind::Bt => "bt",
+            InterfaceKind::Ssif => "ssif",
+        }
+    }
+}
+
+/// Address space of the base address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AddressSpace {
+    Io,
+    Memory,
+    Smbus,
+}
+
+/// One system interface, resolved for a driver
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct IpmiInterface {
+    pub handle: u16,
+    #[serde(rename = "type")]
+    pub kind: InterfaceKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub specification_version: Option<String>,
+    pub address_space: AddressSpace,
+    pub address: u64,
+    /// Bytes between registers: 1, 4 or 16
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub register_spacing: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub irq: Option<u8>,
+    /// IPMB address of the BMC (8-bit form, usually 0x20)
+    pub slave_address: u8,
+}
+
+impl IpmiInterface {
+    /// Resolves a type 38 structure, None for an unknown interface type
+    fn resolve(handle: u16, data: &SMBiosIpmiDeviceInformation<'_>) -> Option<Self> {
+        let kind = match data.interface_type()?.value {
+            IpmiInterfaceType::KeyboardControllerStyle => InterfaceKind::Kcs,
+            IpmiInterfaceType::ServerManagementInterfaceChip => InterfaceKind::Smic,
+            IpmiInterfaceType::BlockTransfer => InterfaceKind::Bt,
+            IpmiInterfaceType::SMBusSystemInterface => InterfaceKind::Ssif,
+            _ => return None,
+        };
+        let base_address = data.base_address()?;
+        let modifier = data.base_address_modifier();
+
+        let (address_space, address) = match (kind, modifier.as_ref()) {
+            (InterfaceKind::Ssif, _) => (AddressSpace::Smbus, (base_address & 0xFF) >> 1),
+            (_, Some(modifier)) => {
+                let address_space = match base_address & 1 == 1 {
+                    true => AddressSpace::Io,
+                    false => AddressSpace::Memory,
+                };
+                let address = match modifier.ls_address_bit {
+                    AddressBit::Zero => base_address & !1,
+                    AddressBit::One => base_address | 1,
+                };
+                (address_space, address)
+            }
+            (_, None) => (AddressSpace::Io, base_address & IO_MASK),
+        };
+
+        let (register_spacing, irq) = match (kind, modifier.as_ref()) {
+            (InterfaceKind::Ssif, _) => (None, None),
+            (_, Some(modifier)) => {
+                let register_spacing = match modifier.register_spacing {
+                    RegisterSpacing::BoundaryByte => Some(1),
+                    RegisterSpacing::Boundary32Bit => Some(4),
+                    RegisterSpacing::Boundary16Bit => Some(16),
+                    RegisterSpacing::None => None,
+                };
+                let irq = match modifier.interrupt_info {
+                    InterruptInfo::Specified => data.interrupt_number().filter(|&irq| irq != 0),
+                    InterruptInfo::NotSpecified => None,
+                };
+                (register_spacing, irq)
+            }
+            (_, None) => (Some(1), None),
+        };
+
+        Some(IpmiInterface {
+            handle,
+            kind,
+            specification_version: data
+                .ipmi_specification_revision()
+                .map(|revision| format!("{}.{}", revision >> 4, revision & 0x0F)),
+            address_space,
+            address,
+            register_spacing,
+            irq,
+            slave_address: data.i2c_target_address().unwrap_or(0x20),
+        })
+    }
+
+    /// Comment line describing the interface
+    fn describe(&self) -> String {
+        let space = match self.address_space {
+            AddressSpace::Io => "I/O port",
+            AddressSpace::Memory => "memory address",
+            AddressSpace::Smbus => "SMBus address",
+        };
+        format!(
+            "# IPMI Device Information, handle {:#06X}: {} at {} {:#x}",
+            self.handle,
+            self.kind.name().to_uppercase(),
+            space,
+            self.address
+        )
+    }
+}
+
+/// Every IPMI system interface of the table
+pub fn ipmi_interfaces(data: &SMBiosData) -> Vec<IpmiInterface> {
+    data.iter()
+        .filter_map(|undefined_struct| match undefined_struct.defined_struct() {
+            DefinedStruct::IpmiDeviceInformation(ipmi) => {
+                IpmiInterface::resolve(*undefined_struct.header.handle(), &ipmi)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Parameters of a driver, in order, with the values of each interface
+type Parameters = Vec<(&'static str, Vec<String>)>;
+
+/// Parameters of ipmi_si and of ipmi_ssif for the interfaces
+///
+/// The array parameters of ipmi_si are matched by position: an interface
+/// uses its ports= entry if it is not 0, its addrs= entry otherwise.
+fn driver_parameters(interfaces: &[IpmiInterface]) -> Vec<(&'static str, Parameters)> {
+    let (ssif, si): (Vec<&IpmiInterface>, Vec<&IpmiInterface>) = interfaces
+        .iter()
+        .partition(|interface| interface.kind == InterfaceKind::Ssif);
+    let mut drivers = Vec::new();
+
+    if !si.is_empty() {
+        let column = |value: &dyn Fn(&IpmiInterface) -> String| -> Vec<String> {
+            si.iter().map(|interface| value(interface)).collect()
+        };
+        let address_in = |space: AddressSpace| {
+            move |interface: &IpmiInterface| match interface.address_space == space {
+                true => format!("{:#x}", interface.address),
+                false => "0".to_string(),
+            }
+        };
+        let mut parameters: Parameters = vec![(
+            "type",
+            column(&|interface| interface.kind.name().to_string()),
+        )];
+        if si.iter().any(|i| i.address_space == AddressSpace::Io) {
+            parameters.push(("ports", column(&address_in(AddressSpace::Io))));
+        }
+        if si.iter().any(|i| i.address_space == AddressSpace::Memory) {
+            parameters.push(("addrs", column(&address_in(AddressSpace::Memory))));
+        }
+        parameters.push((
+            "regspacings",
+            column(&|interface| interface.register_spacing.unwrap_or(1).to_string()),
+        ));
+        parameters.push((
+            "irqs",
+            column(&|interface| interface.irq.unwrap_or(0).to_string()),
+        ));
+        parameters.push((
+            "slave_addrs",
+            column(&|interface| format!("{:#x}", interface.slave_address)),
+        ));
+        drivers.push(("ipmi_si", parameters));
+    }
+    if !ssif.is_empty() {
+        let addresses = ssif
+            .iter()
+            .map(|interface| format!("{:#x}", interface.address))
+            .collect();
+        drivers.push(("ipmi_ssif", vec![("addr", addresses)]));
+    }
+    drivers
+}
+
+/// Writes /etc/modprobe.d options lines
+pub fn write_modprobe(interfaces: &[IpmiInterface]) -> String {
+    let mut output = String::new();
+    for interface in interfaces.iter() {
+        let _ = writeln!(output, "{}", interface.describe());
+    }
+    for (driver, parameters) in driver_parameters(interfaces) {
+        let _ = write!(output, "options {}", driver);
+        for (name, values) in parameters {
+            let _ = write!(output, " {}={}", name, values.join(","));
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Writes kernel command line parameters, for drivers built into the kernel
+pub fn write_cmdline(interfaces: &[IpmiInterface]) -> String {
+    let mut parameters = Vec::new();
+    for (driver, driver_parameters) in driver_parameters(interfaces) {
+        for (name, values) in driver_parameters {
+            parameters.push(format!("{}.{}={}", driver, name, values.join(",")));
+        }
+    }
+    format!("{}\n", parameters.join(" "))
+}
+
+/// Writes a FreeIPMI configuration (freeipmi.conf) for the first interface
+///
+/// FreeIPMI drives KCS at an I/O port and SSIF itself; anything else goes
+/// through the OpenIPMI kernel driver, the one ipmitool -I open uses.
+pub fn write_freeipmi(interfaces: &[IpmiInterface]) -> String {
+    let mut output = String::new();
+    let interface = match interfaces.first() {
+        Some(interface) => interface,
+        None => return output,
+    };
+    let _ = writeln!(output, "{}", interface.describe());
+    match (interface.kind, interface.address_space) {
+        (InterfaceKind::Kcs, AddressSpace::Io) => {
+            output.push_str("driver-type KCS\n");
+            let _ = writeln!(output, "driver-address {:#x}", interface.address);
+            let _ = writeln!(
+                output,
+                "register-spacing {}",
+                interface.register_spacing.unwrap_or(1)
+            );
+        }
+        (InterfaceKind::Ssif, _) => {
+            output.push_str("driver-type SSIF\n");
+            let _ = writeln!(output, "driver-address {:#x}", interface.address);
+            output.push_str(
+                "# Set driver-device to the I2C bus of the BMC\n#driver-device /dev/i2c-0\n",
+            );
+        }
+        _ => {
+            output.push_str("# Load ipmi_si and ipmi_devintf (see --ipmi-config modprobe)\n");
+            output.push_str("driver-type OPENIPMI\n");
+        }
+    }
+    if interface.slave_address != 0x20 {
+        let _ = writeln!(
+            output,
+            "target-slave-address {:#x}",
+            interface.slave_address
+        );
+    }
+    output
+}
+
+#[test]
+fn test_ipmi_interfaces() {
+    let table = vec![
+        // KCS at I/O 0xCA2, byte spacing
+        0x26, 0x12, 0x26, 0x00, 0x01, 0x20, 0x20, 0xFF, 0xA3, 0x0C, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, // BT at memory 0xFED40000, 32-bit spacing, IRQ 10
+        0x26, 0x12, 0x27, 0x00, 0x03, 0x20, 0x20, 0xFF, 0x00, 0x00, 0xD4, 0xFE, 0x00, 0x00, 0x00,
+        0x00, 0x48, 0x0A, 0x00, 0x00, // SMIC, SMBIOS 2.0 length, bit 0 clear
+        0x26, 0x10, 0x28, 0x00, 0x02, 0x10, 0x20, 0xFF, 0xA8, 0x0C, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, // SSIF at SMBus 0x10
+        0x26, 0x12, 0x29, 0x00, 0x04, 0x20, 0x20, 0xFF, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    let interfaces = ipmi_interfaces(&SMBiosData::from_vec_and_version(table, None));
+    assert_eq!(interfaces.len(), 4);
+    assert_eq!(interfaces[0].address_space, AddressSpace::Io);
+    assert_eq!(interfaces[0].address, 0xCA2);
+    assert_eq!(interfaces[1].address_space, AddressSpace::Memory);
+    assert_eq!(interfaces[1].address, 0xFED4_0000);
+    assert_eq!(interfaces[1].register_spacing, Some(4));
+    assert_eq!(interfaces[1].irq, Some(10));
+    assert_eq!(interfaces[2].address_space, AddressSpace::Io);
+    assert_eq!(interfaces[2].address, 0xCA8);
+    assert_eq!(interfaces[3].address_space, AddressSpace::Smbus);
+    assert_eq!(interfaces[3].address, 0x10);
+
+    let modprobe = write_modprobe(&interfaces);
+    assert!(modprobe.contains(
+        "options ipmi_si type=kcs,bt,smic ports=0xca2,0,0xca8 addrs=0,0xfed40000,0 \
+         regspacings=1,4,1 irqs=0,10,0 slave_addrs=0x20,0x20,0x20\n"
+    ));
+    assert!(modprobe.contains("options ipmi_ssif addr=0x10\n"));
+    assert!(write_cmdline(&interfaces[..1])
+        .starts_with("ipmi_si.type=kcs ipmi_si.ports=0xca2 ipmi_si.regspacings=1"));
+    assert!(write_freeipmi(&interfaces).contains("driver-type KCS\ndriver-address 0xca2\n"));
+}
@@ -0,0 +1,151 @@
+//! JSON output of the table (-j, --json-pretty)
+//!
+//! The table is serialized as smbios-lib does, except for the entries of the
+//! Additional Information (type 40) structures: smbios-lib subtracts past
+//! zero when serializing an entry shorter than its 5-byte header or running
+//! past the structure.  Such an entry, and the ones after it, which cannot
+//! be delimited, are left out of `entry_iterator`, while `number_of_entries`
+//! keeps the count the structure gives.
+
+use crate::build::push_struct;
+use serde::{
+    ser::{SerializeSeq, SerializeStruct},
+    Serialize, Serializer,
+};
+use smbioslib::*;
+
+/// Size of the header of an Additional Information entry
+const ENTRY_HEADER_SIZE: usize = 5;
+
+/// Index of the AdditionalInformation variant of smbios-lib's DefinedStruct
+const ADDITIONAL_INFORMATION_VARIANT: u32 = 40;
+
+/// A table as printed by -j and --json-pretty
+pub struct JsonTable<'a>(pub &'a SMBiosData);
+
+impl Serialize for JsonTable<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("SMBiosData", 2)?;
+        state.serialize_field("version", &self.0.version)?;
+        state.serialize_field("table", &JsonStructs(self.0))?;
+        state.end()
+    }
+}
+
+/// The structures of a table, as smbios-lib's DefinedStructTable
+struct JsonStructs<'a>(&'a SMBiosData);
+
+impl Serialize for JsonStructs<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let structs: Vec<&UndefinedStruct> = self.0.iter().collect();
+        let mut seq = serializer.serialize_seq(Some(structs.len()))?;
+        for undefined_struct in structs {
+            match undefined_struct.header.struct_type() {
+                SMBiosAdditionalInformation::STRUCT_TYPE => {
+                    seq.serialize_element(&AdditionalInformation(undefined_struct))?
+                }
+                _ => seq.serialize_element(&undefined_struct.defined_struct())?,
+            }
+        }
+        seq.end()
+    }
+}
+
+/// An Additional Information structure, as the variant of DefinedStruct
+struct AdditionalInformation<'a>(&'a UndefinedStruct);
+
+impl Serialize for AdditionalInformation<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_newtype_variant(
+            "DefinedStruct",
+            ADDITIONAL_INFORMATION_VARIANT,
+            "AdditionalInformation",
+            &AdditionalInformationFields(self.0),
+        )
+    }
+}
+
+/// The fields of an Additional Information structure, with the entries
+/// that can be delimited
+struct AdditionalInformationFields<'a>(&'a UndefinedStruct);
+
+impl Serialize for AdditionalInformationFields<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let parts = self.0;
+        let number_of_entries = parts.get_field_byte(4);
+
+        // A copy giving the number of entries that can be delimited
+        let mut fields = parts.fields.clone();
+        if let Some(count) = number_of_entries {
+            fields[4] = delimited_entries(&fields, count);
+        }
+        let strings: Vec<Vec<u8>> = parts.strings.iter().cloned().collect();
+        let mut raw = Vec::new();
+        push_struct(&mut raw, &fields, &strings);
+        let delimited = UndefinedStruct::new(&raw);
+        let additional_information = SMBiosAdditionalInformation::new(&delimited);
+
+        let mut state = serializer.serialize_struct("SMBiosAdditionalInformation", 3)?;
+        state.serialize_field("header", &parts.header)?;
+        state.serialize_field("number_of_entries", &number_of_entries)?;
+        state.serialize_field("entry_iterator", &additional_information.entry_iterator())?;
+        state.end()
+    }
+}
+
+/// Number of the first `count` entries of `fields` that have a whole header
+/// and end within the structure
+fn delimited_entries(fields: &[u8], count: u8) -> u8 {
+    let mut offset = ENTRY_HEADER_SIZE;
+    let mut entries = 0;
+    while entries < count {
+        match fields.get(offset).map(|length| *length as usize) {
+            Some(length) if length >= ENTRY_HEADER_SIZE && offset + length <= fields.len() => {
+                offset += length;
+                entries += 1;
+            }
+            _ => break,
+        }
+    }
+    entries
+}
+
+#[test]
+fn test_json_table() {
+    let mut table = Vec::new();
+    // Additional Information with a whole entry, then one shorter than its
+    // header
+    let additional = vec![
+        40, 17, 0x02, 0x00, 2, 6, 0x01, 0x00, 0x04, 1, 0xAA, 2, 0x00, 0x00, 0x04, 0, 0,
+    ];
+    push_struct(&mut table, &additional, &[b"Note".to_vec()]);
+    push_struct(&mut table, &[127, 4, 0x03, 0x00], &[]);
+    let data = SMBiosData::from_vec_and_version(table, None);
+
+    let json: serde_json::Value =
+        serde_json::from_str(&serde_json::to_string(&JsonTable(&data)).unwrap()).unwrap();
+    let structs = json["table"].as_array().unwrap();
+    assert_eq!(structs.len(), 2);
+    let additional = &structs[0]["AdditionalInformation"];
+    assert_eq!(additional["number_of_entries"], 2);
+    let entries = additional["entry_iterator"].as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["entry_length"], 6);
+    assert_eq!(entries[0]["string"], "Note");
+
+    // The other structures are serialized as by smbios-lib
+    let end = serde_json::to_value(data.iter().nth(1).unwrap().defined_struct()).unwrap();
+    assert_eq!(structs[1], end);
+}
@@ -1,10 +1,8 @@
-use crate::Opt;
+use crate::{recover::RawTable, Opt};
 use smbioslib::*;
 use std::fmt::Write;
 use std::io::Error;
 
-mod dmiopt;
-
 pub fn table_load(_opt: &Opt) -> Result<(SMBiosData, String), Error> {
     let mut output = String::new();
 
@@ -14,3 +12,20 @@ pub fn table_load(_opt: &Opt) -> Result<(SMBiosData, String), Error> {
 
     Ok((smbios_table, output))
 }
+
+/// Reads the table without decoding it
+pub fn table_read(_opt: &Opt, _tolerant: bool) -> Result<RawTable, Error> {
+    let mut header = String::new();
+
+    writeln!(&mut header, "Getting SMBIOS data from IOKit.").unwrap();
+
+    // The version is known from the entry point only
+    let version = table_load_from_device()?.version;
+
+    Ok(RawTable {
+        table: raw_smbios_from_device()?,
+        version,
+        announced_length: None,
+        header,
+    })
+}
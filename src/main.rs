@@ -8,16 +8,40 @@
 #[cfg_attr(target_os = "macos", path = "macos.rs")]
 mod platform;
 
+mod batch;
+mod build;
+mod csv;
+mod decoded;
 mod default_out;
+mod diff;
+mod dmi_id;
 mod dmifn;
 mod dmiopt;
+mod dump;
 mod error;
+mod explain;
+mod host_interface;
+mod hwids;
+mod ipmi;
+mod json_out;
+mod output;
+mod pci;
+mod prometheus;
+mod query;
+mod recover;
+mod redact;
+mod redfish;
+mod serve;
+mod source;
+mod stats;
 
 use default_out::default_dump;
-use dmiopt::{BiosType, Keyword, Opt};
+use dmiopt::{
+    BiosType, Command, HostInterfaceFormat, IpmiConfigFormat, Keyword, Opt, OutputFormat,
+};
 use enum_iterator::all;
+use error::{DmiError, EXIT_BATCH_PARTIAL, EXIT_TABLES_DIFFER};
 use smbioslib::*;
-use std::fmt::Write;
 use structopt::StructOpt;
 
 /* The original DMI decode command line:
@@ -47,26 +71,274 @@ pub fn print_dmidecode_version() {
     println!("# {} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() {
+    if let Err(error) = run() {
+        let error = DmiError::from(error);
+        eprintln!("Error: {}", error);
+        std::process::exit(error.exit_code());
+    }
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
     let opt: Opt = Opt::from_args();
 
-    // Select an input source, file or device.
-    let smbios_data = if let Some(path) = opt.input.as_ref() {
-        let mut output = String::new();
+    // Subcommands select their own input sources.
+    if let Some(command) = opt.command.as_ref() {
+        match command {
+            Command::Diff { json, a, b } => {
+                // Failures have their own status, set apart from "differ"
+                let differ = diff_tables(*json, a, b, &opt)
+                    .map_err(|error| DmiError::Diff(Box::new(DmiError::from(error))))?;
+                if differ {
+                    std::process::exit(EXIT_TABLES_DIFFER);
+                }
+            }
+            Command::Batch { dir, jobs, errors } => {
+                let jobs = match jobs {
+                    Some(jobs) => *jobs,
+                    None => std::thread::available_parallelism().map_or(1, |n| n.get()),
+                };
+                let (machines, failed) = batch::load_directory(dir, &opt, jobs)?;
+                match opt.format {
+                    Some(format) => {
+                        let rows = machines
+                            .iter()
+                            .flat_map(|machine| {
+                                let keys = vec![
+                                    machine.file.clone(),
+                                    machine.uuid.clone().unwrap_or_default(),
+                                ];
+                                machine
+                                    .structures
+                                    .iter()
+                                    .map(move |decoded| (keys.clone(), decoded))
+                            })
+                            .collect();
+                        export_rows(&["File", "System UUID"], rows, format, &opt)?;
+                    }
+                    None => {
+                        for machine in machines.iter() {
+                            println!("{}", serde_json::to_string(machine)?);
+                        }
+                    }
+                }
+                match errors {
+                    Some(path) => {
+                        let mut report = String::new();
+                        for error in failed.iter() {
+                            report.push_str(&serde_json::to_string(error)?);
+                            report.push('\n');
+                        }
+                        std::fs::write(path, report)?;
+                    }
+                    None => {
+                        for error in failed.iter() {
+                            eprintln!("{}: {}", error.file, error.error);
+                        }
+                    }
+                }
+                if !failed.is_empty() {
+                    std::process::exit(EXIT_BATCH_PARTIAL);
+                }
+            }
+            Command::Serve { listen } => {
+                let (data, _, _) = load_table(&opt)?;
+                serve::serve(serve::Inventory::new(data), listen)?;
+            }
+            Command::Build {
+                spec,
+                output,
+                entry_point,
+                entry_point_format,
+                capture,
+            } => match (capture, spec, output) {
+                (Some(source), _, _) => {
+                    let table = source::table_load_from_source(source, &opt)?;
+                    let spec = build::capture_spec(&table.0);
+                    println!("{}", serde_json::to_string_pretty(&spec)?);
+                }
+                (None, Some(spec), Some(output)) => {
+                    let spec = build::read_spec(spec)?;
+                    let built = build::build_table(&spec)?;
+                    if let Some(path) = entry_point {
+                        std::fs::write(path, built.entry_point(*entry_point_format)?)?;
+                    }
+                    dump_raw(built.table, output)?;
+                }
+                _ => unreachable!("structopt requires SPEC and FILE without --capture"),
+            },
+        }
+        return Ok(());
+    }
 
-        writeln!(
-            &mut output,
-            "Getting SMBIOS data from {}.",
-            path.to_string_lossy()
-        )
-        .unwrap();
+    // --from-text FILE, Display the decoded values of a text report
+    if let Some(path) = opt.text_input.as_ref() {
+        let (structs, header) = source::table_load_from_text(path)?;
+        if let Some(OutputFormat::Redfish) = opt.format {
+            let resources = redfish::redfish_resources(&structs);
+            println!("{}", serde_json::to_string_pretty(&resources)?);
+            return Ok(());
+        }
+        let structs = opt.select_structs(structs);
+        if let Some(query) = opt.query.as_ref() {
+            return print_query(query, &structs, &opt);
+        }
+        if let Some(format) = opt.format {
+            return export_rows(&[], csv::rows(&structs), format, &opt);
+        }
+        match (opt.json_pretty, opt.json) {
+            (true, _) => println!("{}", serde_json::to_string_pretty(&structs)?),
+            (false, true) => println!("{}", serde_json::to_string(&structs)?),
+            (false, false) => {
+                print_dmidecode_version();
+                print!("{}", header);
+                for decoded in structs.iter() {
+                    println!();
+                    for line in decoded.to_text_lines() {
+                        println!("{}", line);
+                    }
+                }
+                println!();
+            }
+        }
+        return Ok(());
+    }
 
-        let data = load_smbios_data_from_file(path)?;
+    let smbios_data = load_table(&opt)?;
 
-        (data, output)
-    } else {
-        platform::table_load(&opt)?
-    };
+    // --explain HANDLE, Display the entry of given handle byte by byte
+    if let Some(handle) = opt.explain.as_ref() {
+        print_dmidecode_version();
+        println!("{}", smbios_data.1);
+        let found_struct = smbios_data
+            .0
+            .find_by_handle(handle)
+            .ok_or(DmiError::InvalidHandle(**handle))?;
+        print!(
+            "{}",
+            explain::explain_struct(found_struct, smbios_data.0.version)
+        );
+        return Ok(());
+    }
+
+    // --hwids, Display the Computer Hardware IDs
+    if opt.hwids {
+        hwids::print_hwids(&smbios_data.0);
+        return Ok(());
+    }
+
+    // --modalias, Display the Linux DMI modalias
+    if opt.modalias {
+        println!("{}", dmi_id::modalias(&smbios_data.0));
+        return Ok(());
+    }
+
+    // --export-dmi-id DIR, Write the /sys/class/dmi/id equivalents
+    if let Some(dir) = opt.export_dmi_id.as_ref() {
+        dmi_id::export_dmi_id(&smbios_data.0, dir)?;
+        return Ok(());
+    }
+
+    // --redfish-host-interface FORMAT, Display the type 42 connection settings
+    if let Some(format) = opt.redfish_host_interface {
+        let interfaces = host_interface::host_interfaces(&smbios_data.0);
+        if interfaces.is_empty() {
+            return Err(Box::new(DmiError::StructureNotFound(
+                "Redfish Host Interface",
+                42,
+            )));
+        }
+        match format {
+            HostInterfaceFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&interfaces)?)
+            }
+            HostInterfaceFormat::NetworkManager => {
+                print!("{}", host_interface::write_networkmanager(&interfaces))
+            }
+            HostInterfaceFormat::Networkd => {
+                print!("{}", host_interface::write_networkd(&interfaces))
+            }
+        }
+        return Ok(());
+    }
+
+    // --ipmi-config FORMAT, Display the IPMI driver parameters
+    if let Some(format) = opt.ipmi_config {
+        let interfaces = ipmi::ipmi_interfaces(&smbios_data.0);
+        if interfaces.is_empty() {
+            return Err(Box::new(DmiError::StructureNotFound(
+                "IPMI Device Information",
+                38,
+            )));
+        }
+        match format {
+            IpmiConfigFormat::Modprobe => print!("{}", ipmi::write_modprobe(&interfaces)),
+            IpmiConfigFormat::Cmdline => print!("{}", ipmi::write_cmdline(&interfaces)),
+            IpmiConfigFormat::FreeIpmi => print!("{}", ipmi::write_freeipmi(&interfaces)),
+            IpmiConfigFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&interfaces)?)
+            }
+        }
+        return Ok(());
+    }
+
+    // --query QUERY, Display the selected decoded fields
+    if let Some(query) = opt.query.as_ref() {
+        let structs = opt.select_structs(decoded::decode_table(&smbios_data.0));
+        return print_query(query, &structs, &opt);
+    }
+
+    // --pci, Display the PCI devices at the slots and onboard devices
+    if opt.pci {
+        let root = opt
+            .sysfs_root
+            .clone()
+            .unwrap_or_else(|| std::path::PathBuf::from(pci::SYSFS_ROOT));
+        let locations = pci::PciSysfs::new(&root).locations(&smbios_data.0);
+        match (opt.json_pretty, opt.json) {
+            (true, _) => println!("{}", serde_json::to_string_pretty(&locations)?),
+            (false, true) => println!("{}", serde_json::to_string(&locations)?),
+            (false, false) => pci::print_locations(&locations),
+        }
+        return Ok(());
+    }
+
+    // --stats, Report the size and content statistics of the table
+    if opt.stats {
+        let table_stats = stats::TableStats::new(&smbios_data.0, live_entry_point(&opt));
+        match (opt.json_pretty, opt.json) {
+            (true, _) => println!("{}", serde_json::to_string_pretty(&table_stats)?),
+            (false, true) => println!("{}", serde_json::to_string(&table_stats)?),
+            (false, false) => println!("{}", table_stats.to_text()),
+        }
+        return Ok(());
+    }
+
+    // --format FORMAT, Display the decoded fields in another format
+    if let Some(OutputFormat::Prometheus) = opt.format {
+        let metrics = prometheus::write_metrics(&smbios_data.0);
+        match opt.output_dir.as_ref() {
+            Some(dir) => {
+                // Written under a temporary name first, the textfile
+                // collector must never read a partial file.
+                std::fs::create_dir_all(dir)?;
+                let temporary = dir.join(format!("dmi.prom.{}", std::process::id()));
+                std::fs::write(&temporary, metrics)?;
+                std::fs::rename(temporary, dir.join("dmi.prom"))?;
+            }
+            None => print!("{}", metrics),
+        }
+        return Ok(());
+    }
+    if let Some(OutputFormat::Redfish) = opt.format {
+        let resources = redfish::redfish_resources(&decoded::decode_table(&smbios_data.0));
+        println!("{}", serde_json::to_string_pretty(&resources)?);
+        return Ok(());
+    }
+    if let Some(format) = opt.format {
+        let structs = opt.select_structs(decoded::decode_table(&smbios_data.0));
+        return export_rows(&[], csv::rows(&structs), format, &opt);
+    }
 
     // Mutually exclusive output options (only one tuple element is Some()).
     match (
@@ -89,7 +361,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         (None, Some(output), None, None, None, false, false, false, false) => {
             print_dmidecode_version();
             // TODO: create stdout output.  dump_raw() and raw_smbios_from_device() do not output.
-            dump_raw(raw_smbios_from_device()?, &output.as_path())?
+            match opt.redact {
+                true => dump_raw(build::table_bytes(&smbios_data.0), output)?,
+                false => dump_raw(
+                    raw_smbios_from_device()
+                        .map_err(|error| DmiError::from_live_load(error, &live_device(&opt)))?,
+                    output,
+                )?,
+            }
         }
         // opt.bios_types, -t, --type TYPE        Only display the entries of given type
         (None, None, Some(bios_types), None, None, false, false, false, false) => {
@@ -104,10 +383,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             let found_struct = smbios_data
                 .0
                 .find_by_handle(&handle)
-                .ok_or(std::io::Error::new(
-                    std::io::ErrorKind::InvalidInput,
-                    format!("Handle not found: {}", *handle),
-                ))?;
+                .ok_or(DmiError::InvalidHandle(**handle))?;
             println!("{:#X?}", &found_struct.defined_struct())
         }
         // opt.oem_string, --oem-string N     Only display the value of the given OEM string
@@ -118,6 +394,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     format!("Invalid OEM string number {}", s),
                 )))
             }
+            fn not_present(s: &str) -> Result<(), Box<dyn std::error::Error>> {
+                Err(Box::new(error::BiosParseError::KeywordNotFound(format!(
+                    "OEM string {}",
+                    s
+                ))))
+            }
             let oem_val = oem.trim().parse::<u8>();
             let mut index = 0;
             match oem_val {
@@ -134,27 +416,24 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             }
             match smbios_data.0.first::<SMBiosOemStrings<'_>>() {
-                Some(v) => {
-                    match v.oem_strings().get_string(index).to_utf8_lossy() {
-                        Some(s) => {
-                            if oem != "count" {
-                                println!("{}", s);
-                            } else {
-                                println!("{}", v.count().unwrap());
-                            }
-                        }
-                        None => {
-                            if index != 0 {
-                                // count
-                                invalid_num(oem.as_str())?
-                            }
+                Some(v) => match v.oem_strings().get_string(index).to_utf8_lossy() {
+                    Some(s) => {
+                        if oem != "count" {
+                            println!("{}", s);
+                        } else {
                             println!("{}", v.count().unwrap());
                         }
                     }
-                }
+                    None => {
+                        if index != 0 {
+                            not_present(oem.as_str())?
+                        }
+                        println!("{}", v.count().unwrap());
+                    }
+                },
                 None => {
                     if index != 0 {
-                        invalid_num(oem.as_str())?
+                        not_present(oem.as_str())?
                     } else {
                         // When no structure exists and --oem-string is "count", return "0"
                         println!("0")
@@ -224,13 +503,29 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
         // opt.json, -j, --json        Display output in JSON pretty print format.
         (None, None, None, None, None, false, false, true, false) => {
-            if let Ok(output) = serde_json::to_string_pretty(&smbios_data.0) {
+            let table = json_out::JsonTable(&smbios_data.0);
+            let output = match opt.recover {
+                true => serde_json::to_string_pretty(&recover::RecoveredTable {
+                    table,
+                    corruption: &smbios_data.2,
+                }),
+                false => serde_json::to_string_pretty(&table),
+            };
+            if let Ok(output) = output {
                 println!("{}", output)
             }
         }
         // opt.json_compat, --json-compact        Display output in JSON compact format.
         (None, None, None, None, None, false, false, false, true) => {
-            if let Ok(output) = serde_json::to_string(&smbios_data.0) {
+            let table = json_out::JsonTable(&smbios_data.0);
+            let output = match opt.recover {
+                true => serde_json::to_string(&recover::RecoveredTable {
+                    table,
+                    corruption: &smbios_data.2,
+                }),
+                false => serde_json::to_string(&table),
+            };
+            if let Ok(output) = output {
                 println!("{}", output)
             }
         }
@@ -243,3 +538,170 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// Compares tables `a` and `b` (diff), returning whether they differ
+fn diff_tables(
+    json: bool,
+    a: &std::path::Path,
+    b: &std::path::Path,
+    opt: &Opt,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let structs_a = source::decoded_load_from_source(a, opt)?;
+    let structs_b = source::decoded_load_from_source(b, opt)?;
+    let diff = diff::TableDiff::from_decoded(structs_a, structs_b);
+    match json {
+        true => println!("{}", serde_json::to_string_pretty(&diff)?),
+        false => diff.print_unified(&a.to_string_lossy(), &b.to_string_lossy()),
+    }
+    Ok(!diff.is_empty())
+}
+
+/// Loads the table selected by the global options (--from-dump,
+/// --from-hexdump or the running system), with --recover, --smbios-version
+/// and --redact applied, and the damaged regions skipped by --recover
+fn load_table(
+    opt: &Opt,
+) -> Result<(SMBiosData, String, Vec<recover::Corruption>), Box<dyn std::error::Error>> {
+    let live_error = |error| DmiError::from_live_load(error, &live_device(opt));
+
+    // Select an input source, file or device.
+    let (mut smbios_data, corruptions) = if opt.recover {
+        // --recover, Keep the well-formed structures of a damaged table
+        let raw_table = if let Some(path) = opt.input.as_ref() {
+            source::table_read_from_file(path)?
+        } else if let Some(path) = opt.hexdump_input.as_ref() {
+            source::table_read_from_hexdump(path)?
+        } else {
+            platform::table_read(opt, true).map_err(live_error)?
+        };
+        let recovered = recover::recover_table(&raw_table.table, raw_table.announced_length);
+        let mut header = raw_table.header;
+        for corruption in recovered.corruptions.iter() {
+            eprintln!("Warning: {}", corruption);
+            header.push_str(&format!("{}\n", corruption));
+        }
+        let data = SMBiosData::from_vec_and_version(recovered.table, raw_table.version);
+        ((data, header), recovered.corruptions)
+    } else if let Some(path) = opt.input.as_ref() {
+        (source::table_load_from_file(path)?, Vec::new())
+    } else if let Some(path) = opt.hexdump_input.as_ref() {
+        (source::table_load_from_hexdump(path)?, Vec::new())
+    } else {
+        (platform::table_load(opt).map_err(live_error)?, Vec::new())
+    };
+
+    // --smbios-version, Decode as the given version
+    if let Some(version) = opt.smbios_version {
+        smbios_data.0.version = Some(version);
+    }
+    if let Some(warning) = smbios_data
+        .0
+        .version
+        .and_then(|version| explain::version_warning(&smbios_data.0, version))
+    {
+        eprintln!("Warning: {}", warning);
+    }
+
+    // --redact, Replace identifying values in every output
+    let (data, header) = match opt.redact {
+        true => (
+            redact::redact_table(&smbios_data.0, &redact::redaction_key(opt)),
+            smbios_data.1,
+        ),
+        false => smbios_data,
+    };
+    Ok((data, header, corruptions))
+}
+
+/// Names the device the table of the running system is read from, for errors
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+fn live_device(opt: &Opt) -> String {
+    match opt.dev_mem.as_ref() {
+        Some(path) => path.to_string_lossy().into_owned(),
+        None => DEV_MEM_FILE.to_string(),
+    }
+}
+
+/// Names the device the table of the running system is read from, for errors
+#[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+fn live_device(_opt: &Opt) -> String {
+    "the firmware table".to_string()
+}
+
+/// The entry point of the running system, when the table is read from sysfs
+#[cfg(target_os = "linux")]
+fn live_entry_point(opt: &Opt) -> Option<stats::EntryPoint> {
+    match opt.input.is_none() && opt.hexdump_input.is_none() && !opt.no_sysfs {
+        true => stats::EntryPoint::from_file(std::path::Path::new(SYS_ENTRY_FILE)),
+        false => None,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn live_entry_point(_opt: &Opt) -> Option<stats::EntryPoint> {
+    None
+}
+
+/// Displays rows of decoded structures in `format`, or writes them into --output-dir
+fn export_rows(
+    keys: &[&str],
+    rows: Vec<csv::Row<'_>>,
+    format: OutputFormat,
+    opt: &Opt,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let delimited = match format {
+        OutputFormat::Csv => csv::Delimited::Csv,
+        OutputFormat::Tsv => csv::Delimited::Tsv,
+        OutputFormat::Prometheus => {
+            return Err("--format prometheus needs a table, not a text report or batch".into())
+        }
+        OutputFormat::Redfish => return Err("--format redfish does not apply to batch".into()),
+    };
+    if let Some(dir) = opt.output_dir.as_ref() {
+        csv::export_types(keys, rows, delimited, dir)?;
+        return Ok(());
+    }
+    let types = csv::by_type(rows);
+    if types.len() > 1 {
+        return Err(format!(
+            "{} structure types selected, select one with -t or use --output-dir",
+            types.len()
+        )
+        .into());
+    }
+    if let Some(rows) = types.values().next() {
+        print!("{}", csv::write_table(keys, rows, delimited));
+    }
+    Ok(())
+}
+
+/// Displays the results of a --query, one value per line or JSON
+fn print_query(
+    query: &query::Query,
+    structs: &[decoded::DecodedStruct],
+    opt: &Opt,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let results = query.run(structs);
+    if results.is_empty() {
+        return Err(DmiError::QueryNoMatch(query.to_string()).into());
+    }
+    if opt.json_pretty || opt.json {
+        let values: Vec<serde_json::Value> = results.iter().map(|r| r.to_json()).collect();
+        match opt.json_pretty {
+            true => println!("{}", serde_json::to_string_pretty(&values)?),
+            false => println!("{}", serde_json::to_string(&values)?),
+        }
+        return Ok(());
+    }
+    for (index, result) in results.iter().enumerate() {
+        if let query::QueryResult::Structure(_) = result {
+            if index > 0 {
+                println!();
+            }
+        }
+        for line in result.to_text_lines() {
+            println!("{}", line);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,58 @@
+//! Capturable text output
+//!
+//! The text decoder prints directly to stdout.  Modules that decode import
+//! [`print`] and [`println`] from here instead of using the std macros, so
+//! that their output can be collected into a `String` with [`capture`] and
+//! reused by features that post-process the decoded text (diff, exports, ...).
+
+use std::cell::RefCell;
+use std::fmt::{Arguments, Write};
+
+thread_local! {
+    static CAPTURE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Writes to the capture buffer when capturing, otherwise to stdout.
+pub fn write_fmt(args: Arguments<'_>) {
+    CAPTURE.with(|capture| match capture.borrow_mut().as_mut() {
+        Some(buffer) => buffer.write_fmt(args).unwrap(),
+        None => std::print!("{}", args),
+    })
+}
+
+/// Runs `f` and returns everything it printed instead of printing it.
+pub fn capture<F: FnOnce()>(f: F) -> String {
+    let previous = CAPTURE.with(|capture| capture.replace(Some(String::new())));
+    f();
+    CAPTURE
+        .with(|capture| capture.replace(previous))
+        .unwrap_or_default()
+}
+
+macro_rules! print {
+    ($($arg:tt)*) => {
+        $crate::output::write_fmt(format_args!($($arg)*))
+    };
+}
+
+macro_rules! println {
+    () => {
+        $crate::output::write_fmt(format_args!("\n"))
+    };
+    ($($arg:tt)*) => {
+        $crate::output::write_fmt(format_args!("{}\n", format_args!($($arg)*)))
+    };
+}
+
+pub(crate) use print;
+pub(crate) use println;
+
+#[test]
+fn test_capture() {
+    let captured = capture(|| {
+        print!("Handle {:#06X}", 0x10);
+        println!(", DMI type {}", 17);
+        println!();
+    });
+    assert_eq!(captured, "Handle 0x0010, DMI type 17\n\n");
+}
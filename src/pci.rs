@@ -0,0 +1,385 @@
+//! Cross-reference of slots and onboard devices with PCI sysfs
+//!
+//! System Slots (type 9) and Onboard Devices Extended Information (type 41)
+//! carry the segment/bus/device/function of what they hold.  Looking these
+//! up under `/sys/bus/pci/devices` tells which card sits in which physical
+//! slot, how its link trained and which network interface an onboard NIC
+//! became.
+//!
+//! Firmware gives either the address of the device in the slot or that of
+//! the bridge (root port) above it.  For a bridge, the devices behind it are
+//! reported; for a device, all functions of that device.
+
+use crate::decoded::decode_struct;
+use serde::Serialize;
+use smbioslib::*;
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+/// Default root of the sysfs tree
+pub const SYSFS_ROOT: &str = "/sys";
+
+/// PCI class code of a PCI-to-PCI bridge, without the programming interface
+const PCI_BRIDGE_CLASS: &str = "0x0604";
+
+/// A PCI address, 0000:01:00.0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PciAddress {
+    pub segment: u16,
+    pub bus: u8,
+    pub device: u8,
+    pub function: u8,
+}
+
+impl PciAddress {
+    fn from_parts(
+        segment: Option<SegmentGroupNumber>,
+        bus: Option<BusNumber>,
+        device_function: Option<DeviceFunctionNumber>,
+    ) -> Option<Self> {
+        let segment = match segment? {
+            SegmentGroupNumber::SingleSegment => 0,
+            SegmentGroupNumber::Number(segment) => segment,
+            SegmentGroupNumber::NotApplicable => return None,
+        };
+        let bus = match bus? {
+            BusNumber::Number(bus) => bus,
+            BusNumber::NotApplicable => return None,
+        };
+        match device_function? {
+            DeviceFunctionNumber::Number { device, function } => Some(PciAddress {
+                segment,
+                bus,
+                device,
+                function,
+            }),
+            DeviceFunctionNumber::NotApplicable => None,
+        }
+    }
+
+    /// "0000:01:00." prefix shared by the functions of a device
+    fn device_prefix(&self) -> String {
+        format!("{:04x}:{:02x}:{:02x}.", self.segment, self.bus, self.device)
+    }
+}
+
+impl fmt::Display for PciAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{:x}", self.device_prefix(), self.function)
+    }
+}
+
+/// What sysfs knows about a PCI function
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct PciFunction {
+    pub address: String,
+    pub vendor_id: String,
+    pub device_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subsystem_vendor_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subsystem_device_id: Option<String>,
+    pub class: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub driver: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_width: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_speed: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_link_width: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_link_speed: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub network_interfaces: Vec<String>,
+}
+
+/// A slot or onboard device and the PCI functions found at its address
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct PciLocation {
+    pub handle: u16,
+    #[serde(rename = "type")]
+    pub struct_type: u8,
+    pub designation: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    pub bus_address: String,
+    pub devices: Vec<PciFunction>,
+}
+
+/// Reads a sysfs attribute, without its trailing newline
+fn attribute(dir: &Path, name: &str) -> Option<String> {
+    let value = fs::read_to_string(dir.join(name)).ok()?;
+    let value = value.trim();
+    match value.is_empty() {
+        true => None,
+        false => Some(value.to_string()),
+    }
+}
+
+/// "0x10de" as lspci prints it, "10de"
+fn hex_attribute(dir: &Path, name: &str) -> Option<String> {
+    attribute(dir, name).map(|value| value.trim_start_matches("0x").to_string())
+}
+
+/// Whether a directory entry is named like a PCI function
+fn is_pci_address(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    bytes.len() == 12
+        && bytes[4] == b':'
+        && bytes[7] == b':'
+        && bytes[10] == b'.'
+        && name
+            .chars()
+            .filter(|c| !matches!(c, ':' | '.'))
+            .all(|c| c.is_ascii_hexdigit())
+}
+
+/// Names of the entries of `dir` that satisfy `keep`, sorted
+fn entries(dir: &Path, keep: impl Fn(&str) -> bool) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .filter(|name| keep(name))
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+fn read_function(address: &str, dir: &Path) -> Option<PciFunction> {
+    Some(PciFunction {
+        address: address.to_string(),
+        vendor_id: hex_attribute(dir, "vendor")?,
+        device_id: hex_attribute(dir, "device")?,
+        subsystem_vendor_id: hex_attribute(dir, "subsystem_vendor"),
+        subsystem_device_id: hex_attribute(dir, "subsystem_device"),
+        class: hex_attribute(dir, "class")?,
+        driver: fs::read_link(dir.join("driver"))
+            .ok()
+            .and_then(|driver| Some(driver.file_name()?.to_string_lossy().into_owned())),
+        link_width: attribute(dir, "current_link_width"),
+        link_speed: attribute(dir, "current_link_speed"),
+        max_link_width: attribute(dir, "max_link_width"),
+        max_link_speed: attribute(dir, "max_link_speed"),
+        network_interfaces: entries(&dir.join("net"), |_| true),
+    })
+}
+
+/// PCI sysfs under a root directory
+pub struct PciSysfs {
+    devices: PathBuf,
+}
+
+impl PciSysfs {
+    /// Sysfs mounted at `root`, usually /sys
+    pub fn new(root: &Path) -> Self {
+        PciSysfs {
+            devices: root.join("bus/pci/devices"),
+        }
+    }
+
+    /// Functions at `address`: those behind it for a bridge, otherwise the
+    /// function itself, or all functions of the device for function 0
+    fn functions(&self, address: &PciAddress, whole_device: bool) -> Vec<PciFunction> {
+        let name = address.to_string();
+        let dir = self.devices.join(&name);
+        let is_bridge =
+            attribute(&dir, "class").is_some_and(|class| class.starts_with(PCI_BRIDGE_CLASS));
+        let (parent, names) = match (is_bridge, whole_device && address.function == 0) {
+            (true, _) => (dir.clone(), entries(&dir, is_pci_address)),
+            (false, true) => {
+                let prefix = address.device_prefix();
+                let names = entries(&self.devices, |entry| entry.starts_with(&prefix));
+                (self.devices.clone(), names)
+            }
+            (false, false) => (self.devices.clone(), vec![name]),
+        };
+        names
+            .iter()
+            .filter_map(|name| read_function(name, &parent.join(name)))
+            .collect()
+    }
+
+    /// Resolves every slot and onboard device that has a bus address
+    pub fn locations(&self, data: &SMBiosData) -> Vec<PciLocation> {
+        let mut locations = Vec::new();
+        for undefined_struct in data.iter() {
+            let (address, whole_device, designation_field) = match undefined_struct.defined_struct()
+            {
+                DefinedStruct::SystemSlot(slot) => (
+                    PciAddress::from_parts(
+                        slot.segment_group_number(),
+                        slot.bus_number(),
+                        slot.device_function_number(),
+                    ),
+                    true,
+                    "Designation",
+                ),
+                DefinedStruct::OnboardDevicesExtendedInformation(device) => (
+                    PciAddress::from_parts(
+                        device.segment_group_number(),
+                        device.bus_number(),
+                        device.device_function_number(),
+                    ),
+                    false,
+                    "Reference Designation",
+                ),
+                _ => continue,
+            };
+            let address = match address {
+                Some(address) => address,
+                None => continue,
+            };
+            let decoded = decode_struct(undefined_struct, data.version);
+            locations.push(PciLocation {
+                handle: decoded.handle,
+                struct_type: decoded.struct_type,
+                designation: decoded
+                    .value(designation_field)
+                    .unwrap_or_default()
+                    .to_string(),
+                kind: decoded.value("Type").map(|kind| kind.to_string()),
+                bus_address: address.to_string(),
+                devices: self.functions(&address, whole_device),
+            });
+        }
+        locations
+    }
+}
+
+/// "x16 at 8.0 GT/s PCIe"
+fn link(width: &Option<String>, speed: &Option<String>) -> Option<String> {
+    match (width, speed) {
+        (Some(width), Some(speed)) => Some(format!("x{} at {}", width, speed)),
+        (Some(width), None) => Some(format!("x{}", width)),
+        (None, Some(speed)) => Some(speed.clone()),
+        (None, None) => None,
+    }
+}
+
+/// Prints the locations in dmidecode's text layout
+pub fn print_locations(locations: &[PciLocation]) {
+    for location in locations.iter() {
+        println!(
+            "Handle {:#06X}, DMI type {}",
+            location.handle, location.struct_type
+        );
+        match location.struct_type {
+            9 => println!("System Slot"),
+            _ => println!("Onboard Device"),
+        }
+        println!("\tDesignation: {}", location.designation);
+        if let Some(kind) = location.kind.as_ref() {
+            println!("\tType: {}", kind);
+        }
+        println!("\tBus Address: {}", location.bus_address);
+        if location.devices.is_empty() {
+            println!("\tPCI Device: Not Found");
+        }
+        for device in location.devices.iter() {
+            println!("\tPCI Device: {}", device.address);
+            print!("\t\tID: {}:{}", device.vendor_id, device.device_id);
+            if let (Some(vendor), Some(device)) = (
+                device.subsystem_vendor_id.as_ref(),
+                device.subsystem_device_id.as_ref(),
+            ) {
+                print!(" (subsystem {}:{})", vendor, device);
+            }
+            println!();
+            println!("\t\tClass: {}", device.class);
+            if let Some(driver) = device.driver.as_ref() {
+                println!("\t\tDriver: {}", driver);
+            }
+            if let Some(current) = link(&device.link_width, &device.link_speed) {
+                match link(&device.max_link_width, &device.max_link_speed) {
+                    Some(max) if max != current => {
+                        println!("\t\tLink: {} (max {})", current, max)
+                    }
+                    _ => println!("\t\tLink: {}", current),
+                }
+            }
+            if !device.network_interfaces.is_empty() {
+                println!(
+                    "\t\tNetwork Interfaces: {}",
+                    device.network_interfaces.join(", ")
+                );
+            }
+        }
+        println!();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn write_function(dir: &Path, class: &str, attributes: &[(&str, &str)]) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join("vendor"), "0x8086\n").unwrap();
+        fs::write(dir.join("device"), "0x1533\n").unwrap();
+        fs::write(dir.join("class"), format!("{}\n", class)).unwrap();
+        for (name, value) in attributes {
+            fs::write(dir.join(name), format!("{}\n", value)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_locations() {
+        let root = tempfile::tempdir().unwrap();
+        let devices = root.path().join("bus/pci/devices");
+        // Root port 00:1c.0 with a card at 01:00.0 behind it
+        let bridge = devices.join("0000:00:1c.0");
+        write_function(&bridge, "0x060400", &[]);
+        write_function(
+            &bridge.join("0000:01:00.0"),
+            "0x020000",
+            &[
+                ("current_link_width", "4"),
+                ("current_link_speed", "5.0 GT/s PCIe"),
+            ],
+        );
+        // Onboard NIC at 03:00.0
+        let nic = devices.join("0000:03:00.0");
+        write_function(&nic, "0x020000", &[]);
+        fs::create_dir_all(nic.join("net/eno1")).unwrap();
+        std::os::unix::fs::symlink("../../../bus/pci/drivers/igb", nic.join("driver")).unwrap();
+
+        let mut table = vec![
+            // System Slot at 00:1c.0
+            0x09, 0x11, 0x09, 0x00, 0x01, 0xA5, 0x0A, 0x04, 0x04, 0x01, 0x00, 0x04, 0x01, 0x00,
+            0x00, 0x00, 0xE0,
+        ];
+        table.extend_from_slice(b"SLOT1\0\0");
+        // Onboard Devices Extended Information at 03:00.0
+        table.extend_from_slice(&[
+            0x29, 0x0B, 0x29, 0x00, 0x01, 0x85, 0x01, 0x00, 0x00, 0x03, 0x00,
+        ]);
+        table.extend_from_slice(b"LAN\0\0");
+        let data = SMBiosData::from_vec_and_version(
+            table,
+            Some(SMBiosVersion {
+                major: 3,
+                minor: 2,
+                revision: 0,
+            }),
+        );
+
+        let locations = PciSysfs::new(root.path()).locations(&data);
+        assert_eq!(locations.len(), 2);
+        assert_eq!(locations[0].designation, "SLOT1");
+        assert_eq!(locations[0].bus_address, "0000:00:1c.0");
+        assert_eq!(locations[0].devices.len(), 1);
+        assert_eq!(locations[0].devices[0].address, "0000:01:00.0");
+        assert_eq!(locations[0].devices[0].link_width.as_deref(), Some("4"));
+        assert_eq!(locations[1].designation, "LAN");
+        assert_eq!(locations[1].devices[0].vendor_id, "8086");
+        assert_eq!(locations[1].devices[0].driver.as_deref(), Some("igb"));
+        assert_eq!(locations[1].devices[0].network_interfaces, vec!["eno1"]);
+    }
+}
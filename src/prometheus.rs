@@ -0,0 +1,409 @@
+//! Prometheus text exposition format
+//!
+//! Hardware inventory as metrics for the node_exporter textfile collector:
+//! info-style gauges (value 1, identifying values as labels) for the BIOS,
+//! the system, processors and memory devices; core, thread and memory size
+//! gauges; and the nominal values, limits and tolerances of the voltage,
+//! temperature and electrical current probes and cooling devices.  Values
+//! are in base units (bytes, volts, celsius, amperes) and unknown values are
+//! left out.
+
+use crate::decoded::{decode_struct, DecodedStruct};
+use smbioslib::*;
+use std::fmt::Write;
+
+/// Metric name prefix
+const PREFIX: &str = "dmi";
+
+/// A metric family: its samples in table order
+struct Family {
+    name: String,
+    help: &'static str,
+    samples: Vec<(Vec<(&'static str, String)>, f64)>,
+}
+
+/// Metric families in order of first appearance
+#[derive(Default)]
+struct Metrics {
+    families: Vec<Family>,
+}
+
+impl Metrics {
+    fn add(
+        &mut self,
+        name: &str,
+        help: &'static str,
+        labels: Vec<(&'static str, String)>,
+        value: f64,
+    ) {
+        let name = format!("{}_{}", PREFIX, name);
+        let index = match self.families.iter().position(|family| family.name == name) {
+            Some(index) => index,
+            None => {
+                self.families.push(Family {
+                    name,
+                    help,
+                    samples: Vec::new(),
+                });
+                self.families.len() - 1
+            }
+        };
+        self.families[index].samples.push((labels, value));
+    }
+
+    fn add_some(
+        &mut self,
+        name: &str,
+        help: &'static str,
+        labels: &[(&'static str, String)],
+        value: Option<f64>,
+    ) {
+        if let Some(value) = value {
+            self.add(name, help, labels.to_vec(), value);
+        }
+    }
+
+    fn write(&self) -> String {
+        let mut output = String::new();
+        for family in self.families.iter() {
+            writeln!(&mut output, "# HELP {} {}", family.name, family.help).unwrap();
+            writeln!(&mut output, "# TYPE {} gauge", family.name).unwrap();
+            for (labels, value) in family.samples.iter() {
+                let labels: Vec<String> = labels
+                    .iter()
+                    .map(|(name, value)| format!("{}=\"{}\"", name, escape_label_value(value)))
+                    .collect();
+                match labels.is_empty() {
+                    true => writeln!(&mut output, "{} {}", family.name, value),
+                    false => writeln!(
+                        &mut output,
+                        "{}{{{}}} {}",
+                        family.name,
+                        labels.join(","),
+                        value
+                    ),
+                }
+                .unwrap();
+            }
+        }
+        output
+    }
+}
+
+/// Escapes backslashes, double quotes and line feeds
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Labels from decoded fields: (label name, field name)
+fn labels(decoded: &DecodedStruct, fields: &[(&'static str, &str)]) -> Vec<(&'static str, String)> {
+    fields
+        .iter()
+        .map(|(label, field)| {
+            let value = decoded
+                .field(field)
+                .and_then(|field| field.value.clone())
+                .unwrap_or_default();
+            (*label, value)
+        })
+        .collect()
+}
+
+/// Returns the metrics of a table in the text exposition format
+pub fn write_metrics(data: &SMBiosData) -> String {
+    let mut metrics = Metrics::default();
+    for undefined_struct in data.iter() {
+        let decoded = || decode_struct(undefined_struct, data.version);
+        match undefined_struct.defined_struct() {
+            DefinedStruct::Information(_) => {
+                let labels = labels(
+                    &decoded(),
+                    &[
+                        ("vendor", "Vendor"),
+                        ("version", "Version"),
+                        ("date", "Release Date"),
+                    ],
+                );
+                metrics.add("bios_info", "BIOS information", labels, 1.0);
+            }
+            DefinedStruct::SystemInformation(_) => {
+                let labels = labels(
+                    &decoded(),
+                    &[
+                        ("manufacturer", "Manufacturer"),
+                        ("product_name", "Product Name"),
+                        ("version", "Version"),
+                        ("serial_number", "Serial Number"),
+                        ("uuid", "UUID"),
+                        ("family", "Family"),
+                    ],
+                );
+                metrics.add("system_info", "System information", labels, 1.0);
+            }
+            DefinedStruct::ProcessorInformation(_) => {
+                let decoded = decoded();
+                let socket = labels(&decoded, &[("socket", "Socket Designation")]);
+                let mut info = socket.clone();
+                info.extend(labels(
+                    &decoded,
+                    &[("manufacturer", "Manufacturer"), ("version", "Version")],
+                ));
+                metrics.add("processor_info", "Processor information", info, 1.0);
+                metrics.add_some(
+                    "processor_cores",
+                    "Number of cores of the processor",
+                    &socket,
+                    decoded.number("Core Count").map(|n| n as f64),
+                );
+                metrics.add_some(
+                    "processor_cores_enabled",
+                    "Number of enabled cores of the processor",
+                    &socket,
+                    decoded.number("Core Enabled").map(|n| n as f64),
+                );
+                metrics.add_some(
+                    "processor_threads",
+                    "Number of threads of the processor",
+                    &socket,
+                    decoded.number("Thread Count").map(|n| n as f64),
+                );
+            }
+            DefinedStruct::MemoryDevice(_) => {
+                let decoded = decoded();
+                let locator = labels(
+                    &decoded,
+                    &[("locator", "Locator"), ("bank_locator", "Bank Locator")],
+                );
+                let mut info = locator.clone();
+                info.extend(labels(
+                    &decoded,
+                    &[
+                        ("size", "Size"),
+                        ("type", "Type"),
+                        ("speed", "Speed"),
+                        ("manufacturer", "Manufacturer"),
+                        ("part_number", "Part Number"),
+                    ],
+                ));
+                metrics.add("memory_device_info", "Memory device information", info, 1.0);
+                let size = match decoded.value("Size") {
+                    Some("No Module Installed") => Some(0.0),
+                    _ => decoded.size_bytes("Size").map(|size| size as f64),
+                };
+                metrics.add_some(
+                    "memory_device_size_bytes",
+                    "Size of the memory device",
+                    &locator,
+                    size,
+                );
+            }
+            DefinedStruct::VoltageProbe(data) => {
+                let labels = probe_labels(&decoded());
+                let volts = |value: Option<ProbeVoltage>| match value {
+                    Some(ProbeVoltage::Millivolts(millivolts)) => Some(millivolts as f64 / 1000.0),
+                    _ => None,
+                };
+                let resolution = match data.resolution() {
+                    Some(VoltageProbeResolution::TenthsOfMillivolts(tenths)) => {
+                        Some(tenths as f64 / 10000.0)
+                    }
+                    _ => None,
+                };
+                let accuracy = match data.accuracy() {
+                    Some(VoltageProbeAccuracy::OneOneHundredthPercent(hundredths)) => {
+                        Some(hundredths as f64 / 10000.0)
+                    }
+                    _ => None,
+                };
+                add_probe(
+                    &mut metrics,
+                    "voltage_probe",
+                    "volts",
+                    labels,
+                    [
+                        volts(data.nominal_value()),
+                        volts(data.maximum_value()),
+                        volts(data.minimum_value()),
+                        volts(data.tolerance()),
+                        resolution,
+                        accuracy,
+                    ],
+                );
+            }
+            DefinedStruct::TemperatureProbe(data) => {
+                let labels = probe_labels(&decoded());
+                let celsius = |value: Option<ProbeTemperature>| match value {
+                    Some(ProbeTemperature::OneTenthDegreesC(tenths)) => Some(tenths as f64 / 10.0),
+                    _ => None,
+                };
+                let resolution = match data.resolution() {
+                    Some(TemperatureProbeResolution::OneOneThousandthDegreesC(thousandths)) => {
+                        Some(thousandths as f64 / 1000.0)
+                    }
+                    _ => None,
+                };
+                let accuracy = match data.accuracy() {
+                    Some(TemperatureProbeAccuracy::OneOneHundredthDegreesC(hundredths)) => {
+                        Some(hundredths as f64 / 10000.0)
+                    }
+                    _ => None,
+                };
+                add_probe(
+                    &mut metrics,
+                    "temperature_probe",
+                    "celsius",
+                    labels,
+                    [
+                        celsius(data.nominal_value()),
+                        celsius(data.maximum_value()),
+                        celsius(data.minimum_value()),
+                        celsius(data.tolerance()),
+                        resolution,
+                        accuracy,
+                    ],
+                );
+            }
+            DefinedStruct::ElectricalCurrentProbe(data) => {
+                let labels = probe_labels(&decoded());
+                let amperes = |value: Option<ProbeAmperage>| match value {
+                    Some(ProbeAmperage::Milliamps(milliamps)) => Some(milliamps as f64 / 1000.0),
+                    _ => None,
+                };
+                let resolution = match data.resolution() {
+                    Some(CurrentProbeResolution::TenthsOfMilliamps(tenths)) => {
+                        Some(tenths as f64 / 10000.0)
+                    }
+                    _ => None,
+                };
+                let accuracy = match data.accuracy() {
+                    Some(CurrentProbeAccuracy::OneOneHundredthPercent(hundredths)) => {
+                        Some(hundredths as f64 / 10000.0)
+                    }
+                    _ => None,
+                };
+                add_probe(
+                    &mut metrics,
+                    "current_probe",
+                    "amperes",
+                    labels,
+                    [
+                        amperes(data.nominal_value()),
+                        amperes(data.maximum_value()),
+                        amperes(data.minimum_value()),
+                        amperes(data.tolerance()),
+                        resolution,
+                        accuracy,
+                    ],
+                );
+            }
+            DefinedStruct::CoolingDevice(data) => {
+                let decoded = decoded();
+                let labels = labels(
+                    &decoded,
+                    &[("description", "Description"), ("type", "Type")],
+                );
+                metrics.add(
+                    "cooling_device_info",
+                    "Cooling device information",
+                    labels.clone(),
+                    1.0,
+                );
+                if let Some(RotationalSpeed::Rpm(rpm)) = data.nominal_speed() {
+                    metrics.add(
+                        "cooling_device_nominal_speed_rpm",
+                        "Nominal speed of the cooling device",
+                        labels,
+                        rpm as f64,
+                    );
+                }
+            }
+            _ => (),
+        }
+    }
+    metrics.write()
+}
+
+fn probe_labels(decoded: &DecodedStruct) -> Vec<(&'static str, String)> {
+    labels(
+        decoded,
+        &[("description", "Description"), ("location", "Location")],
+    )
+}
+
+/// Adds the info gauge and the nominal value, maximum, minimum, tolerance,
+/// resolution and accuracy (as a ratio) of a probe
+fn add_probe(
+    metrics: &mut Metrics,
+    probe: &str,
+    unit: &str,
+    labels: Vec<(&'static str, String)>,
+    values: [Option<f64>; 6],
+) {
+    const HELP: [&str; 6] = [
+        "Nominal value of the probe",
+        "Maximum value the probe can read",
+        "Minimum value the probe can read",
+        "Tolerance of the probe readings",
+        "Resolution of the probe readings",
+        "Accuracy of the probe readings",
+    ];
+    let names = [
+        format!("{}_nominal_{}", probe, unit),
+        format!("{}_maximum_{}", probe, unit),
+        format!("{}_minimum_{}", probe, unit),
+        format!("{}_tolerance_{}", probe, unit),
+        format!("{}_resolution_{}", probe, unit),
+        format!("{}_accuracy_ratio", probe),
+    ];
+    metrics.add(
+        &format!("{}_info", probe),
+        "Probe information",
+        labels.clone(),
+        1.0,
+    );
+    for ((name, help), value) in names.iter().zip(HELP.iter()).zip(values.iter()) {
+        metrics.add_some(name, help, &labels, *value);
+    }
+}
+
+#[test]
+fn test_write_metrics() {
+    let mut table = vec![
+        // BIOS Information
+        0x00, 0x18, 0x00, 0x00, 0x01, 0x02, 0x00, 0xF0, 0x03, 0x03, 0x90, 0xDA, 0xCB, 0x7F, 0x00,
+        0x00, 0x00, 0x00, 0x34, 0x01, 0x01, 0x32, 0xFF, 0xFF,
+    ];
+    table.extend_from_slice(b"ACME \"Corp.\"\0V1.50\\x\x0012/07/2018\0\0");
+    table.extend_from_slice(&[
+        // Temperature Probe: maximum 100.0, minimum unknown, nominal 45.0 deg C
+        0x1C, 0x16, 0x01, 0x00, 0x01, 0x63, 0xE8, 0x03, 0x00, 0x80, 0x64, 0x00, 0x05, 0x00, 0x32,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0xC2, 0x01,
+    ]);
+    table.extend_from_slice(b"CPU Thermal Probe\0\0");
+    let data = SMBiosData::from_vec_and_version(table, None);
+
+    let metrics = write_metrics(&data);
+    let lines: Vec<&str> = metrics.lines().collect();
+    assert_eq!(lines[0], "# HELP dmi_bios_info BIOS information");
+    assert_eq!(lines[1], "# TYPE dmi_bios_info gauge");
+    assert_eq!(
+        lines[2],
+        "dmi_bios_info{vendor=\"ACME \\\"Corp.\\\"\",version=\"V1.50\\\\x\",date=\"12/07/2018\"} 1"
+    );
+    assert!(lines.contains(
+        &"dmi_temperature_probe_maximum_celsius{description=\"CPU Thermal Probe\",\
+            location=\"Processor\"} 100"
+    ));
+    assert!(lines.contains(
+        &"dmi_temperature_probe_nominal_celsius{description=\"CPU Thermal Probe\",\
+            location=\"Processor\"} 45"
+    ));
+    assert!(lines.contains(
+        &"dmi_temperature_probe_accuracy_ratio{description=\"CPU Thermal Probe\",\
+            location=\"Processor\"} 0.005"
+    ));
+    assert!(!metrics.contains("dmi_temperature_probe_minimum_celsius"));
+}
@@ -0,0 +1,321 @@
+//! Field queries across all structure types
+//!
+//! `--query` addresses any decoded field without a dedicated `-s` keyword:
+//!
+//! ```text
+//! query     = structure { "[" filter "]" } [ "." field ]
+//! structure = "*" | "typeN" | N | -t keyword | structure name
+//! filter    = "*" | index | field "=" value | field "!=" value | field "~" value
+//! ```
+//!
+//! Structure and field names are written in lower case with dashes
+//! ("memory-device", "part-number"), matching dmidecode's names regardless
+//! of case and punctuation.  Filters apply in order: `[locator~DIMM][0]`
+//! is the first structure whose locator contains "DIMM".  An index counts
+//! from 0 among the structures kept so far.
+//!
+//! Examples: `memory-device[*].part-number`, `type17.speed`,
+//! `processor[socket-designation=CPU1].core-count`.
+
+use crate::{
+    decoded::{DecodedField, DecodedStruct},
+    dmiopt::BiosType,
+};
+use std::{fmt, str::FromStr};
+
+/// Lower case words joined by dashes: "Part Number" -> "part-number"
+pub fn normalize(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_ascii_lowercase())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Which structures a query starts from
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Structures {
+    All,
+    Types(Vec<u8>),
+    Name(String),
+}
+
+impl Structures {
+    fn matches(&self, decoded: &DecodedStruct) -> bool {
+        match self {
+            Structures::All => true,
+            Structures::Types(types) => types.contains(&decoded.struct_type),
+            Structures::Name(name) => normalize(&decoded.name) == *name,
+        }
+    }
+}
+
+/// Comparison of a filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Contains,
+}
+
+/// A filter between brackets
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Filter {
+    All,
+    Index(usize),
+    Field {
+        field: String,
+        comparison: Comparison,
+        value: String,
+    },
+}
+
+impl Filter {
+    fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if text == "*" {
+            return Ok(Filter::All);
+        }
+        if let Ok(index) = text.parse() {
+            return Ok(Filter::Index(index));
+        }
+        let (field, comparison, value) = if let Some((field, value)) = text.split_once("!=") {
+            (field, Comparison::NotEqual, value)
+        } else if let Some((field, value)) = text.split_once('=') {
+            (field, Comparison::Equal, value)
+        } else if let Some((field, value)) = text.split_once('~') {
+            (field, Comparison::Contains, value)
+        } else {
+            return Err(format!(
+                "Invalid filter [{}], expected *, an index, field=value, field!=value \
+                 or field~value",
+                text
+            ));
+        };
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .or_else(|| {
+                value
+                    .strip_prefix('\'')
+                    .and_then(|value| value.strip_suffix('\''))
+            })
+            .unwrap_or(value);
+        Ok(Filter::Field {
+            field: normalize(field),
+            comparison,
+            value: value.to_string(),
+        })
+    }
+
+    fn apply<'a>(&self, structs: Vec<&'a DecodedStruct>) -> Vec<&'a DecodedStruct> {
+        match self {
+            Filter::All => structs,
+            Filter::Index(index) => structs.into_iter().skip(*index).take(1).collect(),
+            Filter::Field {
+                field,
+                comparison,
+                value,
+            } => structs
+                .into_iter()
+                .filter(|decoded| {
+                    let actual = find_field(decoded, field).and_then(|f| f.value.as_deref());
+                    match (comparison, actual) {
+                        (Comparison::Equal, Some(actual)) => actual == value,
+                        (Comparison::NotEqual, actual) => actual != Some(value.as_str()),
+                        (Comparison::Contains, Some(actual)) => actual.contains(value.as_str()),
+                        (_, None) => false,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The first field of a structure with the given normalized name
+fn find_field<'a>(decoded: &'a DecodedStruct, name: &str) -> Option<&'a DecodedField> {
+    decoded
+        .fields
+        .iter()
+        .find(|field| normalize(&field.name) == name)
+}
+
+/// A parsed `--query` selector
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    text: String,
+    structures: Structures,
+    filters: Vec<Filter>,
+    field: Option<String>,
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim();
+        let structure_end = text.find(['[', '.']).unwrap_or(text.len());
+        let structure = text[..structure_end].trim();
+        let structures = match structure {
+            "" => return Err(format!("Invalid query {}: no structure type", s)),
+            "*" => Structures::All,
+            _ => match structure
+                .strip_prefix("type")
+                .and_then(|number| number.parse::<u8>().ok())
+            {
+                Some(struct_type) => Structures::Types(vec![struct_type]),
+                None => match BiosType::from_str(structure) {
+                    Ok(bios_type) => Structures::Types(bios_type.into_iter().collect()),
+                    Err(_) => Structures::Name(normalize(structure)),
+                },
+            },
+        };
+
+        let mut filters = Vec::new();
+        let mut rest = &text[structure_end..];
+        while let Some(inner) = rest.strip_prefix('[') {
+            let end = inner
+                .find(']')
+                .ok_or_else(|| format!("Invalid query {}: missing ]", s))?;
+            filters.push(Filter::parse(&inner[..end])?);
+            rest = &inner[end + 1..];
+        }
+        let field = match rest.strip_prefix('.') {
+            Some(field) if !normalize(field).is_empty() => Some(normalize(field)),
+            Some(_) => return Err(format!("Invalid query {}: no field after '.'", s)),
+            None if rest.trim().is_empty() => None,
+            None => return Err(format!("Invalid query {}: unexpected {}", s, rest)),
+        };
+        Ok(Query {
+            text: text.to_string(),
+            structures,
+            filters,
+            field,
+        })
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+/// What a query found in one structure
+#[derive(Debug, PartialEq, Eq)]
+pub enum QueryResult<'a> {
+    Structure(&'a DecodedStruct),
+    Field(&'a DecodedField),
+}
+
+impl QueryResult<'_> {
+    /// Text lines: the structure as in the default output, or the field's
+    /// value and items
+    pub fn to_text_lines(&self) -> Vec<String> {
+        match self {
+            QueryResult::Structure(decoded) => decoded.to_text_lines(),
+            QueryResult::Field(field) => field
+                .value
+                .iter()
+                .chain(field.items.iter())
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// JSON value: the structure, the field value, or the list of items
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            QueryResult::Structure(decoded) => serde_json::to_value(decoded).unwrap(),
+            QueryResult::Field(field) => match (&field.value, field.items.is_empty()) {
+                (Some(value), true) => serde_json::Value::from(value.as_str()),
+                _ => serde_json::Value::from(self.to_text_lines()),
+            },
+        }
+    }
+}
+
+impl Query {
+    /// Runs the query over decoded structures
+    pub fn run<'a>(&self, structs: &'a [DecodedStruct]) -> Vec<QueryResult<'a>> {
+        let mut selected: Vec<&DecodedStruct> = structs
+            .iter()
+            .filter(|decoded| self.structures.matches(decoded))
+            .collect();
+        for filter in self.filters.iter() {
+            selected = filter.apply(selected);
+        }
+        match self.field.as_ref() {
+            Some(field) => selected
+                .into_iter()
+                .filter_map(|decoded| find_field(decoded, field))
+                .map(QueryResult::Field)
+                .collect(),
+            None => selected.into_iter().map(QueryResult::Structure).collect(),
+        }
+    }
+}
+
+#[test]
+fn test_query() {
+    let text = "Handle 0x0040, DMI type 4, 48 bytes
+Processor Information
+\tSocket Designation: CPU0
+\tCore Count: 8
+
+Handle 0x0041, DMI type 4, 48 bytes
+Processor Information
+\tSocket Designation: CPU1
+\tCore Count: 16
+
+Handle 0x0011, DMI type 17, 40 bytes
+Memory Device
+\tLocator: DIMM A1
+\tPart Number: HMA81GU6
+
+Handle 0x0012, DMI type 17, 40 bytes
+Memory Device
+\tLocator: DIMM B1
+\tPart Number: Not Specified
+";
+    let structs = crate::decoded::parse_text(text);
+    let values = |query: &str| -> Vec<String> {
+        Query::from_str(query)
+            .unwrap()
+            .run(&structs)
+            .iter()
+            .flat_map(|result| result.to_text_lines())
+            .collect()
+    };
+
+    assert_eq!(
+        values("memory-device[*].part-number"),
+        vec!["HMA81GU6", "Not Specified"]
+    );
+    assert_eq!(values("type17.locator"), vec!["DIMM A1", "DIMM B1"]);
+    assert_eq!(
+        values("processor[socket-designation=CPU1].core-count"),
+        vec!["16"]
+    );
+    assert_eq!(values("Processor Information[1].Core Count"), vec!["16"]);
+    assert_eq!(
+        values("memory[locator~B].part-number"),
+        vec!["Not Specified"]
+    );
+    assert_eq!(
+        values("17[part-number!='Not Specified'].locator"),
+        vec!["DIMM A1"]
+    );
+    assert_eq!(values("*[0].socket-designation"), vec!["CPU0"]);
+    assert_eq!(
+        values("processor[socket-designation=CPU2]"),
+        Vec::<String>::new()
+    );
+    assert_eq!(values("type4[0]")[0], "Handle 0x0040, DMI type 4, 48 bytes");
+
+    assert!(Query::from_str("memory-device[*").is_err());
+    assert!(Query::from_str("memory-device[locator]").is_err());
+    assert!(Query::from_str("memory-device.").is_err());
+    assert!(Query::from_str(".speed").is_err());
+}
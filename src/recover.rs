@@ -0,0 +1,323 @@
+//! Tolerant decoding of damaged tables
+//!
+//! smbios-lib stops at the first structure it cannot delimit and drops the
+//! rest of the table, and a table shorter than its entry point announces
+//! fails to load from `/dev/mem`.  With `--recover` the table is read as far
+//! as it goes and walked here first: a structure that cannot be delimited,
+//! or whose strings hold binary data while no structure follows it (the sign
+//! of a wrong length byte), is recorded as a corruption and the walk resumes
+//! at the next offset that holds a well-formed structure.  The structures
+//! kept form a table that decodes in every output format.
+
+use crate::json_out::JsonTable;
+use serde::Serialize;
+use smbioslib::*;
+use std::{cmp::Reverse, collections::HashSet, fmt};
+
+/// Size of the structure header (type, length and handle)
+const HEADER_SIZE: usize = 4;
+
+/// Handles from 0xFF00 up are reserved for references between structures
+const RESERVED_HANDLES: u16 = 0xFF00;
+
+/// Type of the End-of-Table structure
+const END_OF_TABLE: u8 = 127;
+
+/// A table as read from its source, before any decoding
+#[derive(Debug, Clone)]
+pub struct RawTable {
+    pub table: Vec<u8>,
+    pub version: Option<SMBiosVersion>,
+    /// Length given by the entry point, when the source has one
+    pub announced_length: Option<usize>,
+    /// The "Getting SMBIOS data from" lines of the report
+    pub header: String,
+}
+
+impl RawTable {
+    /// Decodes the table as is, as the loaders without `--recover` do
+    pub fn into_data(self) -> (SMBiosData, String) {
+        (
+            SMBiosData::from_vec_and_version(self.table, self.version),
+            self.header,
+        )
+    }
+}
+
+/// A damaged part of a table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Corruption {
+    /// Offset of the damaged data in the table
+    pub offset: usize,
+    /// Handle of the last structure decoded before it
+    pub after_handle: Option<u16>,
+    pub reason: String,
+    /// Offset of the next structure decoded, or none if nothing more could
+    /// be decoded
+    pub resumed_at: Option<usize>,
+}
+
+impl fmt::Display for Corruption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Corrupt data at offset {:#06X}", self.offset)?;
+        if let Some(handle) = self.after_handle {
+            write!(f, " (after handle {:#06X})", handle)?;
+        }
+        write!(f, ": {}; ", self.reason)?;
+        match self.resumed_at {
+            Some(offset) => write!(f, "resumed at offset {:#06X}.", offset),
+            None => write!(f, "nothing more could be decoded."),
+        }
+    }
+}
+
+/// The structures that could be decoded from a damaged table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recovered {
+    /// The well-formed structures, one after the other
+    pub table: Vec<u8>,
+    pub corruptions: Vec<Corruption>,
+}
+
+/// A table as printed by -j and --json-pretty with `--recover`
+#[derive(Serialize)]
+pub struct RecoveredTable<'a> {
+    #[serde(flatten)]
+    pub table: JsonTable<'a>,
+    pub corruption: &'a [Corruption],
+}
+
+/// Delimits the structure at `offset` and returns the offset following it
+fn delimit_struct(data: &[u8], offset: usize) -> Result<usize, String> {
+    let rest = &data[offset..];
+    if rest.len() < HEADER_SIZE {
+        return Err(format!(
+            "structure header cut short after {} bytes",
+            rest.len()
+        ));
+    }
+    let length = rest[1] as usize;
+    if length < HEADER_SIZE {
+        return Err(format!("length {} is shorter than the header", length));
+    }
+    if length > rest.len() {
+        return Err(format!(
+            "formatted area of {} bytes runs past the end of the table",
+            length
+        ));
+    }
+    let strings_length = rest[length..]
+        .windows(2)
+        .position(|pair| pair == [0, 0])
+        .ok_or("string set is not terminated before the end of the table")?;
+    Ok(offset + length + strings_length + 2)
+}
+
+/// Whether the string set of the structure at `offset`, which ends before
+/// `next`, holds control characters other than tabs
+fn holds_binary(data: &[u8], offset: usize, next: usize) -> bool {
+    data[offset + data[offset + 1] as usize..next]
+        .iter()
+        .any(|&byte| byte != 0 && byte < b' ' && byte != b'\t')
+}
+
+/// Checks the structure at `offset` and returns the offset following it
+///
+/// Strings may hold control characters (line breaks in OEM strings), so
+/// binary data only counts against a structure when what follows it does
+/// not delimit either: a wrong length byte leaves the next header out of
+/// place.  A run of such structures stands or falls with the first one
+/// after it that holds text, the End-of-Table or the padding at the end.
+fn check_struct(data: &[u8], offset: usize) -> Result<usize, String> {
+    let next = delimit_struct(data, offset)?;
+    let (mut current, mut following) = (offset, next);
+    while holds_binary(data, current, following) {
+        if data[current] == END_OF_TABLE || data[following..].iter().all(|&byte| byte == 0) {
+            break;
+        }
+        match delimit_struct(data, following) {
+            Ok(after) => {
+                current = following;
+                following = after;
+            }
+            Err(_) => {
+                return Err(format!(
+                    "string set holds binary data and no structure follows it, the length \
+                     byte ({}) may be wrong",
+                    data[offset + 1]
+                ))
+            }
+        }
+    }
+    Ok(next)
+}
+
+/// Number of well-formed structures from `offset` on
+fn chain_length(data: &[u8], mut offset: usize) -> usize {
+    let mut count = 0;
+    while let Ok(next) = check_struct(data, offset) {
+        count += 1;
+        if data[offset] == END_OF_TABLE || next == data.len() {
+            break;
+        }
+        offset = next;
+    }
+    count
+}
+
+/// Finds the offset after `offset` where decoding should resume
+///
+/// Formatted areas often hold two zero bytes followed by what reads as a
+/// header, and a header read that way swallows the structures that follow
+/// it.  Of the offsets after the end of a string set, the one that starts
+/// the longest run of well-formed structures is taken; reserved and known
+/// handles are skipped.
+fn resync(data: &[u8], offset: usize, handles: &HashSet<u16>) -> Option<usize> {
+    (offset.max(1) + 1..data.len().saturating_sub(HEADER_SIZE - 1))
+        .filter(|&candidate| {
+            let handle = u16::from_le_bytes([data[candidate + 2], data[candidate + 3]]);
+            data[candidate - 2..candidate] == [0, 0]
+                && handle < RESERVED_HANDLES
+                && !handles.contains(&handle)
+        })
+        .map(|candidate| (chain_length(data, candidate), candidate))
+        .filter(|&(length, _)| length > 0)
+        // The earliest of the longest runs
+        .max_by_key(|&(length, candidate)| (length, Reverse(candidate)))
+        .map(|(_, candidate)| candidate)
+}
+
+/// Keeps the well-formed structures of `data` and records where it is
+/// damaged; `announced_length` is the length given by the entry point
+pub fn recover_table(data: &[u8], announced_length: Option<usize>) -> Recovered {
+    let mut table = Vec::with_capacity(data.len());
+    let mut corruptions = Vec::new();
+    let mut handles = HashSet::new();
+    let mut after_handle = None;
+    let mut offset = 0;
+
+    while offset < data.len() {
+        match check_struct(data, offset) {
+            Ok(next) => {
+                let handle = u16::from_le_bytes([data[offset + 2], data[offset + 3]]);
+                table.extend_from_slice(&data[offset..next]);
+                handles.insert(handle);
+                after_handle = Some(handle);
+                // What follows End-of-Table is not part of the table
+                if data[offset] == END_OF_TABLE {
+                    break;
+                }
+                offset = next;
+            }
+            // Padding after the last structure
+            Err(_) if data[offset..].iter().all(|&byte| byte == 0) => break,
+            Err(reason) => {
+                let resumed_at = resync(data, offset, &handles);
+                corruptions.push(Corruption {
+                    offset,
+                    after_handle,
+                    reason,
+                    resumed_at,
+                });
+                match resumed_at {
+                    Some(next) => offset = next,
+                    None => break,
+                }
+            }
+        }
+    }
+
+    if let Some(length) = announced_length.filter(|&length| length > data.len()) {
+        corruptions.push(Corruption {
+            offset: data.len(),
+            after_handle,
+            reason: format!(
+                "the table ends after {} of the {} bytes given by the entry point",
+                data.len(),
+                length
+            ),
+            resumed_at: None,
+        });
+    }
+
+    Recovered { table, corruptions }
+}
+
+#[test]
+fn test_recover_table() {
+    use crate::build::push_struct;
+
+    let mut data = Vec::new();
+    push_struct(&mut data, &[1, 8, 1, 0, 1, 0, 0, 0], &[b"Vendor".to_vec()]);
+    let first_end = data.len();
+    // A length byte too short: the rest of the formatted area reads as
+    // strings, ended by its zero bytes, and the next header is out of place
+    push_struct(
+        &mut data,
+        &[2, 11, 2, 0, 1, 2, 3, 4, 5, 0, 0],
+        &[b"Board".to_vec()],
+    );
+    data[first_end + 1] = 5;
+    let resumed = data.len();
+    push_struct(&mut data, &[3, 6, 3, 0, 0, 0], &[]);
+    // Cut in the middle of the string set
+    let cut = data.len();
+    push_struct(&mut data, &[4, 6, 4, 0, 1, 0], &[b"Processor".to_vec()]);
+    data.truncate(cut + 9);
+
+    let recovered = recover_table(&data, Some(data.len() + 10));
+    let table = UndefinedStructTable::from(recovered.table.clone());
+    let handles: Vec<u16> = table.iter().map(|s| *s.header.handle()).collect();
+    assert_eq!(handles, vec![1, 3]);
+
+    assert_eq!(recovered.corruptions.len(), 3);
+    let wrong_length = &recovered.corruptions[0];
+    assert_eq!(wrong_length.offset, first_end);
+    assert_eq!(wrong_length.after_handle, Some(1));
+    assert!(wrong_length.reason.contains("length byte (5)"));
+    assert_eq!(wrong_length.resumed_at, Some(resumed));
+    let truncated = &recovered.corruptions[1];
+    assert_eq!(truncated.offset, cut);
+    assert_eq!(truncated.after_handle, Some(3));
+    assert!(truncated.reason.contains("not terminated"));
+    assert_eq!(truncated.resumed_at, None);
+    assert!(recovered.corruptions[2]
+        .reason
+        .contains("the table ends after"));
+    // The structures kept form a table that is recovered whole
+    let again = recover_table(&recovered.table, None);
+    assert_eq!(again.table, recovered.table);
+    assert!(again.corruptions.is_empty());
+    assert_eq!(
+        truncated.to_string(),
+        format!(
+            "Corrupt data at offset {:#06X} (after handle 0x0003): string set is not \
+             terminated before the end of the table; nothing more could be decoded.",
+            cut
+        )
+    );
+
+    // An intact table, with padding after End-of-Table, is kept whole
+    let mut data = Vec::new();
+    push_struct(&mut data, &[0, 4, 0, 0], &[b"Vendor".to_vec()]);
+    push_struct(&mut data, &[127, 4, 1, 0], &[]);
+    let end = data.len();
+    data.extend_from_slice(&[0xFF; 16]);
+    let recovered = recover_table(&data, None);
+    assert_eq!(recovered.table, data[..end].to_vec());
+    assert!(recovered.corruptions.is_empty());
+
+    // Line breaks in strings, with the structures after them in place, are
+    // kept
+    let mut data = Vec::new();
+    push_struct(
+        &mut data,
+        &[11, 5, 0, 0, 2],
+        &[b"Line 1\r\nLine 2".to_vec(), b"Build\x01".to_vec()],
+    );
+    push_struct(&mut data, &[1, 8, 1, 0, 1, 0, 0, 0], &[b"Vendor".to_vec()]);
+    push_struct(&mut data, &[127, 4, 2, 0], &[]);
+    let recovered = recover_table(&data, None);
+    assert_eq!(recovered.table, data);
+    assert!(recovered.corruptions.is_empty());
+}
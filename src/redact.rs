@@ -0,0 +1,496 @@
+//! Redaction of identifying values
+//!
+//! Serial numbers, asset tags, UUIDs, MAC addresses found in OEM strings and
+//! the Redfish addresses and network device of management controller host
+//! interfaces (type 42) are replaced by fake values of the same length and
+//! format.  A fake value is derived from the original one, so a value
+//! repeated across structures (e.g. a system serial number reused as the
+//! chassis serial number) is replaced by the same fake value everywhere.
+//!
+//! Fake values are an HMAC of the original under a secret key, so that
+//! short values (a MAC address of a known vendor, a numeric asset tag)
+//! cannot be found by trying every candidate.  The key is given with
+//! `--redact-key`, or is a random key kept in the user's configuration
+//! directory, which keeps the fake values of one reporter stable.
+//!
+//! Nothing changes length, so every structure keeps its layout and the
+//! table its size.  None of the redacted bytes are covered by a checksum.
+
+use crate::build::push_struct;
+use crate::hwids::sha1;
+use crate::Opt;
+use smbioslib::*;
+use std::{
+    collections::hash_map::RandomState,
+    fs::OpenOptions,
+    hash::{BuildHasher, Hasher},
+    io::{Error, ErrorKind, Write},
+    path::PathBuf,
+};
+
+/// Length of the generated keys
+const KEY_LENGTH: usize = 32;
+
+/// Block size of SHA-1, as used by HMAC
+const SHA1_BLOCK_SIZE: usize = 64;
+
+/// String fields holding serial numbers and asset tags (type, offset)
+const IDENTIFIER_STRINGS: [(u8, usize); 12] = [
+    (1, 0x07),  // System Serial Number
+    (2, 0x07),  // Base Board Serial Number
+    (2, 0x08),  // Base Board Asset Tag
+    (3, 0x07),  // Chassis Serial Number
+    (3, 0x08),  // Chassis Asset Tag
+    (4, 0x20),  // Processor Serial Number
+    (4, 0x21),  // Processor Asset Tag
+    (17, 0x18), // Memory Device Serial Number
+    (17, 0x19), // Memory Device Asset Tag
+    (22, 0x07), // Portable Battery Serial Number
+    (39, 0x08), // Power Supply Serial Number
+    (39, 0x09), // Power Supply Asset Tag
+];
+
+/// Values firmware uses for unset strings, which identify nothing
+const PLACEHOLDERS: [&str; 12] = [
+    "Not Specified",
+    "Not Applicable",
+    "Not Available",
+    "None",
+    "N/A",
+    "Unknown",
+    "Default string",
+    "To Be Filled By O.E.M.",
+    "System Serial Number",
+    "Chassis Serial Number",
+    "Base Board Serial Number",
+    "No Asset Tag",
+];
+
+/// Redfish over IP protocol (DSP0270)
+const REDFISH_OVER_IP: u8 = 0x04;
+
+/// The key of --redact: --redact-key, or the key kept in the user's
+/// configuration directory, created on first use
+pub fn redaction_key(opt: &Opt) -> Vec<u8> {
+    if let Some(key) = opt.redact_key.as_ref() {
+        return key.as_bytes().to_vec();
+    }
+    let path = match key_path() {
+        Some(path) => path,
+        None => {
+            eprintln!(
+                "Warning: no configuration directory for the redaction key; \
+                 fake values will differ between runs"
+            );
+            return random_key();
+        }
+    };
+    match read_or_create_key(&path) {
+        Ok(key) => key,
+        Err(error) => {
+            eprintln!(
+                "Warning: cannot keep the redaction key in {}: {}; \
+                 fake values will differ between runs",
+                path.display(),
+                error
+            );
+            random_key()
+        }
+    }
+}
+
+/// The redaction key file under the user's configuration directory
+fn key_path() -> Option<PathBuf> {
+    let config = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(config) => PathBuf::from(config),
+        None => match std::env::var_os("APPDATA") {
+            Some(appdata) => PathBuf::from(appdata),
+            None => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+        },
+    };
+    Some(config.join(env!("CARGO_PKG_NAME")).join("redact.key"))
+}
+
+fn read_or_create_key(path: &std::path::Path) -> Result<Vec<u8>, Error> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    match options.open(path) {
+        Ok(mut file) => {
+            let key = random_key();
+            file.write_all(&key)?;
+            Ok(key)
+        }
+        // Created by another run in the meantime
+        Err(error) if error.kind() == ErrorKind::AlreadyExists => {
+            let key = std::fs::read(path)?;
+            match key.is_empty() {
+                true => Err(Error::new(ErrorKind::InvalidData, "the key file is empty")),
+                false => Ok(key),
+            }
+        }
+        Err(error) => Err(error),
+    }
+}
+
+/// A key from the random SipHash keys the standard library seeds from the
+/// operating system
+fn random_key() -> Vec<u8> {
+    let state = RandomState::new();
+    (0..KEY_LENGTH / 8)
+        .flat_map(|block| {
+            let mut hasher = state.build_hasher();
+            hasher.write_usize(block);
+            hasher.finish().to_le_bytes()
+        })
+        .collect()
+}
+
+/// Returns a copy of `data` with identifying values replaced
+pub fn redact_table(data: &SMBiosData, key: &[u8]) -> SMBiosData {
+    SMBiosData::from_vec_and_version(redacted_table_bytes(data, key), data.version)
+}
+
+/// Returns the raw table of `data` with identifying values replaced
+pub fn redacted_table_bytes(data: &SMBiosData, key: &[u8]) -> Vec<u8> {
+    let mut table = Vec::new();
+    for undefined_struct in data.iter() {
+        let mut fields = undefined_struct.fields.clone();
+        let mut strings: Vec<Vec<u8>> = undefined_struct.strings.iter().cloned().collect();
+        redact_struct(&mut fields, &mut strings, key);
+        push_struct(&mut table, &fields, &strings);
+    }
+    table
+}
+
+fn redact_struct(fields: &mut [u8], strings: &mut [Vec<u8>], key: &[u8]) {
+    let struct_type = fields[0];
+
+    for (_, offset) in IDENTIFIER_STRINGS
+        .iter()
+        .filter(|(identifier_type, _)| *identifier_type == struct_type)
+    {
+        if let Some(string) = fields
+            .get(*offset)
+            .filter(|&&index| index != 0)
+            .and_then(|&index| strings.get_mut(index as usize - 1))
+        {
+            if !is_placeholder(string) {
+                *string = fake_string(string, key);
+            }
+        }
+    }
+
+    match struct_type {
+        // System UUID
+        1 => {
+            if let Some(uuid) = fields.get_mut(0x08..0x18) {
+                redact_uuid(uuid, key);
+            }
+        }
+        // Portable Battery: SBDS Serial Number, used when the string is absent
+        22 => {
+            if let Some(serial) = fields.get_mut(0x10..0x12) {
+                if serial != [0, 0] {
+                    serial.copy_from_slice(&fake_bytes(serial, key));
+                }
+            }
+        }
+        // OEM Strings
+        11 => {
+            for string in strings.iter_mut() {
+                redact_mac_addresses(string, key);
+            }
+        }
+        // Management Controller Host Interface
+        42 => redact_host_interface(fields, strings, key),
+        _ => (),
+    }
+}
+
+/// True for strings that are blank, repeat a single character or are a
+/// well known placeholder
+fn is_placeholder(string: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(string);
+    let text = text.trim();
+    match text.chars().next() {
+        None => true,
+        Some(first) => {
+            text.chars().all(|c| c == first)
+                || PLACEHOLDERS
+                    .iter()
+                    .any(|placeholder| placeholder.eq_ignore_ascii_case(text))
+        }
+    }
+}
+
+/// HMAC-SHA1 (RFC 2104) of `message` under `key`
+fn hmac_sha1(key: &[u8], message: &[u8]) -> [u8; 20] {
+    let mut block = [0u8; SHA1_BLOCK_SIZE];
+    match key.len() > SHA1_BLOCK_SIZE {
+        true => block[..20].copy_from_slice(&sha1(key)),
+        false => block[..key.len()].copy_from_slice(key),
+    }
+    let mut inner: Vec<u8> = block.iter().map(|byte| byte ^ 0x36).collect();
+    inner.extend_from_slice(message);
+    let mut outer: Vec<u8> = block.iter().map(|byte| byte ^ 0x5c).collect();
+    outer.extend_from_slice(&sha1(&inner));
+    sha1(&outer)
+}
+
+/// Stable pseudo-random bytes derived from `original` under `key`, as many
+/// as it has
+fn fake_bytes(original: &[u8], key: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(original.len());
+    let mut block = 0u32;
+    while bytes.len() < original.len() {
+        let mut message = original.to_vec();
+        message.extend_from_slice(&block.to_le_bytes());
+        bytes.extend_from_slice(&hmac_sha1(key, &message));
+        block += 1;
+    }
+    bytes.truncate(original.len());
+    bytes
+}
+
+/// Replaces every digit and letter with a fake one of the same kind,
+/// keeping separators and spacing
+fn fake_string(original: &[u8], key: &[u8]) -> Vec<u8> {
+    original
+        .iter()
+        .zip(fake_bytes(original, key))
+        .map(|(&c, random)| match c {
+            b'0'..=b'9' => b'0' + random % 10,
+            b'A'..=b'Z' => b'A' + random % 26,
+            b'a'..=b'z' => b'a' + random % 26,
+            _ => c,
+        })
+        .collect()
+}
+
+/// Replaces a UUID unless it is one of the "not present" / "not set" values
+fn redact_uuid(uuid: &mut [u8], key: &[u8]) {
+    if uuid.iter().all(|&x| x == 0x00) || uuid.iter().all(|&x| x == 0xFF) {
+        return;
+    }
+    uuid.copy_from_slice(&fake_bytes(uuid, key));
+}
+
+/// Replaces the hexadecimal digits of MAC addresses written as six pairs
+/// separated by ':' or '-'
+fn redact_mac_addresses(string: &mut [u8], key: &[u8]) {
+    const MAC_LENGTH: usize = 17;
+    let mut start = 0;
+    while start + MAC_LENGTH <= string.len() {
+        let candidate = &string[start..start + MAC_LENGTH];
+        let separator = candidate[2];
+        let is_mac = (separator == b':' || separator == b'-')
+            && candidate.iter().enumerate().all(|(i, c)| match i % 3 {
+                2 => *c == separator,
+                _ => c.is_ascii_hexdigit(),
+            });
+        if !is_mac {
+            start += 1;
+            continue;
+        }
+        let fake = fake_bytes(candidate, key);
+        for (c, random) in string[start..start + MAC_LENGTH].iter_mut().zip(fake) {
+            if c.is_ascii_hexdigit() {
+                let digit = b"0123456789abcdef"[(random % 16) as usize];
+                *c = match c.is_ascii_uppercase() {
+                    true => digit.to_ascii_uppercase(),
+                    false => digit,
+                };
+            }
+        }
+        start += MAC_LENGTH;
+    }
+}
+
+/// Replaces the MAC address and USB serial number of the v2 device
+/// descriptors (DSP0270: 8.2), and the service UUID, addresses and hostname
+/// of Redfish over IP protocol records (DSP0270: 8.6)
+fn redact_host_interface(fields: &mut [u8], strings: &mut [Vec<u8>], key: &[u8]) {
+    let specific_length = match fields.get(0x05) {
+        Some(&length) => length as usize,
+        None => return,
+    };
+    if let Some(specific_data) = fields.get_mut(0x06..0x06 + specific_length) {
+        // The descriptor follows the device type; offsets are in it
+        let (serial, mac) = match specific_data.first() {
+            Some(0x4) => (Some(0x05), Some(0x06)),
+            Some(0x5) => (None, Some(0x09)),
+            _ => (None, None),
+        };
+        if let Some(mac) = mac.and_then(|mac| specific_data.get_mut(1 + mac..1 + mac + 6)) {
+            mac.copy_from_slice(&fake_bytes(mac, key));
+        }
+        if let Some(string) = serial
+            .and_then(|serial| specific_data.get(1 + serial))
+            .filter(|&&index| index != 0)
+            .and_then(|&index| strings.get_mut(index as usize - 1))
+        {
+            if !is_placeholder(string) {
+                *string = fake_string(string, key);
+            }
+        }
+    }
+    let mut offset = 0x06 + specific_length;
+    let record_count = match fields.get(offset) {
+        Some(&count) => count,
+        None => return,
+    };
+    offset += 1;
+
+    for _ in 0..record_count {
+        let (protocol_type, length) = match fields.get(offset..offset + 2) {
+            Some(record) => (record[0], record[1] as usize),
+            None => return,
+        };
+        let rdata = match fields.get_mut(offset + 2..offset + 2 + length) {
+            Some(rdata) => rdata,
+            None => return,
+        };
+        if protocol_type == REDFISH_OVER_IP && rdata.len() >= 91 {
+            redact_uuid(&mut rdata[0..16], key);
+            let host_format = rdata[17];
+            redact_address(&mut rdata[18..34], host_format, key);
+            let service_format = rdata[51];
+            redact_address(&mut rdata[52..68], service_format, key);
+            let hostname_length = rdata[90] as usize;
+            if let Some(hostname) = rdata.get_mut(91..91 + hostname_length) {
+                let fake = fake_string(hostname, key);
+                hostname.copy_from_slice(&fake);
+            }
+        }
+        offset += 2 + length;
+    }
+}
+
+/// Replaces an IPv4 or IPv6 address with one from the ranges reserved for
+/// documentation (192.0.2.0/24, 2001:db8::/32)
+fn redact_address(address: &mut [u8], address_format: u8, key: &[u8]) {
+    if address.iter().all(|&x| x == 0) {
+        return;
+    }
+    let fake = fake_bytes(address, key);
+    match address_format {
+        0x1 => {
+            address[0..3].copy_from_slice(&[192, 0, 2]);
+            address[3] = fake[3];
+        }
+        0x2 => {
+            address[0..4].copy_from_slice(&[0x20, 0x01, 0x0d, 0xb8]);
+            address[4..].copy_from_slice(&fake[4..]);
+        }
+        _ => address.copy_from_slice(&fake),
+    }
+}
+
+#[test]
+fn test_redact_table() {
+    let mut table = vec![
+        // System Information with a UUID, serial number "ABC-1234"
+        0x01, 0x1B, 0x01, 0x00, 0x01, 0x02, 0x00, 0x03, 0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66,
+        0x77, 0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF, 0x06, 0x00, 0x00,
+    ];
+    table.extend_from_slice(b"ACME Corp.\0Road Runner\0ABC-1234\0\0");
+    table.extend_from_slice(&[
+        // Chassis Information, same serial number, placeholder asset tag
+        0x03, 0x09, 0x02, 0x00, 0x01, 0x0A, 0x00, 0x02, 0x03,
+    ]);
+    table.extend_from_slice(b"ACME Corp.\0ABC-1234\0Not Specified\0\0");
+    // OEM Strings
+    table.extend_from_slice(&[0x0B, 0x05, 0x03, 0x00, 0x01]);
+    table.extend_from_slice(b"mac=00:1A:2b:3C:4d:5E;\0\0");
+    // End of Table
+    table.extend_from_slice(&[0x7F, 0x04, 0x04, 0x00, 0x00, 0x00]);
+
+    let data = SMBiosData::from_vec_and_version(table.clone(), None);
+    let redacted = redacted_table_bytes(&data, b"key");
+    assert_eq!(redacted.len(), table.len());
+    // The fake values depend on the key
+    assert_eq!(redacted_table_bytes(&data, b"key"), redacted);
+    assert_ne!(redacted_table_bytes(&data, b"other key"), redacted);
+
+    let redacted = SMBiosData::from_vec_and_version(redacted, None);
+    let system = redacted.first::<SMBiosSystemInformation<'_>>().unwrap();
+    let serial = system.serial_number().to_utf8_lossy().unwrap();
+    assert_ne!(serial, "ABC-1234");
+    assert_eq!(serial.len(), 8);
+    assert_eq!(&serial[3..4], "-");
+    assert!(serial[..3].chars().all(|c| c.is_ascii_uppercase()));
+    assert_eq!(
+        system.product_name().to_utf8_lossy().unwrap(),
+        "Road Runner"
+    );
+    assert_ne!(
+        &redacted.iter().next().unwrap().fields[0x08..0x18],
+        &table[0x08..0x18]
+    );
+
+    let chassis = redacted
+        .first::<SMBiosSystemChassisInformation<'_>>()
+        .unwrap();
+    assert_eq!(chassis.serial_number().to_utf8_lossy().unwrap(), serial);
+    assert_eq!(
+        chassis.asset_tag_number().to_utf8_lossy().unwrap(),
+        "Not Specified"
+    );
+
+    let oem = redacted.first::<SMBiosOemStrings<'_>>().unwrap();
+    let mac = oem.oem_strings().get_string(1).to_utf8_lossy().unwrap();
+    assert_ne!(mac, "mac=00:1A:2b:3C:4d:5E;");
+    assert!(mac.starts_with("mac=") && mac.ends_with(';'));
+    assert_eq!(mac.len(), 22);
+
+    assert_eq!(redacted.iter().count(), 4);
+    assert_eq!(crate::build::table_bytes(&data), table);
+}
+
+#[test]
+fn test_hmac_sha1() {
+    // RFC 2202 test cases 1 and 6
+    assert_eq!(
+        hmac_sha1(&[0x0b; 20], b"Hi There"),
+        [
+            0xb6, 0x17, 0x31, 0x86, 0x55, 0x05, 0x72, 0x64, 0xe2, 0x8b, 0xc0, 0xb6, 0xfb, 0x37,
+            0x8c, 0x8e, 0xf1, 0x46, 0xbe, 0x00
+        ]
+    );
+    assert_eq!(
+        hmac_sha1(
+            &[0xaa; 80],
+            b"Test Using Larger Than Block-Size Key - Hash Key First"
+        ),
+        [
+            0xaa, 0x4a, 0xe5, 0xe1, 0x52, 0x72, 0xd0, 0x0e, 0x95, 0x70, 0x56, 0x37, 0xce, 0x8a,
+            0x3b, 0x55, 0xed, 0x40, 0x21, 0x12
+        ]
+    );
+}
+
+#[test]
+fn test_redact_host_interface() {
+    // Network Host Interface with a USB v2 descriptor and no protocol
+    // records; serial number "SN42"
+    let mut fields = vec![
+        42, 0x18, 0x2A, 0x00, 0x40, 0x11, 0x04, 0x10, 0x6B, 0x04, 0xB0, 0xFF,
+    ];
+    fields.extend_from_slice(&[
+        0x01, 0x02, 0x00, 0x00, 0x5E, 0x00, 0x53, 0x01, 0x00, 0x00, 0x00, 0x00,
+    ]);
+    let mut strings = vec![b"SN42".to_vec()];
+    let original = fields.clone();
+    redact_struct(&mut fields, &mut strings, b"key");
+
+    assert_ne!(&fields[0x0D..0x13], &original[0x0D..0x13]);
+    assert_eq!(&fields[..0x0D], &original[..0x0D]);
+    assert_eq!(&fields[0x13..], &original[0x13..]);
+    assert_ne!(strings[0], b"SN42");
+    assert_eq!(strings[0].len(), 4);
+}
@@ -0,0 +1,584 @@
+//! Redfish resources
+//!
+//! Maps decoded structures onto DMTF Redfish resources (DSP0268), keyed by
+//! their `@odata.id` as in a Redfish mockup:
+//!
+//! * ComputerSystem from System Information (type 1), with the processor
+//!   and memory summaries
+//! * Bios and a BIOS SoftwareInventory entry from BIOS Information (type 0)
+//! * Processor collection from Processor Information (type 4), with the
+//!   caches (type 7) as processor memory
+//! * Memory collection from Memory Device (type 17)
+//! * Chassis from Chassis Information (type 3), with its PCIeSlots from
+//!   System Slots (type 9)
+//!
+//! Values the tables do not hold, or that have no Redfish equivalent, are
+//! left out.
+
+use crate::decoded::DecodedStruct;
+use serde_json::{json, Map, Value};
+
+const SYSTEM: &str = "/redfish/v1/Systems/1";
+const CHASSIS: &str = "/redfish/v1/Chassis/1";
+const BIOS_INVENTORY: &str = "/redfish/v1/UpdateService/FirmwareInventory/BIOS";
+
+const MIB: u64 = 1 << 20;
+
+/// Builds the Redfish resources of a table
+pub fn redfish_resources(structs: &[DecodedStruct]) -> Map<String, Value> {
+    let of_type = |struct_type: u8| structs.iter().filter(move |s| s.struct_type == struct_type);
+    let mut resources = Map::new();
+
+    let bios = of_type(0).next();
+    let system = of_type(1).next();
+    let processors: Vec<&DecodedStruct> = of_type(4)
+        .filter(|s| s.value("Type") != Some("Other"))
+        .collect();
+    let memory: Vec<&DecodedStruct> = of_type(17).collect();
+
+    // ComputerSystem
+    let mut computer_system = resource(
+        "#ComputerSystem.v1_20_0.ComputerSystem",
+        SYSTEM,
+        "1",
+        "System",
+    );
+    if let Some(system) = system {
+        insert_values(
+            &mut computer_system,
+            system,
+            &[
+                ("Manufacturer", "Manufacturer"),
+                ("Model", "Product Name"),
+                ("SerialNumber", "Serial Number"),
+                ("SKU", "SKU Number"),
+                ("UUID", "UUID"),
+            ],
+        );
+    }
+    computer_system.insert("SystemType".into(), json!("Physical"));
+    if let Some(bios) = bios {
+        insert_values(&mut computer_system, bios, &[("BiosVersion", "Version")]);
+    }
+    let populated: Vec<&&DecodedStruct> = processors
+        .iter()
+        .filter(|s| {
+            s.value("Status")
+                .is_some_and(|v| v.starts_with("Populated"))
+        })
+        .collect();
+    let mut processor_summary = Map::new();
+    processor_summary.insert("Count".into(), json!(populated.len()));
+    if let Some(model) = populated.first().and_then(|s| known(s.value("Version"))) {
+        processor_summary.insert("Model".into(), json!(model));
+    }
+    let cores: u64 = populated
+        .iter()
+        .filter_map(|s| s.number("Core Count"))
+        .sum();
+    let threads: u64 = populated
+        .iter()
+        .filter_map(|s| s.number("Thread Count"))
+        .sum();
+    if cores > 0 {
+        processor_summary.insert("CoreCount".into(), json!(cores));
+    }
+    if threads > 0 {
+        processor_summary.insert("LogicalProcessorCount".into(), json!(threads));
+    }
+    computer_system.insert("ProcessorSummary".into(), Value::Object(processor_summary));
+    let total_bytes: u64 = memory.iter().filter_map(|s| s.size_bytes("Size")).sum();
+    computer_system.insert(
+        "MemorySummary".into(),
+        json!({ "TotalSystemMemoryGiB": total_bytes as f64 / (1u64 << 30) as f64 }),
+    );
+    computer_system.insert("Bios".into(), link(&format!("{}/Bios", SYSTEM)));
+    computer_system.insert("Processors".into(), link(&format!("{}/Processors", SYSTEM)));
+    computer_system.insert("Memory".into(), link(&format!("{}/Memory", SYSTEM)));
+    computer_system.insert("Links".into(), json!({ "Chassis": [link(CHASSIS)] }));
+    resources.insert(SYSTEM.into(), Value::Object(computer_system));
+
+    // Bios, and the BIOS firmware in the firmware inventory
+    let mut bios_resource = resource(
+        "#Bios.v1_2_0.Bios",
+        &format!("{}/Bios", SYSTEM),
+        "Bios",
+        "BIOS",
+    );
+    bios_resource.insert(
+        "Links".into(),
+        json!({ "ActiveSoftwareImage": link(BIOS_INVENTORY) }),
+    );
+    resources.insert(format!("{}/Bios", SYSTEM), Value::Object(bios_resource));
+    if let Some(bios) = bios {
+        let mut inventory = resource(
+            "#SoftwareInventory.v1_9_0.SoftwareInventory",
+            BIOS_INVENTORY,
+            "BIOS",
+            "BIOS",
+        );
+        insert_values(
+            &mut inventory,
+            bios,
+            &[("Manufacturer", "Vendor"), ("Version", "Version")],
+        );
+        if let Some(date) = bios.value("Release Date").and_then(iso_date) {
+            inventory.insert("ReleaseDate".into(), json!(date));
+        }
+        let upgradeable = bios
+            .field("Characteristics")
+            .is_some_and(|field| field.items.iter().any(|item| item == "BIOS is upgradeable"));
+        inventory.insert("Updateable".into(), json!(upgradeable));
+        resources.insert(BIOS_INVENTORY.into(), Value::Object(inventory));
+    }
+
+    // Processors
+    let mut members = Vec::new();
+    for (index, processor) in processors.iter().enumerate() {
+        let id = format!("CPU{}", index);
+        let odata_id = format!("{}/Processors/{}", SYSTEM, id);
+        let name = processor.value("Socket Designation").unwrap_or("Processor");
+        let mut resource = resource("#Processor.v1_18_0.Processor", &odata_id, &id, name);
+        insert_values(
+            &mut resource,
+            processor,
+            &[
+                ("Socket", "Socket Designation"),
+                ("Manufacturer", "Manufacturer"),
+                ("Model", "Version"),
+                ("SerialNumber", "Serial Number"),
+                ("PartNumber", "Part Number"),
+            ],
+        );
+        if processor.value("Type") == Some("Central Processor") {
+            resource.insert("ProcessorType".into(), json!("CPU"));
+        }
+        insert_numbers(
+            &mut resource,
+            processor,
+            &[
+                ("MaxSpeedMHz", "Max Speed"),
+                ("OperatingSpeedMHz", "Current Speed"),
+                ("TotalCores", "Core Count"),
+                ("TotalEnabledCores", "Core Enabled"),
+                ("TotalThreads", "Thread Count"),
+            ],
+        );
+        if let Some(id) = processor.value("ID") {
+            resource.insert(
+                "ProcessorId".into(),
+                json!({ "IdentificationRegisters": format!("0x{}", id.replace(' ', "")) }),
+            );
+        }
+        let caches: Vec<Value> = ["L1", "L2", "L3"]
+            .iter()
+            .filter_map(|level| {
+                let handle = processor.value(&format!("{} Cache Handle", level))?;
+                let handle = u16::from_str_radix(handle.strip_prefix("0x")?, 16).ok()?;
+                let cache = structs
+                    .iter()
+                    .find(|s| s.struct_type == 7 && s.handle == handle)?;
+                let mut memory = Map::new();
+                memory.insert("MemoryType".into(), json!(format!("{}Cache", level)));
+                memory.insert("IntegratedMemory".into(), json!(true));
+                if let Some(size) = cache
+                    .size_bytes("Installed Size")
+                    .filter(|size| *size >= MIB)
+                {
+                    memory.insert("CapacityMiB".into(), json!(size / MIB));
+                }
+                Some(Value::Object(memory))
+            })
+            .collect();
+        if !caches.is_empty() {
+            resource.insert("ProcessorMemory".into(), Value::Array(caches));
+        }
+        resource.insert(
+            "Status".into(),
+            json!({ "State": match processor.value("Status") {
+                Some("Populated, Enabled") => "Enabled",
+                Some("Unpopulated") => "Absent",
+                _ => "Disabled",
+            }}),
+        );
+        resources.insert(odata_id.clone(), Value::Object(resource));
+        members.push(link(&odata_id));
+    }
+    resources.insert(
+        format!("{}/Processors", SYSTEM),
+        collection(
+            "#ProcessorCollection.ProcessorCollection",
+            &format!("{}/Processors", SYSTEM),
+            "Processors Collection",
+            members,
+        ),
+    );
+
+    // Memory
+    let mut members = Vec::new();
+    for (index, device) in memory.iter().enumerate() {
+        let id = format!("DIMM{}", index);
+        let odata_id = format!("{}/Memory/{}", SYSTEM, id);
+        let name = device.value("Locator").unwrap_or("Memory");
+        let mut resource = resource("#Memory.v1_17_0.Memory", &odata_id, &id, name);
+        let size = device.size_bytes("Size");
+        if let Some(size) = size {
+            resource.insert("CapacityMiB".into(), json!(size / MIB));
+        }
+        insert_values(
+            &mut resource,
+            device,
+            &[
+                ("Manufacturer", "Manufacturer"),
+                ("PartNumber", "Part Number"),
+                ("SerialNumber", "Serial Number"),
+            ],
+        );
+        insert_numbers(
+            &mut resource,
+            device,
+            &[
+                ("OperatingSpeedMhz", "Configured Memory Speed"),
+                ("DataWidthBits", "Data Width"),
+                ("BusWidthBits", "Total Width"),
+                ("RankCount", "Rank"),
+            ],
+        );
+        if let Some(speed) = device.number("Speed") {
+            resource.insert("AllowedSpeedsMHz".into(), json!([speed]));
+        }
+        if let Some(device_type) = device.value("Type").and_then(memory_device_type) {
+            resource.insert("MemoryDeviceType".into(), json!(device_type));
+            resource.insert("MemoryType".into(), json!("DRAM"));
+        }
+        if let Some(module_type) = base_module_type(device) {
+            resource.insert("BaseModuleType".into(), json!(module_type));
+        }
+        let mut location = Map::new();
+        location.insert("ServiceLabel".into(), json!(name));
+        location.insert("LocationType".into(), json!("Slot"));
+        resource.insert("Location".into(), json!({ "PartLocation": location }));
+        if let Some(bank) = known(device.value("Bank Locator")) {
+            resource.insert("DeviceLocator".into(), json!(format!("{} {}", bank, name)));
+        } else {
+            resource.insert("DeviceLocator".into(), json!(name));
+        }
+        resource.insert(
+            "Status".into(),
+            json!({ "State": match size {
+                Some(_) => "Enabled",
+                None => "Absent",
+            }}),
+        );
+        resources.insert(odata_id.clone(), Value::Object(resource));
+        members.push(link(&odata_id));
+    }
+    resources.insert(
+        format!("{}/Memory", SYSTEM),
+        collection(
+            "#MemoryCollection.MemoryCollection",
+            &format!("{}/Memory", SYSTEM),
+            "Memory Collection",
+            members,
+        ),
+    );
+
+    // Chassis and its PCIe slots
+    let mut chassis = resource("#Chassis.v1_23_0.Chassis", CHASSIS, "1", "Chassis");
+    if let Some(information) = of_type(3).next() {
+        insert_values(
+            &mut chassis,
+            information,
+            &[
+                ("Manufacturer", "Manufacturer"),
+                ("Model", "Version"),
+                ("SerialNumber", "Serial Number"),
+                ("AssetTag", "Asset Tag"),
+                ("SKU", "SKU Number"),
+            ],
+        );
+        if let Some(chassis_type) = information.value("Type") {
+            chassis.insert("ChassisType".into(), json!(chassis_type_of(chassis_type)));
+        }
+    } else {
+        chassis.insert("ChassisType".into(), json!("Other"));
+    }
+    chassis.insert("PCIeSlots".into(), link(&format!("{}/PCIeSlots", CHASSIS)));
+    chassis.insert("Links".into(), json!({ "ComputerSystems": [link(SYSTEM)] }));
+    resources.insert(CHASSIS.into(), Value::Object(chassis));
+
+    let slots: Vec<Value> = of_type(9).filter_map(pcie_slot).collect();
+    let mut pcie_slots = resource(
+        "#PCIeSlots.v1_6_0.PCIeSlots",
+        &format!("{}/PCIeSlots", CHASSIS),
+        "PCIeSlots",
+        "PCIe Slots",
+    );
+    pcie_slots.insert("Slots".into(), Value::Array(slots));
+    resources.insert(format!("{}/PCIeSlots", CHASSIS), Value::Object(pcie_slots));
+
+    resources
+}
+
+/// A resource with its common properties
+fn resource(odata_type: &str, odata_id: &str, id: &str, name: &str) -> Map<String, Value> {
+    let mut resource = Map::new();
+    resource.insert("@odata.type".into(), json!(odata_type));
+    resource.insert("@odata.id".into(), json!(odata_id));
+    resource.insert("Id".into(), json!(id));
+    resource.insert("Name".into(), json!(name));
+    resource
+}
+
+fn collection(odata_type: &str, odata_id: &str, name: &str, members: Vec<Value>) -> Value {
+    json!({
+        "@odata.type": odata_type,
+        "@odata.id": odata_id,
+        "Name": name,
+        "Members@odata.count": members.len(),
+        "Members": members,
+    })
+}
+
+fn link(odata_id: &str) -> Value {
+    json!({ "@odata.id": odata_id })
+}
+
+/// A value that holds information, not a placeholder such as "Unknown" or
+/// a decoding error such as "<BAD INDEX>"
+fn known(value: Option<&str>) -> Option<&str> {
+    value.filter(|value| {
+        !value.starts_with('<')
+            && !matches!(
+                *value,
+                "" | "Unknown" | "Not Specified" | "Not Provided" | "None" | "Other"
+            )
+    })
+}
+
+/// Sets string properties from fields: (property, field name)
+fn insert_values(
+    resource: &mut Map<String, Value>,
+    decoded: &DecodedStruct,
+    fields: &[(&str, &str)],
+) {
+    for (property, field) in fields.iter() {
+        if let Some(value) = known(decoded.value(field)) {
+            resource.insert(property.to_string(), json!(value.trim()));
+        }
+    }
+}
+
+/// Sets integer properties from the leading number of fields
+fn insert_numbers(
+    resource: &mut Map<String, Value>,
+    decoded: &DecodedStruct,
+    fields: &[(&str, &str)],
+) {
+    for (property, field) in fields.iter() {
+        if let Some(number) = decoded.number(field) {
+            resource.insert(property.to_string(), json!(number));
+        }
+    }
+}
+
+/// MM/DD/YYYY to the ISO 8601 date Redfish uses
+fn iso_date(date: &str) -> Option<String> {
+    let parts: Vec<&str> = date.split('/').collect();
+    match parts[..] {
+        [month, day, year] if month.len() == 2 && day.len() == 2 && year.len() == 4 => {
+            Some(format!("{}-{}-{}T00:00:00Z", year, month, day))
+        }
+        _ => None,
+    }
+}
+
+/// Memory.MemoryDeviceType of a dmidecode memory type
+fn memory_device_type(memory_type: &str) -> Option<&'static str> {
+    Some(match memory_type {
+        "DDR" => "DDR",
+        "DDR2" => "DDR2",
+        "DDR3" => "DDR3",
+        "DDR4" => "DDR4",
+        "DDR5" => "DDR5",
+        "LPDDR3" => "LPDDR3_SDRAM",
+        "LPDDR4" => "LPDDR4_SDRAM",
+        "LPDDR5" => "LPDDR5_SDRAM",
+        "SDRAM" => "SDRAM",
+        "ROM" => "ROM",
+        "EDO" => "EDO",
+        "HBM" => "HBM",
+        "HBM2" => "HBM2",
+        "HBM3" => "HBM3",
+        _ => return None,
+    })
+}
+
+/// Memory.BaseModuleType of a memory device's form factor and type detail
+fn base_module_type(device: &DecodedStruct) -> Option<&'static str> {
+    let detail = device.value("Type Detail").unwrap_or_default();
+    match device.value("Form Factor")? {
+        "SODIMM" => Some("SO_DIMM"),
+        "DIMM" if detail.contains("LRDIMM") => Some("LRDIMM"),
+        "DIMM" if detail.contains("Registered (Buffered)") => Some("RDIMM"),
+        "DIMM" if detail.contains("Unbuffered (Unregistered)") => Some("UDIMM"),
+        "Die" => Some("Die"),
+        _ => None,
+    }
+}
+
+/// Chassis.ChassisType of a dmidecode chassis type
+fn chassis_type_of(chassis_type: &str) -> &'static str {
+    match chassis_type {
+        "Rack Mount Chassis" => "RackMount",
+        "Blade" => "Blade",
+        "Blade Enclosure" | "Multi-system" => "Enclosure",
+        "Expansion Chassis" | "Bus Expansion Chassis" => "Expansion",
+        "Sub Chassis" | "Peripheral Chassis" | "RAID Chassis" => "Module",
+        "Other" | "Unknown" => "Other",
+        _ => "StandAlone",
+    }
+}
+
+/// A PCIeSlots slot from a System Slots structure, for PCI Express slots
+fn pcie_slot(slot: &DecodedStruct) -> Option<Value> {
+    let slot_type = slot.value("Type")?;
+    let generation = slot_type.split("PCI Express").nth(1)?;
+    let mut properties = Map::new();
+
+    // Type: "x16 PCI Express 3 x16", "x4 PCI Express 4 M.2 Socket 3"
+    let pcie_type = match generation.split_whitespace().next() {
+        Some(number) if number.chars().all(|c| c.is_ascii_digit()) => format!("Gen{}", number),
+        _ => "Gen1".to_string(),
+    };
+    properties.insert("PCIeType".into(), json!(pcie_type));
+    if let Some(lanes) = slot_type
+        .split_whitespace()
+        .next()
+        .and_then(|width| width.strip_prefix('x'))
+        .and_then(|lanes| lanes.parse::<u32>().ok())
+    {
+        properties.insert("Lanes".into(), json!(lanes));
+    }
+    let form = if slot_type.contains("M.2") {
+        Some("M2")
+    } else if slot_type.contains("U.2") {
+        Some("U2")
+    } else if slot_type.contains("Mini") {
+        Some("Mini")
+    } else {
+        match slot.value("Length") {
+            Some("Long") => Some("FullLength"),
+            Some("Short") => Some("HalfLength"),
+            _ => None,
+        }
+    };
+    if let Some(form) = form {
+        properties.insert("SlotType".into(), json!(form));
+    }
+    let hot_plug = slot.field("Characteristics").is_some_and(|field| {
+        field
+            .items
+            .iter()
+            .any(|item| item == "Hot-plug devices are supported")
+    });
+    properties.insert("HotPluggable".into(), json!(hot_plug));
+    let mut location = Map::new();
+    if let Some(designation) = known(slot.value("Designation")) {
+        location.insert("ServiceLabel".into(), json!(designation));
+    }
+    location.insert("LocationType".into(), json!("Slot"));
+    if let Some(id) = slot.number("ID") {
+        location.insert("LocationOrdinalValue".into(), json!(id));
+    }
+    properties.insert("Location".into(), json!({ "PartLocation": location }));
+    properties.insert(
+        "Status".into(),
+        json!({ "State": match slot.value("Current Usage") {
+            Some("Available") => "Absent",
+            _ => "Enabled",
+        }}),
+    );
+    Some(Value::Object(properties))
+}
+
+#[test]
+fn test_redfish_resources() {
+    let text = "Handle 0x0000, DMI type 0, 24 bytes
+BIOS Information
+\tVendor: ACME Corp.
+\tVersion: V1.50
+\tRelease Date: 12/07/2018
+\tCharacteristics:
+\t\tBIOS is upgradeable
+
+Handle 0x0001, DMI type 1, 27 bytes
+System Information
+\tManufacturer: ACME Corp.
+\tProduct Name: Road Runner
+\tSerial Number: ABC-1234
+\tUUID: 33221100-5544-7766-8899-aabbccddeeff
+
+Handle 0x0008, DMI type 17, 40 bytes
+Memory Device
+\tTotal Width: 72 bits
+\tData Width: 64 bits
+\tSize: 16 GB
+\tForm Factor: DIMM
+\tLocator: DIMM 0
+\tBank Locator: P0 CHANNEL A
+\tType: DDR4
+\tType Detail: Synchronous Registered (Buffered)
+\tSpeed: 3200 MT/s
+\tRank: 2
+\tConfigured Memory Speed: 2933 MT/s
+
+Handle 0x0009, DMI type 17, 40 bytes
+Memory Device
+\tSize: No Module Installed
+\tLocator: DIMM 1
+
+Handle 0x0010, DMI type 9, 17 bytes
+System Slot Information
+\tDesignation: PCIE1
+\tType: x16 PCI Express 4 x16
+\tCurrent Usage: Available
+\tLength: Long
+\tID: 1
+";
+    let structs = crate::decoded::parse_text(text);
+    let resources = redfish_resources(&structs);
+
+    let system = &resources["/redfish/v1/Systems/1"];
+    assert_eq!(system["Model"], "Road Runner");
+    assert_eq!(system["UUID"], "33221100-5544-7766-8899-aabbccddeeff");
+    assert_eq!(system["BiosVersion"], "V1.50");
+    assert_eq!(system["MemorySummary"]["TotalSystemMemoryGiB"], 16.0);
+
+    let inventory = &resources["/redfish/v1/UpdateService/FirmwareInventory/BIOS"];
+    assert_eq!(inventory["ReleaseDate"], "2018-12-07T00:00:00Z");
+    assert_eq!(inventory["Updateable"], true);
+
+    let memory = &resources["/redfish/v1/Systems/1/Memory"];
+    assert_eq!(memory["Members@odata.count"], 2);
+    let dimm = &resources["/redfish/v1/Systems/1/Memory/DIMM0"];
+    assert_eq!(dimm["CapacityMiB"], 16384);
+    assert_eq!(dimm["OperatingSpeedMhz"], 2933);
+    assert_eq!(dimm["AllowedSpeedsMHz"], json!([3200]));
+    assert_eq!(dimm["MemoryDeviceType"], "DDR4");
+    assert_eq!(dimm["BaseModuleType"], "RDIMM");
+    assert_eq!(dimm["BusWidthBits"], 72);
+    assert_eq!(dimm["DeviceLocator"], "P0 CHANNEL A DIMM 0");
+    let empty = &resources["/redfish/v1/Systems/1/Memory/DIMM1"];
+    assert_eq!(empty["Status"]["State"], "Absent");
+    assert!(empty.get("CapacityMiB").is_none());
+
+    let slots = &resources["/redfish/v1/Chassis/1/PCIeSlots"]["Slots"];
+    assert_eq!(slots[0]["PCIeType"], "Gen4");
+    assert_eq!(slots[0]["Lanes"], 16);
+    assert_eq!(slots[0]["SlotType"], "FullLength");
+    assert_eq!(
+        slots[0]["Location"]["PartLocation"]["ServiceLabel"],
+        "PCIE1"
+    );
+}
@@ -0,0 +1,365 @@
+//! Local HTTP inventory endpoint
+//!
+//! The table is loaded and decoded once, then served read-only over HTTP/1.0
+//! on a TCP address or a Unix socket, so that local processes can query
+//! hardware identity without root privileges or repeated firmware reads.
+//!
+//! | Path                   | Content                                         |
+//! |------------------------|-------------------------------------------------|
+//! | `/summary`             | every `-s` keyword and its value (JSON)         |
+//! | `/structures`          | all decoded structures (JSON)                   |
+//! | `/structures/{handle}` | one decoded structure (JSON)                    |
+//! | `/types/{type}`        | structures of a `-t` type or keyword (JSON)     |
+//! | `/keywords/{keyword}`  | the value of a `-s` keyword (text)              |
+//! | `/metrics`             | the `--format prometheus` metrics (text)        |
+
+use crate::{
+    decoded::{decode_table, DecodedStruct},
+    dmiopt::{BiosType, Keyword},
+    prometheus::write_metrics,
+};
+use enum_iterator::all;
+use smbioslib::*;
+use std::{
+    io::{BufRead, BufReader, Error, Read, Write},
+    net::{TcpListener, TcpStream},
+    str::FromStr,
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+/// How long a client may take to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Most bytes read of a request line and headers; the rest is ignored
+const MAX_REQUEST_LENGTH: u64 = 8192;
+
+/// Pause after a failed accept, so that running out of file descriptors
+/// does not spin
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+const JSON: &str = "application/json";
+const TEXT: &str = "text/plain; charset=utf-8";
+const METRICS: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// A response: status code, content type and body
+#[derive(Debug, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    fn ok(content_type: &'static str, body: String) -> Self {
+        Response {
+            status: 200,
+            content_type,
+            body,
+        }
+    }
+
+    fn json<T: serde::Serialize + ?Sized>(value: &T) -> Self {
+        Response::ok(JSON, serde_json::to_string_pretty(value).unwrap())
+    }
+
+    fn error(status: u16, message: String) -> Self {
+        Response {
+            status,
+            content_type: TEXT,
+            body: format!("{}\n", message),
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+/// The table and everything derived from it, computed once
+pub struct Inventory {
+    data: SMBiosData,
+    structs: Vec<DecodedStruct>,
+    metrics: String,
+}
+
+impl Inventory {
+    /// Decodes `data` for serving
+    pub fn new(data: SMBiosData) -> Self {
+        let structs = decode_table(&data);
+        let metrics = write_metrics(&data);
+        Inventory {
+            data,
+            structs,
+            metrics,
+        }
+    }
+
+    /// Answers a request
+    pub fn respond(&self, method: &str, path: &str) -> Response {
+        if method != "GET" && method != "HEAD" {
+            return Response::error(405, format!("Method {} not allowed", method));
+        }
+        let path = path.split('?').next().unwrap_or_default();
+        let segments: Vec<&str> = path
+            .trim_matches('/')
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        match segments[..] {
+            ["summary"] => Response::json(&self.summary()),
+            ["structures"] => Response::json(&self.structs),
+            ["structures", handle] => match parse_handle(handle) {
+                Some(handle) => match self.structs.iter().find(|s| s.handle == handle) {
+                    Some(decoded) => Response::json(decoded),
+                    None => Response::error(404, format!("Handle not found: {}", handle)),
+                },
+                None => Response::error(400, format!("Invalid handle {}", handle)),
+            },
+            ["types", bios_type] => match BiosType::from_str(bios_type) {
+                Ok(bios_type) => {
+                    let types: Vec<u8> = bios_type.into_iter().collect();
+                    let structs: Vec<&DecodedStruct> = self
+                        .structs
+                        .iter()
+                        .filter(|s| types.contains(&s.struct_type))
+                        .collect();
+                    Response::json(&structs)
+                }
+                Err(_) => Response::error(400, format!("Invalid type {}", bios_type)),
+            },
+            ["keywords", keyword] => match Keyword::from_str(keyword) {
+                Ok(keyword) => match keyword.parse(&self.data) {
+                    Ok(value) => Response::ok(TEXT, format!("{}\n", value)),
+                    Err(error) => Response::error(404, error.to_string()),
+                },
+                Err(_) => Response::error(404, format!("Unknown keyword {}", keyword)),
+            },
+            ["metrics"] => Response::ok(METRICS, self.metrics.clone()),
+            _ => Response::error(404, format!("Not found: {}", path)),
+        }
+    }
+
+    /// Every keyword that has a value
+    fn summary(&self) -> serde_json::Map<String, serde_json::Value> {
+        all::<Keyword>()
+            .filter_map(|keyword| {
+                let value = keyword.parse(&self.data).ok()?;
+                Some((keyword.to_string(), serde_json::Value::String(value)))
+            })
+            .collect()
+    }
+}
+
+/// Parses a handle, decimal or hexadecimal with a 0x prefix
+fn parse_handle(text: &str) -> Option<u16> {
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+/// Reads one request and writes its response
+fn handle_connection<S: Read + Write>(stream: S, inventory: &Inventory) -> Result<(), Error> {
+    let mut reader = BufReader::new(stream).take(MAX_REQUEST_LENGTH);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip the headers, nothing in them changes the response
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim_end().is_empty() {
+            break;
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let response = match (parts.next(), parts.next()) {
+        _ if !request_line.ends_with('\n') => Response::error(400, "Request too long".to_string()),
+        (Some(method), Some(path)) => inventory.respond(method, path),
+        _ => Response::error(400, "Malformed request".to_string()),
+    };
+    let mut stream = reader.into_inner().into_inner();
+    write!(
+        stream,
+        "HTTP/1.0 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.reason(),
+        response.content_type,
+        response.body.len()
+    )?;
+    if !request_line.starts_with("HEAD ") {
+        stream.write_all(response.body.as_bytes())?;
+    }
+    stream.flush()
+}
+
+/// Serves `inventory` on `listen`: a TCP address (127.0.0.1:8080) or, on
+/// Unix, the path of a socket to create (anything containing a '/')
+pub fn serve(inventory: Inventory, listen: &str) -> Result<(), Error> {
+    let inventory = Arc::new(inventory);
+    if listen.contains('/') {
+        return serve_unix(inventory, listen);
+    }
+    let listener = TcpListener::bind(listen)?;
+    eprintln!("Listening on http://{}", listener.local_addr()?);
+    serve_connections(listener.incoming(), inventory, TcpStream::set_read_timeout);
+    Ok(())
+}
+
+/// Answers each connection of `incoming` on its own thread
+///
+/// The server runs until it is stopped: a failed accept (a client that
+/// hung up, no file descriptor left) is logged and the next one is waited
+/// for.
+fn serve_connections<S, I>(
+    incoming: I,
+    inventory: Arc<Inventory>,
+    set_read_timeout: fn(&S, Option<Duration>) -> Result<(), Error>,
+) where
+    S: Read + Write + Send + 'static,
+    I: Iterator<Item = Result<S, Error>>,
+{
+    for stream in incoming {
+        let stream = match stream.and_then(|stream| {
+            set_read_timeout(&stream, Some(READ_TIMEOUT))?;
+            Ok(stream)
+        }) {
+            Ok(stream) => stream,
+            Err(error) => {
+                eprintln!("Failed to accept a connection: {}", error);
+                thread::sleep(ACCEPT_BACKOFF);
+                continue;
+            }
+        };
+        let inventory = Arc::clone(&inventory);
+        thread::spawn(move || handle_connection(stream, &inventory));
+    }
+}
+
+/// Serves on a Unix socket at `path`
+///
+/// A socket file left behind by a server that has stopped is removed; one
+/// that a running server answers on is left alone, and binding fails.
+#[cfg(unix)]
+fn serve_unix(inventory: Arc<Inventory>, path: &str) -> Result<(), Error> {
+    use std::os::unix::{
+        fs::FileTypeExt,
+        net::{UnixListener, UnixStream},
+    };
+
+    let is_socket = std::fs::symlink_metadata(path)
+        .map(|metadata| metadata.file_type().is_socket())
+        .unwrap_or(false);
+    if is_socket && UnixStream::connect(path).is_err() {
+        std::fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    eprintln!("Listening on {}", path);
+    serve_connections(listener.incoming(), inventory, UnixStream::set_read_timeout);
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn serve_unix(_inventory: Arc<Inventory>, path: &str) -> Result<(), Error> {
+    Err(Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!("Unix sockets are not supported here: {}", path),
+    ))
+}
+
+#[test]
+fn test_respond() {
+    let mut table = vec![
+        // BIOS Information
+        0x00, 0x18, 0x00, 0x00, 0x01, 0x02, 0x00, 0xF0, 0x03, 0x03, 0x90, 0xDA, 0xCB, 0x7F, 0x00,
+        0x00, 0x00, 0x00, 0x34, 0x01, 0x01, 0x32, 0xFF, 0xFF,
+    ];
+    table.extend_from_slice(b"ACME Corp.\0V1.50\x0012/07/2018\0\0");
+    table.extend_from_slice(&[0x7F, 0x04, 0x10, 0x00, 0x00, 0x00]);
+    let inventory = Inventory::new(SMBiosData::from_vec_and_version(table, None));
+
+    let response = inventory.respond("GET", "/keywords/bios-version");
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, "V1.50\n");
+    assert_eq!(
+        inventory.respond("GET", "/keywords/system-uuid").status,
+        404
+    );
+    assert_eq!(inventory.respond("GET", "/keywords/bogus").status, 404);
+
+    let summary = inventory.respond("GET", "/summary");
+    assert!(summary.body.contains("\"bios-vendor\": \"ACME Corp.\""));
+
+    let structure = inventory.respond("GET", "/structures/0x0010");
+    assert_eq!(structure.status, 200);
+    assert!(structure.body.contains("\"type\": 127"));
+    assert_eq!(inventory.respond("GET", "/structures/16"), structure);
+    assert_eq!(inventory.respond("GET", "/structures/0x11").status, 404);
+    assert_eq!(inventory.respond("GET", "/structures/x").status, 400);
+
+    let bios = inventory.respond("GET", "/types/bios");
+    assert!(bios.body.contains("\"name\": \"BIOS Information\""));
+    assert_eq!(inventory.respond("GET", "/types/0"), bios);
+
+    assert!(inventory
+        .respond("GET", "/metrics")
+        .body
+        .contains("dmi_bios_info{vendor=\"ACME Corp.\""));
+    assert_eq!(inventory.respond("POST", "/summary").status, 405);
+    assert_eq!(inventory.respond("GET", "/nothing").status, 404);
+}
+
+#[test]
+fn test_handle_connection() {
+    /// A connection reading `input` and writing into `output`
+    struct Connection {
+        input: std::io::Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+    impl Read for Connection {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+            self.input.read(buf)
+        }
+    }
+    impl Write for Connection {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+            self.output.write(buf)
+        }
+        fn flush(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+    let respond = |request: Vec<u8>| {
+        let mut connection = Connection {
+            input: std::io::Cursor::new(request),
+            output: Vec::new(),
+        };
+        let inventory = Inventory::new(SMBiosData::from_vec_and_version(
+            vec![0x7F, 0x04, 0x10, 0x00, 0x00, 0x00],
+            None,
+        ));
+        handle_connection(&mut connection, &inventory).unwrap();
+        (
+            String::from_utf8(connection.output).unwrap(),
+            connection.input.position(),
+        )
+    };
+
+    let (response, _) = respond(b"GET /structures/16 HTTP/1.0\r\nHost: x\r\n\r\n".to_vec());
+    assert!(response.starts_with("HTTP/1.0 200 OK\r\n"));
+    assert!(response.contains("\"type\": 127"));
+
+    // A request line without end is read no further than the limit
+    let mut request = b"GET /".to_vec();
+    request.resize(1 << 20, b'a');
+    let (response, read) = respond(request);
+    assert!(response.starts_with("HTTP/1.0 400 Bad Request\r\n"));
+    assert!(read <= MAX_REQUEST_LENGTH + 8192);
+}
@@ -0,0 +1,244 @@
+//! Table sources other than the running system's firmware
+//!
+//! A source is a binary dump (as written by `--dump-bin`), a hex dump (as
+//! printed by `-u`), a directory laid out like `/sys/firmware/dmi/tables`
+//! (or a copy of a whole sysfs root), or the special name `live` for the
+//! running system.  Text reports (the default output) are sources of
+//! decoded structures only.
+
+use crate::{
+    decoded::{decode_table, parse_text, DecodedStruct},
+    dump::{parse_hexdump, split_dump},
+    platform,
+    recover::RawTable,
+    redact::{redact_table, redaction_key},
+    Opt,
+};
+use smbioslib::*;
+use std::{
+    fmt::Write,
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+/// Name that selects the running system as a source
+pub const LIVE_SOURCE: &str = "live";
+
+/// Load from a binary dump file
+pub fn table_load_from_file(path: &Path) -> Result<(SMBiosData, String), Error> {
+    Ok(table_read_from_file(path)?.into_data())
+}
+
+/// Reads a binary dump file, raw or in the Windows format, without decoding it
+pub fn table_read_from_file(path: &Path) -> Result<RawTable, Error> {
+    let mut header = String::new();
+
+    writeln!(
+        &mut header,
+        "Getting SMBIOS data from {}.",
+        path.to_string_lossy()
+    )
+    .unwrap();
+
+    let (table, version) = split_dump(std::fs::read(path)?);
+
+    Ok(RawTable {
+        table,
+        version,
+        announced_length: None,
+        header,
+    })
+}
+
+/// Load from a hex dump, as printed by `-u` (by this tool or by dmidecode)
+pub fn table_load_from_hexdump(path: &Path) -> Result<(SMBiosData, String), Error> {
+    Ok(table_read_from_hexdump(path)?.into_data())
+}
+
+/// Reads a hex dump without decoding it
+pub fn table_read_from_hexdump(path: &Path) -> Result<RawTable, Error> {
+    let text = std::fs::read_to_string(path)?;
+    let (table, version) = parse_hexdump(&text)?;
+
+    let mut header = String::new();
+
+    writeln!(
+        &mut header,
+        "Getting SMBIOS data from {}.",
+        path.to_string_lossy()
+    )
+    .unwrap();
+
+    if let Some(version) = version {
+        writeln!(
+            &mut header,
+            "SMBIOS {}.{}.{} present.",
+            version.major, version.minor, version.revision
+        )
+        .unwrap();
+    }
+
+    Ok(RawTable {
+        table,
+        version,
+        announced_length: None,
+        header,
+    })
+}
+
+/// Load from a text report, as printed by default (by this tool or by dmidecode)
+pub fn table_load_from_text(path: &Path) -> Result<(Vec<DecodedStruct>, String), Error> {
+    let text = std::fs::read_to_string(path)?;
+    let structs = parse_text(&text);
+    if structs.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("No structures found in {}", path.to_string_lossy()),
+        ));
+    }
+
+    let mut output = String::new();
+
+    writeln!(
+        &mut output,
+        "Getting SMBIOS data from {}.",
+        path.to_string_lossy()
+    )
+    .unwrap();
+
+    if let Some(present) = text
+        .lines()
+        .map(str::trim)
+        .find(|line| line.starts_with("SMBIOS ") && line.ends_with(" present."))
+    {
+        writeln!(&mut output, "{}", present).unwrap();
+    }
+
+    Ok((structs, output))
+}
+
+/// Load from a dump file, a hex dump, a sysfs tables directory or the live system
+pub fn table_load_from_source(source: &Path, opt: &Opt) -> Result<(SMBiosData, String), Error> {
+    if source == Path::new(LIVE_SOURCE) {
+        platform::table_load(opt)
+    } else if source.is_dir() {
+        table_load_from_sysfs_root(source)
+    } else {
+        match file_kind(source) {
+            FileKind::Binary => table_load_from_file(source),
+            FileKind::HexDump => table_load_from_hexdump(source),
+            FileKind::TextReport => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "{} is a text report, which holds decoded values only",
+                    source.to_string_lossy()
+                ),
+            )),
+        }
+    }
+}
+
+/// Load the decoded structures of any source, including text reports
+pub fn decoded_load_from_source(source: &Path, opt: &Opt) -> Result<Vec<DecodedStruct>, Error> {
+    if source != Path::new(LIVE_SOURCE)
+        && source.is_file()
+        && file_kind(source) == FileKind::TextReport
+    {
+        return Ok(table_load_from_text(source)?.0);
+    }
+    let (mut data, _) = table_load_from_source(source, opt)?;
+    if let Some(version) = opt.smbios_version {
+        data.version = Some(version);
+    }
+    if opt.redact {
+        data = redact_table(&data, &redaction_key(opt));
+    }
+    Ok(decode_table(&data))
+}
+
+/// What a source file holds, recognized by its content
+#[derive(Debug, PartialEq, Eq)]
+enum FileKind {
+    Binary,
+    HexDump,
+    TextReport,
+}
+
+/// A text file is a hex dump when the first handle line is followed by the
+/// raw data, and a text report otherwise
+fn file_kind(path: &Path) -> FileKind {
+    let text = match std::fs::read(path).map(String::from_utf8) {
+        Ok(Ok(text)) => text,
+        _ => return FileKind::Binary,
+    };
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| !line.starts_with("Handle 0x"));
+    match (lines.next(), lines.next()) {
+        (Some(_), Some("Header and Data:")) => FileKind::HexDump,
+        (Some(_), _) => FileKind::TextReport,
+        (None, _) => FileKind::Binary,
+    }
+}
+
+/// Load from a directory containing `DMI` and `smbios_entry_point`
+///
+/// `root` may be the tables directory itself, or the root of a copied sysfs
+/// (`<root>/firmware/dmi/tables`) or file system (`<root>/sys/firmware/dmi/tables`).
+pub fn table_load_from_sysfs_root(root: &Path) -> Result<(SMBiosData, String), Error> {
+    let tables = [
+        root.to_path_buf(),
+        root.join("firmware/dmi/tables"),
+        root.join("sys/firmware/dmi/tables"),
+    ]
+    .iter()
+    .find(|dir| dir.join("DMI").is_file())
+    .cloned()
+    .ok_or_else(|| {
+        Error::new(
+            ErrorKind::NotFound,
+            format!("No DMI table found under {}", root.to_string_lossy()),
+        )
+    })?;
+
+    let mut output = String::new();
+
+    writeln!(
+        &mut output,
+        "Getting SMBIOS data from {}.",
+        tables.to_string_lossy()
+    )
+    .unwrap();
+
+    let version = entry_point_version(&tables.join("smbios_entry_point"));
+    if let Some(version) = version {
+        writeln!(
+            &mut output,
+            "SMBIOS {}.{}.{} present.",
+            version.major, version.minor, version.revision
+        )
+        .unwrap();
+    }
+
+    let table_path: PathBuf = tables.join("DMI");
+    let data = std::fs::read(table_path)?;
+
+    Ok((SMBiosData::from_vec_and_version(data, version), output))
+}
+
+/// Reads the version from a 64-bit or 32-bit entry point file
+fn entry_point_version(path: &Path) -> Option<SMBiosVersion> {
+    match SMBiosEntryPoint64::try_load_from_file(path) {
+        Ok(entry_point) => Some(SMBiosVersion::new(
+            entry_point.major_version(),
+            entry_point.minor_version(),
+            entry_point.docrev(),
+        )),
+        Err(_) => SMBiosEntryPoint32::try_load_from_file(path)
+            .ok()
+            .map(|entry_point| {
+                SMBiosVersion::new(entry_point.major_version(), entry_point.minor_version(), 0)
+            }),
+    }
+}
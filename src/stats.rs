@@ -0,0 +1,387 @@
+//! Size and content statistics of a table
+//!
+//! `--stats` summarizes what a table is made of: how many structures of each
+//! type, how many bytes go to formatted areas and to strings, which
+//! structures are the largest, how many strings repeat or are blank, how the
+//! handles are numbered and how much of the size allowed by the entry point
+//! is used.  Comparing reports across firmware releases shows where a table
+//! grows.
+
+use crate::dmifn::dmi_smbios_structure_type;
+use serde::Serialize;
+use smbioslib::*;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    convert::TryFrom,
+    path::Path,
+};
+
+/// Number of structures listed as the largest
+const LARGEST_COUNT: usize = 5;
+
+/// The entry point that describes the table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EntryPoint {
+    /// "_SM3_" (64-bit) or "_SM_" (32-bit)
+    pub anchor: &'static str,
+    pub version: String,
+    /// Maximum size of the table (64-bit) or its exact length (32-bit)
+    pub maximum_table_size: u32,
+}
+
+impl EntryPoint {
+    /// Reads a 64-bit or 32-bit entry point
+    pub fn from_bytes(bytes: Vec<u8>) -> Option<Self> {
+        match SMBiosEntryPoint64::try_from(bytes.clone()) {
+            Ok(entry_point) => Some(EntryPoint {
+                anchor: "_SM3_",
+                version: format!(
+                    "{}.{}.{}",
+                    entry_point.major_version(),
+                    entry_point.minor_version(),
+                    entry_point.docrev()
+                ),
+                maximum_table_size: entry_point.structure_table_maximum_size(),
+            }),
+            Err(_) => SMBiosEntryPoint32::try_from(bytes)
+                .ok()
+                .map(|entry_point| EntryPoint {
+                    anchor: "_SM_",
+                    version: format!(
+                        "{}.{}",
+                        entry_point.major_version(),
+                        entry_point.minor_version()
+                    ),
+                    maximum_table_size: entry_point.structure_table_length() as u32,
+                }),
+        }
+    }
+
+    /// Reads the entry point from a file, as found in sysfs
+    pub fn from_file(path: &Path) -> Option<Self> {
+        std::fs::read(path).ok().and_then(EntryPoint::from_bytes)
+    }
+}
+
+/// Structures of one type
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TypeStats {
+    pub struct_type: u8,
+    pub name: String,
+    pub count: usize,
+    /// Bytes of the formatted areas
+    pub formatted_bytes: usize,
+    /// Bytes of the string sets, terminators included
+    pub string_bytes: usize,
+}
+
+/// One structure and its size
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StructSize {
+    pub handle: u16,
+    pub struct_type: u8,
+    pub name: String,
+    pub size: usize,
+}
+
+/// Strings that repeat or are blank
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct StringStats {
+    pub count: usize,
+    /// Strings equal to an earlier string anywhere in the table
+    pub duplicated: usize,
+    /// Bytes taken by those repeats
+    pub duplicated_bytes: usize,
+    /// Strings equal to an earlier string of the same structure, which
+    /// could have been referenced twice instead
+    pub duplicated_in_structure: usize,
+    /// Strings that are empty or only white space
+    pub empty: usize,
+}
+
+/// Handle numbering
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct HandleStats {
+    pub first: Option<u16>,
+    pub last: Option<u16>,
+    /// Unused handles between the first and the last
+    pub gaps: Vec<(u16, u16)>,
+    /// Handles used by more than one structure
+    pub duplicates: Vec<u16>,
+}
+
+/// The statistics of a table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TableStats {
+    pub version: Option<String>,
+    pub entry_point: Option<EntryPoint>,
+    pub structure_count: usize,
+    pub table_bytes: usize,
+    pub types: Vec<TypeStats>,
+    pub largest: Vec<StructSize>,
+    pub strings: StringStats,
+    pub handles: HandleStats,
+}
+
+/// Bytes of a string set as stored in the table: each string and its
+/// terminator, then the final terminator (two zeros for no strings)
+fn string_set_size(undefined_struct: &UndefinedStruct) -> usize {
+    match undefined_struct.strings.iter().len() {
+        0 => 2,
+        _ => {
+            undefined_struct
+                .strings
+                .iter()
+                .map(|string| string.len() + 1)
+                .sum::<usize>()
+                + 1
+        }
+    }
+}
+
+/// Ranges of unused handles between used ones
+fn handle_gaps(handles: &[u16]) -> Vec<(u16, u16)> {
+    handles
+        .windows(2)
+        .filter(|pair| pair[1] > pair[0] + 1)
+        .map(|pair| (pair[0] + 1, pair[1] - 1))
+        .collect()
+}
+
+impl TableStats {
+    pub fn new(data: &SMBiosData, entry_point: Option<EntryPoint>) -> Self {
+        let mut types: BTreeMap<u8, TypeStats> = BTreeMap::new();
+        let mut sizes = Vec::new();
+        let mut strings = StringStats::default();
+        let mut seen: HashSet<&[u8]> = HashSet::new();
+        let mut handles = Vec::new();
+
+        for undefined_struct in data.iter() {
+            let struct_type = undefined_struct.header.struct_type();
+            let name = dmi_smbios_structure_type(struct_type);
+            let formatted_bytes = undefined_struct.fields.len();
+            let string_bytes = string_set_size(undefined_struct);
+
+            let type_stats = types.entry(struct_type).or_insert_with(|| TypeStats {
+                struct_type,
+                name: name.clone(),
+                ..Default::default()
+            });
+            type_stats.count += 1;
+            type_stats.formatted_bytes += formatted_bytes;
+            type_stats.string_bytes += string_bytes;
+
+            let handle = *undefined_struct.header.handle();
+            sizes.push(StructSize {
+                handle,
+                struct_type,
+                name,
+                size: formatted_bytes + string_bytes,
+            });
+            handles.push(handle);
+
+            let mut in_structure: HashSet<&[u8]> = HashSet::new();
+            for string in undefined_struct.strings.iter() {
+                strings.count += 1;
+                if !seen.insert(string) {
+                    strings.duplicated += 1;
+                    strings.duplicated_bytes += string.len() + 1;
+                }
+                if !in_structure.insert(string) {
+                    strings.duplicated_in_structure += 1;
+                }
+                if string.iter().all(|byte| byte.is_ascii_whitespace()) {
+                    strings.empty += 1;
+                }
+            }
+        }
+
+        let structure_count = sizes.len();
+        let table_bytes = sizes.iter().map(|s| s.size).sum();
+        // Stable sort: equal sizes stay in table order
+        sizes.sort_by_key(|s| std::cmp::Reverse(s.size));
+        sizes.truncate(LARGEST_COUNT);
+
+        let mut counts: HashMap<u16, usize> = HashMap::new();
+        for handle in handles.iter() {
+            *counts.entry(*handle).or_default() += 1;
+        }
+        let mut duplicates: Vec<u16> = counts
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(handle, _)| handle)
+            .collect();
+        duplicates.sort_unstable();
+        handles.sort_unstable();
+        handles.dedup();
+
+        TableStats {
+            version: data
+                .version
+                .map(|v| format!("{}.{}.{}", v.major, v.minor, v.revision)),
+            entry_point,
+            structure_count,
+            table_bytes,
+            types: types.into_values().collect(),
+            largest: sizes,
+            strings,
+            handles: HandleStats {
+                first: handles.first().copied(),
+                last: handles.last().copied(),
+                gaps: handle_gaps(&handles),
+                duplicates,
+            },
+        }
+    }
+
+    /// Percentage of the entry point's maximum table size in use
+    pub fn maximum_size_used(&self) -> Option<f64> {
+        self.entry_point
+            .as_ref()
+            .filter(|entry_point| entry_point.maximum_table_size > 0)
+            .map(|entry_point| {
+                self.table_bytes as f64 * 100.0 / entry_point.maximum_table_size as f64
+            })
+    }
+
+    /// Text report
+    pub fn to_text(&self) -> String {
+        let mut lines = Vec::new();
+        lines.push(format!(
+            "SMBIOS Version: {}",
+            self.version.as_deref().unwrap_or("Unknown")
+        ));
+        match self.entry_point.as_ref() {
+            Some(entry_point) => {
+                lines.push(format!(
+                    "Entry Point: {} (SMBIOS {})",
+                    entry_point.anchor, entry_point.version
+                ));
+                lines.push(format!(
+                    "Table Size: {} bytes of {} ({:.1}%)",
+                    self.table_bytes,
+                    entry_point.maximum_table_size,
+                    self.maximum_size_used().unwrap_or(0.0)
+                ));
+            }
+            None => {
+                lines.push("Entry Point: Not Available".to_string());
+                lines.push(format!("Table Size: {} bytes", self.table_bytes));
+            }
+        }
+        lines.push(format!("Structures: {}", self.structure_count));
+
+        lines.push(String::new());
+        lines.push("Type  Count  Formatted  Strings  Name".to_string());
+        for stats in self.types.iter() {
+            lines.push(format!(
+                "{:>4}  {:>5}  {:>9}  {:>7}  {}",
+                stats.struct_type,
+                stats.count,
+                stats.formatted_bytes,
+                stats.string_bytes,
+                stats.name
+            ));
+        }
+
+        lines.push(String::new());
+        lines.push("Largest Structures:".to_string());
+        for largest in self.largest.iter() {
+            lines.push(format!(
+                "\t{:#06X} DMI type {}, {} bytes ({})",
+                largest.handle, largest.struct_type, largest.size, largest.name
+            ));
+        }
+
+        lines.push(String::new());
+        lines.push(format!("Strings: {}", self.strings.count));
+        lines.push(format!(
+            "\tDuplicated: {} ({} bytes)",
+            self.strings.duplicated, self.strings.duplicated_bytes
+        ));
+        lines.push(format!(
+            "\tDuplicated Within A Structure: {}",
+            self.strings.duplicated_in_structure
+        ));
+        lines.push(format!("\tEmpty: {}", self.strings.empty));
+
+        lines.push(String::new());
+        match (self.handles.first, self.handles.last) {
+            (Some(first), Some(last)) => {
+                lines.push(format!("Handles: {:#06X} to {:#06X}", first, last))
+            }
+            _ => lines.push("Handles: None".to_string()),
+        }
+        match self.handles.gaps.is_empty() {
+            true => lines.push("\tGaps: None".to_string()),
+            false => {
+                lines.push(format!("\tGaps: {}", self.handles.gaps.len()));
+                for (first, last) in self.handles.gaps.iter() {
+                    match first == last {
+                        true => lines.push(format!("\t\t{:#06X}", first)),
+                        false => lines.push(format!("\t\t{:#06X} to {:#06X}", first, last)),
+                    }
+                }
+            }
+        }
+        if !self.handles.duplicates.is_empty() {
+            lines.push(format!(
+                "\tDuplicated: {}",
+                self.handles
+                    .duplicates
+                    .iter()
+                    .map(|handle| format!("{:#06X}", handle))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+#[test]
+fn test_table_stats() {
+    use crate::build::push_struct;
+
+    let mut table = Vec::new();
+    let mut push = |struct_type: u8, length: u8, handle: u8, strings: &[&str]| {
+        let mut fields = vec![struct_type, length, handle, 0];
+        fields.resize(length as usize, 0);
+        let strings: Vec<Vec<u8>> = strings.iter().map(|s| s.as_bytes().to_vec()).collect();
+        push_struct(&mut table, &fields, &strings);
+    };
+    push(1, 8, 0x00, &["Acme", "Box", "Acme"]);
+    push(2, 6, 0x01, &["Acme", " "]);
+    push(2, 6, 0x05, &[]);
+    push(127, 4, 0x08, &[]);
+    let data = SMBiosData::from_vec_and_version(table, Some(SMBiosVersion::new(3, 4, 0)));
+
+    let stats = TableStats::new(
+        &data,
+        Some(EntryPoint {
+            anchor: "_SM3_",
+            version: "3.4.0".to_string(),
+            maximum_table_size: 102,
+        }),
+    );
+    assert_eq!(stats.structure_count, 4);
+    assert_eq!(stats.table_bytes, 23 + 14 + 8 + 6);
+    assert_eq!(stats.types.len(), 3);
+    let baseboard = &stats.types[1];
+    assert_eq!(baseboard.name, "Base Board");
+    assert_eq!(baseboard.count, 2);
+    assert_eq!(baseboard.formatted_bytes, 12);
+    assert_eq!(baseboard.string_bytes, 10);
+    assert_eq!(stats.largest[0].handle, 0x0000);
+    assert_eq!(stats.largest.len(), 4);
+    assert_eq!(stats.strings.count, 5);
+    assert_eq!(stats.strings.duplicated, 2);
+    assert_eq!(stats.strings.duplicated_bytes, 10);
+    assert_eq!(stats.strings.duplicated_in_structure, 1);
+    assert_eq!(stats.strings.empty, 1);
+    assert_eq!(stats.handles.first, Some(0x0000));
+    assert_eq!(stats.handles.last, Some(0x0008));
+    assert_eq!(stats.handles.gaps, vec![(2, 4), (6, 7)]);
+    assert!(stats.handles.duplicates.is_empty());
+    assert_eq!(stats.maximum_size_used(), Some(50.0));
+}
@@ -1,16 +1,21 @@
-use crate::Opt;
-use io::{Error, ErrorKind};
+use crate::{recover::RawTable, Opt};
+use io::{Error, ErrorKind, Read, Seek, SeekFrom};
 use smbioslib::*;
 use std::{fmt::Write, path::Path};
 
-mod dmiopt;
-
 #[cfg(target_os = "linux")]
 pub fn table_load(opt: &Opt) -> Result<(SMBiosData, String), Error> {
+    Ok(table_read(opt, false)?.into_data())
+}
+
+/// Reads the table without decoding it; when `tolerant`, a table shorter
+/// than its entry point announces is returned as far as it could be read
+#[cfg(target_os = "linux")]
+pub fn table_read(opt: &Opt, tolerant: bool) -> Result<RawTable, Error> {
     if !opt.no_sysfs {
         // read from /sys/firmware/dmi/tables/DMI
-        if let Ok(smbios_data) = table_load_from_sysfs() {
-            return Ok(smbios_data);
+        if let Ok(raw_table) = table_read_from_sysfs() {
+            return Ok(raw_table);
         }
     }
 
@@ -20,27 +25,36 @@ pub fn table_load(opt: &Opt) -> Result<(SMBiosData, String), Error> {
         None => std::path::Path::new(DEV_MEM_FILE),
     };
 
-    table_load_from_dev_mem(&path)
+    table_read_from_dev_mem(path, tolerant)
+}
+
+#[cfg(target_os = "freebsd")]
+pub fn table_load(opt: &Opt) -> Result<(SMBiosData, String), Error> {
+    Ok(table_read(opt, false)?.into_data())
 }
 
+/// Reads the table without decoding it; when `tolerant`, a table shorter
+/// than its entry point announces is returned as far as it could be read
 #[cfg(target_os = "freebsd")]
-pub fn table_load(_opt: &Opt) -> Result<(SMBiosData, String), Error> {
+pub fn table_read(opt: &Opt, tolerant: bool) -> Result<RawTable, Error> {
     // FreeBSD only has /dev/mem and does not have sysfs (/sys/firmware/dmi/tables/DMI)
     let path = match &opt.dev_mem {
         Some(given_file) => given_file.as_path(),
         None => std::path::Path::new(DEV_MEM_FILE),
     };
 
-    table_load_from_dev_mem(&path)
+    table_read_from_dev_mem(&path, tolerant)
 }
 
 /// Load from /sys/firmware/dmi/tables/DMI
-fn table_load_from_sysfs() -> Result<(SMBiosData, String), Error> {
+fn table_read_from_sysfs() -> Result<RawTable, Error> {
     let mut output = String::new();
 
     writeln!(&mut output, "Getting SMBIOS data from sysfs.").unwrap();
 
     let version: SMBiosVersion;
+    // The 64-bit entry point gives a maximum size only
+    let mut announced_length = None;
     let entry_path = std::path::Path::new(SYS_ENTRY_FILE);
 
     match SMBiosEntryPoint64::try_load_from_file(entry_path) {
@@ -75,6 +89,7 @@ fn table_load_from_sysfs() -> Result<(SMBiosData, String), Error> {
                         minor: entry_point.minor_version(),
                         revision: 0,
                     };
+                    announced_length = Some(entry_point.structure_table_length() as usize);
 
                     writeln!(
                         &mut output,
@@ -105,13 +120,16 @@ fn table_load_from_sysfs() -> Result<(SMBiosData, String), Error> {
         },
     }
 
-    let smbios_data = SMBiosData::try_load_from_file(SYS_TABLE_FILE, Some(version))?;
-
-    Ok((smbios_data, output))
+    Ok(RawTable {
+        table: fs::read(SYS_TABLE_FILE)?,
+        version: Some(version),
+        announced_length,
+        header: output,
+    })
 }
 
 /// Load from /dev/mem
-fn table_load_from_dev_mem(path: &Path) -> Result<(SMBiosData, String), Error> {
+fn table_read_from_dev_mem(path: &Path, tolerant: bool) -> Result<RawTable, Error> {
     const RANGE_START: u64 = 0x000F0000u64;
     const RANGE_END: u64 = 0x000FFFFFu64;
     let mut dev_mem = fs::File::open(path)?;
@@ -194,11 +212,26 @@ fn table_load_from_dev_mem(path: &Path) -> Result<(SMBiosData, String), Error> {
         ));
     }
 
-    let table = UndefinedStructTable::try_load_from_file_offset(
-        &mut dev_mem,
-        structure_table_address,
-        structure_table_length as usize,
-    )?;
+    let mut table = Vec::with_capacity(structure_table_length as usize);
+    dev_mem.seek(SeekFrom::Start(structure_table_address))?;
+    (&mut dev_mem)
+        .take(structure_table_length as u64)
+        .read_to_end(&mut table)?;
+    if table.len() < structure_table_length as usize && !tolerant {
+        return Err(Error::new(
+            ErrorKind::UnexpectedEof,
+            format!(
+                "The table ends after {} of the {} bytes given by the entry point",
+                table.len(),
+                structure_table_length
+            ),
+        ));
+    }
 
-    Ok((SMBiosData::new(table, Some(version)), output))
+    Ok(RawTable {
+        table,
+        version: Some(version),
+        announced_length: Some(structure_table_length as usize),
+        header: output,
+    })
 }
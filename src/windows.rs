@@ -1,9 +1,7 @@
-use crate::Opt;
+use crate::{recover::RawTable, Opt};
 use smbioslib::*;
 use std::fmt::Write;
-use std::io::Error;
-
-mod dmiopt;
+use std::io::{Error, ErrorKind};
 
 pub fn table_load(_opt: &Opt) -> Result<(SMBiosData, String), Error> {
     let mut output = String::new();
@@ -18,3 +16,33 @@ pub fn table_load(_opt: &Opt) -> Result<(SMBiosData, String), Error> {
 
     Ok((smbios_table, output))
 }
+
+/// Reads the table without decoding it
+pub fn table_read(_opt: &Opt, _tolerant: bool) -> Result<RawTable, Error> {
+    let mut header = String::new();
+
+    writeln!(
+        &mut header,
+        "Getting SMBIOS data from GetSystemFirmwareTable()."
+    )
+    .unwrap();
+
+    let data = raw_smbios_from_device()?;
+    if !WinSMBiosData::is_valid_win_smbios_data(&data) {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "Invalid WinSMBiosData structure",
+        ));
+    }
+
+    Ok(RawTable {
+        table: data[WinSMBiosData::SMBIOS_TABLE_DATA_OFFSET..].to_vec(),
+        version: Some(SMBiosVersion {
+            major: data[WinSMBiosData::SMBIOS_MAJOR_VERSION_OFFSET],
+            minor: data[WinSMBiosData::SMBIOS_MINOR_VERSION_OFFSET],
+            revision: data[WinSMBiosData::DMI_REVISION_OFFSET],
+        }),
+        announced_length: None,
+        header,
+    })
+}
@@ -182,3 +182,100 @@ fn test_json_valid() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[test]
+fn test_diff_exit_status() -> Result<(), Box<dyn std::error::Error>> {
+    let corpus = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let laptop = corpus.join("laptop-3.2.bin");
+
+    // The same table: no difference
+    let mut cmd = Command::cargo_bin(CLI_COMMAND)?;
+    cmd.arg("diff").arg(&laptop).arg(&laptop);
+    cmd.assert().code(0);
+
+    // Different tables: 1, as diff(1) has it
+    let mut cmd = Command::cargo_bin(CLI_COMMAND)?;
+    cmd.arg("diff")
+        .arg(&laptop)
+        .arg(corpus.join("server-2.8.bin"));
+    cmd.assert().code(1);
+
+    // A table that cannot be loaded is a failure, not a difference
+    let mut cmd = Command::cargo_bin(CLI_COMMAND)?;
+    cmd.arg("diff").arg(&laptop).arg(corpus.join("missing.bin"));
+    cmd.assert()
+        .code(9)
+        .stderr(predicate::str::contains("No such file"));
+
+    Ok(())
+}
+
+#[test]
+fn test_from_text_json() -> Result<(), Box<dyn std::error::Error>> {
+    let corpus = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+
+    // A report has no raw bytes: its structures are output as it gives them
+    let mut cmd = Command::cargo_bin(CLI_COMMAND)?;
+    cmd.arg("--from-text")
+        .arg(corpus.join("laptop-3.2.txt"))
+        .arg("-j");
+    cmd.assert().code(0).stdout(predicate::str::starts_with(
+        r#"[{"handle":0,"type":18,"length":23,"name":"32-bit Memory Error Information","fields":["#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_query_no_match() -> Result<(), Box<dyn std::error::Error>> {
+    let corpus = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let laptop = corpus.join("laptop-3.2.bin");
+
+    // No match is the same failure whether the values are text or JSON
+    for json in [None, Some("-j")].iter() {
+        let mut cmd = Command::cargo_bin(CLI_COMMAND)?;
+        cmd.arg("--from-dump")
+            .arg(&laptop)
+            .arg("--query")
+            .arg("processor[socket-designation=CPU9].core-count")
+            .args(json);
+        cmd.assert()
+            .code(11)
+            .stdout(predicate::str::contains("[]").not())
+            .stderr(predicate::str::contains("No match for query"));
+    }
+
+    let mut cmd = Command::cargo_bin(CLI_COMMAND)?;
+    cmd.arg("--from-dump")
+        .arg(&laptop)
+        .arg("--query")
+        .arg("processor[*].core-count")
+        .arg("-j");
+    cmd.assert().code(0).stdout(predicate::str::starts_with("["));
+
+    Ok(())
+}
+
+#[test]
+fn test_batch_exit_status() -> Result<(), Box<dyn std::error::Error>> {
+    let corpus = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let dir = tempdir()?;
+    std::fs::copy(corpus.join("laptop-3.2.bin"), dir.path().join("laptop.bin"))?;
+    std::fs::write(dir.path().join("empty.bin"), b"")?;
+
+    // Some tables fail to load: the others are still output
+    let mut cmd = Command::cargo_bin(CLI_COMMAND)?;
+    cmd.arg("batch").arg(dir.path());
+    cmd.assert()
+        .code(10)
+        .stdout(predicate::str::contains("laptop.bin"))
+        .stderr(predicate::str::contains("empty.bin"));
+
+    // A directory that cannot be read is a failure of its own
+    let mut cmd = Command::cargo_bin(CLI_COMMAND)?;
+    cmd.arg("batch").arg(dir.path().join("missing"));
+    cmd.assert().code(1);
+
+    dir.close()?;
+    Ok(())
+}
@@ -0,0 +1,146 @@
+//! Golden-output tests over the dumps in tests/corpus
+//!
+//! Each `NAME.bin` is decoded with `--from-dump`, plus the arguments listed
+//! in `NAME.args` (the SMBIOS version of the table, as dumps have no entry
+//! point), and the text and JSON outputs are compared with `NAME.txt` and
+//! `NAME.json`.  After an intended change of the output,
+//! write the expected files again and review their diff:
+//!
+//! ```text
+//! BLESS=1 cargo test --test golden
+//! ```
+
+use assert_cmd::prelude::*;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+static CLI_COMMAND: &str = "dmidecode";
+
+/// Set to write the expected outputs instead of comparing with them
+static BLESS_VARIABLE: &str = "BLESS";
+
+/// Stand-in for the version in the "# dmidecode-rs X.Y.Z" line
+static VERSION_PLACEHOLDER: &str = "VERSION";
+
+fn corpus_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus")
+}
+
+/// The dumps of the corpus, by file name
+fn corpus_dumps() -> Vec<String> {
+    let mut dumps: Vec<String> = fs::read_dir(corpus_dir())
+        .unwrap()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.ends_with(".bin"))
+        .collect();
+    dumps.sort();
+    dumps
+}
+
+/// Decodes `dump` with `extra` arguments; the dump is given relative to the
+/// corpus so that the "Getting SMBIOS data from" line does not depend on the
+/// checkout
+fn decode(dump: &str, extra: &[&str]) -> Result<String, String> {
+    let stem = dump.trim_end_matches(".bin");
+    let args = fs::read_to_string(corpus_dir().join(format!("{}.args", stem))).unwrap_or_default();
+    let output = Command::cargo_bin(CLI_COMMAND)
+        .unwrap()
+        .current_dir(corpus_dir())
+        .arg("--from-dump")
+        .arg(dump)
+        .args(args.split_whitespace())
+        .args(extra)
+        .output()
+        .unwrap();
+    match output.status.success() {
+        true => Ok(String::from_utf8_lossy(&output.stdout).replace(
+            &format!("# dmidecode-rs {}", env!("CARGO_PKG_VERSION")),
+            &format!("# dmidecode-rs {}", VERSION_PLACEHOLDER),
+        )),
+        false => Err(format!(
+            "{} failed: {}",
+            dump,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+    }
+}
+
+/// The first line where `actual` differs from `expected`
+fn first_difference(expected: &str, actual: &str) -> String {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut number = 1;
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(e), Some(a)) if e == a => number += 1,
+            (e, a) => {
+                return format!(
+                    "line {}:\n  expected: {}\n  actual:   {}",
+                    number,
+                    e.unwrap_or("<end of file>"),
+                    a.unwrap_or("<end of file>")
+                )
+            }
+        }
+    }
+}
+
+/// Compares one output of each dump with the expected file of `extension`
+fn check_outputs(extension: &str, extra: &[&str]) {
+    let bless = std::env::var_os(BLESS_VARIABLE).is_some();
+    let mut failures = Vec::new();
+
+    for dump in corpus_dumps() {
+        let expected_path = corpus_dir().join(dump.replace(".bin", extension));
+        let actual = match decode(&dump, extra) {
+            Ok(actual) => actual,
+            Err(error) => {
+                failures.push(error);
+                continue;
+            }
+        };
+        if bless {
+            fs::write(&expected_path, &actual).unwrap();
+            continue;
+        }
+        match fs::read_to_string(&expected_path) {
+            Ok(expected) if expected == actual => (),
+            Ok(expected) => failures.push(format!(
+                "{} differs at {}",
+                expected_path.display(),
+                first_difference(&expected, &actual)
+            )),
+            Err(_) => failures.push(format!(
+                "{} is missing, run with {}=1 to write it",
+                expected_path.display(),
+                BLESS_VARIABLE
+            )),
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{}\n\nIf the change is intended, run with {}=1 and review the diff",
+        failures.join("\n"),
+        BLESS_VARIABLE
+    );
+}
+
+#[test]
+fn test_corpus_is_not_empty() {
+    assert!(!corpus_dumps().is_empty());
+}
+
+#[test]
+fn test_golden_text() {
+    check_outputs(".txt", &[]);
+}
+
+#[test]
+fn test_golden_json() {
+    check_outputs(".json", &["--json-pretty"]);
+}